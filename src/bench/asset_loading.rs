@@ -0,0 +1,84 @@
+// Compares loading every asset lib from the RON files in `asset/def` with
+// loading them from a compiled bundle. Run with `cargo bench`.
+use game_mechanic::prelude::*;
+use game_system::prelude::{AssetBundle, AssetLib, BundleError};
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use term_screen::database::bundle_asset_libs;
+use term_screen::project::Project;
+
+const RUNS: u32 = 200;
+
+// Loads all of the libs once. Each is passed to `black_box` so loading
+// isn't optimized away.
+fn load_ron() {
+    let project = Project::default();
+    let path = |name| project.lib_path(name);
+    black_box(AbilityLib::new(&path("ability")));
+    black_box(AuraLib::new(&path("aura")));
+    black_box(CharacterLib::new(&path("character")));
+    black_box(ItemLib::new(&path("item")));
+    black_box(LootTableLib::new(&path("loot_table")));
+    black_box(RecipeLib::new(&path("recipe")));
+    black_box(VendorLib::new(&path("vendor")));
+}
+
+fn load_bundle(bytes: &[u8]) -> Result<(), BundleError> {
+    let bundle = AssetBundle::read(bytes)?;
+    black_box(AbilityLib::from_bundle(&bundle, "ability")?);
+    black_box(AuraLib::from_bundle(&bundle, "aura")?);
+    black_box(CharacterLib::from_bundle(&bundle, "character")?);
+    black_box(ItemLib::from_bundle(&bundle, "item")?);
+    black_box(LootTableLib::from_bundle(&bundle, "loot_table")?);
+    black_box(RecipeLib::from_bundle(&bundle, "recipe")?);
+    black_box(VendorLib::from_bundle(&bundle, "vendor")?);
+    Ok(())
+}
+
+// The average time `load` takes over `RUNS` runs.
+fn time(mut load: impl FnMut()) -> Duration {
+    load();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        load();
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    let bytes = bundle_asset_libs(&Project::default())
+        .and_then(|bundle| bundle.to_bytes())
+        .unwrap();
+    let ron_size: u64 = [
+        "ability",
+        "aura",
+        "character",
+        "item",
+        "loot_table",
+        "recipe",
+        "vendor",
+    ]
+    .iter()
+    .map(|name| {
+        fs::metadata(Project::default().lib_path(name))
+            .unwrap()
+            .len()
+    })
+    .sum();
+
+    // RON is read from disk each time, so the bundle is too.
+    let bundle_path = std::env::temp_dir().join(format!("assets_{}.bundle", std::process::id()));
+    fs::write(&bundle_path, &bytes).unwrap();
+    let ron = time(load_ron);
+    let bundle = time(|| load_bundle(&fs::read(&bundle_path).unwrap()).unwrap());
+    let _ = fs::remove_file(&bundle_path);
+
+    println!("{:<8} {:>10} {:>12}", "", "Size", "Load");
+    println!("{:<8} {:>8} B {:>12?}", "RON", ron_size, ron);
+    println!("{:<8} {:>8} B {:>12?}", "Bundle", bytes.len(), bundle);
+    println!(
+        "The bundle loads {:.1}x faster",
+        ron.as_secs_f64() / bundle.as_secs_f64()
+    );
+}
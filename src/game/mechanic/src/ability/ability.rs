@@ -0,0 +1,234 @@
+use crate::aura::aura::AuraLib;
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::Reflect;
+use game_system::asset::asset_lib::{AssetDef, AssetType, DefLib, LibDef};
+use game_system::asset::attribute::{MarkupText, MultiLine};
+use game_system::asset::identity::AssetReference;
+use game_system::asset::migration::Migration;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Reflect)]
+pub enum ResourceType {
+    None,
+    Mana,
+    Energy,
+    Rage,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Reflect)]
+pub enum TargetingMode {
+    // The caster themselves.
+    Caster,
+    Enemy,
+    Ally,
+    // A point on the ground.
+    Ground,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum EffectType {
+    #[default]
+    Damage,
+    Heal,
+    ApplyAura,
+}
+
+pub type AbilityLib = DefLib<AbilityDef>;
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+pub struct AbilityDef {
+    pub id: u32,
+    #[serde(default)]
+    pub guid: String,
+    pub name: String,
+    pub icon: String,
+    #[reflect(@MultiLine, @MarkupText)]
+    pub rules_text: String,
+    pub resource_type: ResourceType,
+    pub resource_cost: u32,
+    // In seconds.
+    pub cooldown: f32,
+    // In seconds, 0 for instant abilities.
+    pub cast_time: f32,
+    pub range: f32,
+    pub targeting: TargetingMode,
+    #[serde(default)]
+    pub effects: Vec<AbilityEffect>,
+}
+
+// Something that happens to the target when an ability lands. `aura_id` is
+// the `AuraDef` applied by `ApplyAura` effects, and is unused otherwise.
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize, Reflect)]
+#[reflect(Default)]
+pub struct AbilityEffect {
+    pub effect_type: EffectType,
+    pub amount: u32,
+    #[serde(default)]
+    pub aura_id: u32,
+}
+
+#[derive(Debug)]
+pub struct Ability {
+    pub def: Arc<AbilityDef>,
+}
+
+impl FromStr for ResourceType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ResourceType, Self::Err> {
+        match input {
+            "None" => Ok(ResourceType::None),
+            "Mana" => Ok(ResourceType::Mana),
+            "Energy" => Ok(ResourceType::Energy),
+            "Rage" => Ok(ResourceType::Rage),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for TargetingMode {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<TargetingMode, Self::Err> {
+        match input {
+            "Caster" => Ok(TargetingMode::Caster),
+            "Enemy" => Ok(TargetingMode::Enemy),
+            "Ally" => Ok(TargetingMode::Ally),
+            "Ground" => Ok(TargetingMode::Ground),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for TargetingMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for EffectType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<EffectType, Self::Err> {
+        match input {
+            "Damage" => Ok(EffectType::Damage),
+            "Heal" => Ok(EffectType::Heal),
+            "ApplyAura" => Ok(EffectType::ApplyAura),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for EffectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl AssetDef for AbilityDef {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon(&self) -> &str {
+        &self.icon
+    }
+}
+
+impl LibDef for AbilityDef {
+    const ASSET_TYPE: AssetType = AssetType::Ability;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[AssetReference {
+        path: "effects[].aura_id",
+        to: AssetType::Aura,
+        when: Some(("effect_type", "ApplyAura")),
+    }];
+}
+
+impl AbilityDef {
+    // The ids of auras applied by this ability that aren't in `aura_lib`.
+    pub fn missing_auras(&self, aura_lib: &AuraLib) -> Vec<u32> {
+        self.effects
+            .iter()
+            .filter(|effect| {
+                effect.effect_type == EffectType::ApplyAura && !aura_lib.contains_id(effect.aura_id)
+            })
+            .map(|effect| effect.aura_id)
+            .collect()
+    }
+}
+
+impl Ability {
+    pub fn new(def: &Arc<AbilityDef>) -> Ability {
+        Ability { def: def.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::MECHANIC_TEST_DIR;
+    use game_system::asset::asset_lib::AssetLib;
+
+    #[test]
+    fn abilitylib_load_and_access() {
+        let ability_lib =
+            AbilityLib::new(&format!("{}/test/data/test_ability.ron", MECHANIC_TEST_DIR));
+        let expected_ability_def = AbilityDef {
+            id: 0,
+            guid: String::new(),
+            name: "Zap".to_string(),
+            icon: "sprite/icon/lightning.png".to_string(),
+            rules_text: "Zaps the target, leaving them {aura:Shocked}.".to_string(),
+            resource_type: ResourceType::Mana,
+            resource_cost: 20,
+            cooldown: 6.0,
+            cast_time: 1.5,
+            range: 30.0,
+            targeting: TargetingMode::Enemy,
+            effects: vec![
+                AbilityEffect {
+                    effect_type: EffectType::Damage,
+                    amount: 40,
+                    aura_id: 0,
+                },
+                AbilityEffect {
+                    effect_type: EffectType::ApplyAura,
+                    amount: 0,
+                    aura_id: 1,
+                },
+            ],
+        };
+        assert_eq!(*ability_lib.id(0), expected_ability_def);
+        assert_eq!(ability_lib.name("Mend".to_string()).name, "Mend");
+    }
+
+    #[test]
+    fn ability_missing_auras() {
+        let ability_lib =
+            AbilityLib::new(&format!("{}/test/data/test_ability.ron", MECHANIC_TEST_DIR));
+        let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
+        assert!(ability_lib.id(0).missing_auras(&aura_lib).is_empty());
+        assert_eq!(ability_lib.id(1).missing_auras(&aura_lib), vec![7]);
+    }
+}
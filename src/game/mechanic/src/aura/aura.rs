@@ -1,11 +1,11 @@
+use crate::stat::stat::StatModifier;
 use bevy_reflect::Reflect;
-use game_system::asset::asset_lib::AssetLib;
-use ron;
+use game_system::asset::asset_lib::{AssetDef, AssetType, DefLib, LibDef};
+use game_system::asset::attribute::{MarkupText, MultiLine};
+use game_system::asset::identity::AssetReference;
+use game_system::asset::migration::Migration;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fmt;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -17,33 +17,110 @@ pub enum AuraType {
     None,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct AuraRon {
-    next_id: u32,
-    defs: Vec<AuraDef>,
+// What happens when an aura is applied to a target that already has it.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum StackingRule {
+    // The existing aura's duration is reset.
+    #[default]
+    Refresh,
+    // A stack is added, up to `max_stacks`, and the duration is reset.
+    Stack,
+    // Each source has its own copy of the aura.
+    UniquePerSource,
+    // The aura is replaced if the new one is at least as strong.
+    ReplaceWeaker,
 }
 
-#[derive(Debug, Default)]
-pub struct AuraLib {
-    next_id: u32,
-    name_map: HashMap<String, usize>,
-    id_map: HashMap<u32, usize>,
-    pub defs: Vec<Arc<AuraDef>>,
+// What an aura does each `tick_interval` seconds.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum TickEffect {
+    #[default]
+    None,
+    Damage,
+    Heal,
 }
 
+pub type AuraLib = DefLib<AuraDef>;
+
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
 pub struct AuraDef {
     pub id: u32,
+    #[serde(default)]
+    pub guid: String,
     pub name: String,
     pub icon: String,
     pub duration: f32,
     pub aura_type: AuraType,
+    #[reflect(@MultiLine, @MarkupText)]
     pub rules_text: String,
+    #[serde(default)]
+    pub modifiers: Vec<StatModifier>,
+    #[serde(default)]
+    pub stacking: StackingRule,
+    // Only used by `StackingRule::Stack`. 0 is treated as 1.
+    #[serde(default)]
+    pub max_stacks: u32,
+    #[serde(default)]
+    pub tick_effect: TickEffect,
+    // Seconds between ticks. 0 means the aura never ticks.
+    #[serde(default)]
+    pub tick_interval: f32,
+    // The amount of each tick, for one stack at a strength of 1.
+    #[serde(default)]
+    pub tick_amount: u32,
 }
 
-#[derive(Debug)]
+// An aura applied to a target. `source` is whoever applied it and `strength`
+// scales its tick amount.
+#[derive(Debug, PartialEq, Clone)]
 pub struct Aura {
     pub def: Arc<AuraDef>,
+    pub source: u32,
+    pub strength: f32,
+    pub stacks: u32,
+    // Seconds until the aura expires. Auras with no duration never do.
+    pub remaining: f32,
+    // Seconds since the last tick.
+    pub since_tick: f32,
+}
+
+impl FromStr for StackingRule {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<StackingRule, Self::Err> {
+        match input {
+            "Refresh" => Ok(StackingRule::Refresh),
+            "Stack" => Ok(StackingRule::Stack),
+            "UniquePerSource" => Ok(StackingRule::UniquePerSource),
+            "ReplaceWeaker" => Ok(StackingRule::ReplaceWeaker),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for StackingRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for TickEffect {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<TickEffect, Self::Err> {
+        match input {
+            "None" => Ok(TickEffect::None),
+            "Damage" => Ok(TickEffect::Damage),
+            "Heal" => Ok(TickEffect::Heal),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for TickEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
 }
 
 impl FromStr for AuraType {
@@ -66,78 +143,87 @@ impl fmt::Display for AuraType {
     }
 }
 
-impl AssetLib<AuraLib> for AuraLib {
-    fn new(path: &str) -> Self {
-        let mut file = File::open(path).unwrap();
-        let mut data = String::new();
-        file.read_to_string(&mut data).unwrap();
-        let aura_ron: AuraRon = ron::from_str(&data).expect("RON was not well-formatted");
+impl AssetDef for AuraDef {
+    fn id(&self) -> u32 {
+        self.id
+    }
 
-        let mut name_map = HashMap::new();
-        let mut id_map = HashMap::new();
-        let mut defs: Vec<Arc<AuraDef>> = vec![];
-        for (i, def) in aura_ron.defs.into_iter().enumerate() {
-            name_map.insert(def.name.clone(), i);
-            id_map.insert(def.id, i);
-            defs.push(Arc::new(def));
-        }
-        AuraLib {
-            next_id: aura_ron.next_id,
-            name_map,
-            id_map,
-            defs,
-        }
+    fn guid(&self) -> &str {
+        &self.guid
     }
 
-    fn save(&self, path: &str) {
-        let mut defs = vec![];
-        for def in self.defs.clone() {
-            defs.push((*def).clone());
-        }
-        let aura_ron = AuraRon {
-            next_id: self.next_id,
-            defs,
-        };
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-        let mut aura_def_file = OpenOptions::new()
-            .truncate(true)
-            .write(true)
-            .create(true)
-            .open(path)
-            .unwrap();
-        let _ = aura_def_file.write(
-            ron::ser::to_string_pretty(&aura_ron, ron::ser::PrettyConfig::default())
-                .unwrap()
-                .as_bytes(),
-        );
+    fn icon(&self) -> &str {
+        &self.icon
     }
 }
 
-impl AuraLib {
-    pub fn id(&self, id: u32) -> &AuraDef {
-        &self.defs[self.id_map[&id]]
-    }
+impl LibDef for AuraDef {
+    const ASSET_TYPE: AssetType = AssetType::Aura;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[];
+}
 
-    pub fn name(&self, name: String) -> &Arc<AuraDef> {
-        &self.defs[self.name_map[&name]]
+impl AuraDef {
+    pub fn is_permanent(&self) -> bool {
+        self.duration <= 0.0
     }
 
-    pub fn update_def(&mut self, def: Arc<AuraDef>) {
-        let id = &def.id.clone();
-        self.defs[self.id_map[id]] = def
+    pub fn max_stacks(&self) -> u32 {
+        self.max_stacks.max(1)
     }
 }
 
 impl Aura {
-    pub fn new(def: &Arc<AuraDef>) -> Aura {
-        Aura { def: def.clone() }
+    pub fn new(def: &Arc<AuraDef>, source: u32, strength: f32) -> Aura {
+        Aura {
+            def: def.clone(),
+            source,
+            strength,
+            stacks: 1,
+            remaining: def.duration,
+            since_tick: 0.0,
+        }
+    }
+
+    // The amount of a single tick, for all stacks.
+    pub fn tick_amount(&self) -> f32 {
+        self.def.tick_amount as f32 * self.strength * self.stacks as f32
+    }
+}
+
+// Formats a duration in seconds as hours, minutes and seconds, e.g. `1h 6m 40s`.
+// Durations under a minute keep their fractions of a second.
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.max(0.0);
+    if seconds < 60.0 && seconds.fract() != 0.0 {
+        return format!("{}s", seconds);
     }
+    let seconds = seconds.round() as u64;
+    let mut parts = vec![];
+    if seconds >= 3600 {
+        parts.push(format!("{}h", seconds / 3600));
+    }
+    if seconds % 3600 >= 60 {
+        parts.push(format!("{}m", seconds % 3600 / 60));
+    }
+    if !seconds.is_multiple_of(60) || parts.is_empty() {
+        parts.push(format!("{}s", seconds % 60));
+    }
+    parts.join(" ")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::prelude::MECHANIC_TEST_DIR;
+    use game_system::asset::asset_lib::AssetLib;
 
     #[test]
     fn auralib_load_and_access() {
@@ -146,18 +232,37 @@ mod tests {
             name: "Well Fed".to_string(),
             icon: "sprite/icon/cheese.png".to_string(),
             id: 0,
+            guid: String::new(),
             aura_type: AuraType::None,
             duration: 60.0 * 60.0,
             rules_text: "You feel full! Your fortitudeness is through the roof.".to_string(),
+            modifiers: vec![],
+            stacking: StackingRule::Refresh,
+            max_stacks: 0,
+            tick_effect: TickEffect::None,
+            tick_interval: 0.0,
+            tick_amount: 0,
         };
         assert_eq!(*aura_lib.id(0), expected_aura_def);
         assert_eq!(aura_lib.name("Shocked".to_string()).name, "Shocked");
     }
 
+    #[test]
+    fn aura_format_duration() {
+        assert_eq!(format_duration(3600.0), "1h");
+        assert_eq!(format_duration(4000.0), "1h 6m 40s");
+        assert_eq!(format_duration(90.0), "1m 30s");
+        assert_eq!(format_duration(10.0), "10s");
+        assert_eq!(format_duration(2.5), "2.5s");
+        assert_eq!(format_duration(0.0), "0s");
+    }
+
     #[test]
     fn aura_new() {
         let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
-        let _aura1 = Aura::new(aura_lib.name("Shocked".to_string()));
-        let _aura2 = Aura::new(aura_lib.name("Shocked".to_string()));
+        let aura = Aura::new(aura_lib.name("Shocked".to_string()), 7, 2.0);
+        assert_eq!(aura.remaining, 10.0);
+        assert_eq!(aura.source, 7);
+        assert_eq!(aura.tick_amount(), 10.0);
     }
 }
@@ -0,0 +1,292 @@
+use crate::aura::aura::{Aura, AuraDef, AuraType, StackingRule, TickEffect};
+use std::sync::Arc;
+
+// Something that happened to an aura in an `AuraContainer`. Applying an aura
+// that is already there, and is refreshed or gains a stack, is also `Applied`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum AuraEvent {
+    Applied {
+        aura_id: u32,
+        source: u32,
+        stacks: u32,
+    },
+    Ticked {
+        aura_id: u32,
+        source: u32,
+        effect: TickEffect,
+        amount: f32,
+    },
+    Expired {
+        aura_id: u32,
+        source: u32,
+    },
+    Dispelled {
+        aura_id: u32,
+        source: u32,
+    },
+}
+
+// The auras on a single target. Auras are kept in the order they were first
+// applied, and events are returned in that order, so the same calls always
+// give the same events.
+#[derive(Debug, Default, Clone)]
+pub struct AuraContainer {
+    auras: Vec<Aura>,
+}
+
+impl AuraContainer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn auras(&self) -> &[Aura] {
+        &self.auras
+    }
+
+    pub fn contains(&self, aura_id: u32) -> bool {
+        self.auras.iter().any(|aura| aura.def.id == aura_id)
+    }
+
+    // The defs of the active auras, for `compute_stats`.
+    pub fn defs(&self) -> Vec<&AuraDef> {
+        self.auras.iter().map(|aura| aura.def.as_ref()).collect()
+    }
+
+    // Applies the aura following its def's `stacking` rule. Nothing happens if
+    // a `ReplaceWeaker` aura is weaker than the one already there.
+    pub fn apply(&mut self, def: &Arc<AuraDef>, source: u32, strength: f32) -> Vec<AuraEvent> {
+        let existing = self.auras.iter().position(|aura| {
+            aura.def.id == def.id
+                && (def.stacking != StackingRule::UniquePerSource || aura.source == source)
+        });
+        let Some(index) = existing else {
+            self.auras.push(Aura::new(def, source, strength));
+            return vec![AuraEvent::Applied {
+                aura_id: def.id,
+                source,
+                stacks: 1,
+            }];
+        };
+
+        let aura = &mut self.auras[index];
+        match def.stacking {
+            StackingRule::Refresh | StackingRule::UniquePerSource => {
+                aura.remaining = def.duration;
+            }
+            StackingRule::Stack => {
+                aura.stacks = (aura.stacks + 1).min(def.max_stacks());
+                aura.remaining = def.duration;
+            }
+            StackingRule::ReplaceWeaker => {
+                if strength < aura.strength {
+                    return vec![];
+                }
+                *aura = Aura::new(def, source, strength);
+            }
+        }
+        vec![AuraEvent::Applied {
+            aura_id: def.id,
+            source: aura.source,
+            stacks: aura.stacks,
+        }]
+    }
+
+    // Advances every aura by `delta` seconds, ticking and expiring them.
+    // An aura that expires during the update still ticks up to its expiry.
+    pub fn update(&mut self, delta: f32) -> Vec<AuraEvent> {
+        let mut events = vec![];
+        for aura in &mut self.auras {
+            let elapsed = if aura.def.is_permanent() {
+                delta
+            } else {
+                delta.min(aura.remaining)
+            };
+            if aura.def.tick_effect != TickEffect::None && aura.def.tick_interval > 0.0 {
+                aura.since_tick += elapsed;
+                while aura.since_tick >= aura.def.tick_interval {
+                    aura.since_tick -= aura.def.tick_interval;
+                    events.push(AuraEvent::Ticked {
+                        aura_id: aura.def.id,
+                        source: aura.source,
+                        effect: aura.def.tick_effect,
+                        amount: aura.tick_amount(),
+                    });
+                }
+            }
+            if !aura.def.is_permanent() {
+                aura.remaining -= elapsed;
+                if aura.remaining <= 0.0 {
+                    events.push(AuraEvent::Expired {
+                        aura_id: aura.def.id,
+                        source: aura.source,
+                    });
+                }
+            }
+        }
+        self.auras
+            .retain(|aura| aura.def.is_permanent() || aura.remaining > 0.0);
+        events
+    }
+
+    // Removes every aura of the given type.
+    pub fn dispel(&mut self, aura_type: &AuraType) -> Vec<AuraEvent> {
+        let mut events = vec![];
+        self.auras.retain(|aura| {
+            if aura.def.aura_type != *aura_type {
+                return true;
+            }
+            events.push(AuraEvent::Dispelled {
+                aura_id: aura.def.id,
+                source: aura.source,
+            });
+            false
+        });
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(id: u32, stacking: StackingRule) -> Arc<AuraDef> {
+        Arc::new(AuraDef {
+            id,
+            guid: String::new(),
+            name: format!("Aura {}", id),
+            icon: String::new(),
+            duration: 10.0,
+            aura_type: AuraType::Magic,
+            rules_text: String::new(),
+            modifiers: vec![],
+            stacking,
+            max_stacks: 3,
+            tick_effect: TickEffect::Damage,
+            tick_interval: 2.0,
+            tick_amount: 5,
+        })
+    }
+
+    fn ticked(aura_id: u32, source: u32, amount: f32) -> AuraEvent {
+        AuraEvent::Ticked {
+            aura_id,
+            source,
+            effect: TickEffect::Damage,
+            amount,
+        }
+    }
+
+    #[test]
+    fn aura_container_tick_and_expire() {
+        let mut container = AuraContainer::new();
+        let shocked = def(0, StackingRule::Refresh);
+        container.apply(&shocked, 1, 1.0);
+
+        assert_eq!(container.update(1.0), vec![]);
+        assert_eq!(container.update(1.0), vec![ticked(0, 1, 5.0)]);
+        assert_eq!(
+            container.update(4.5),
+            vec![ticked(0, 1, 5.0), ticked(0, 1, 5.0)]
+        );
+        // Only 3.5 seconds are left, so the last tick happens as it expires.
+        assert_eq!(
+            container.update(100.0),
+            vec![
+                ticked(0, 1, 5.0),
+                ticked(0, 1, 5.0),
+                AuraEvent::Expired {
+                    aura_id: 0,
+                    source: 1
+                }
+            ]
+        );
+        assert!(!container.contains(0));
+        assert_eq!(container.update(1.0), vec![]);
+    }
+
+    #[test]
+    fn aura_container_refresh() {
+        let mut container = AuraContainer::new();
+        let shocked = def(0, StackingRule::Refresh);
+        container.apply(&shocked, 1, 1.0);
+        container.update(6.0);
+        assert_eq!(
+            container.apply(&shocked, 2, 1.0),
+            vec![AuraEvent::Applied {
+                aura_id: 0,
+                source: 1,
+                stacks: 1
+            }]
+        );
+        assert_eq!(container.auras().len(), 1);
+        assert_eq!(container.auras()[0].remaining, 10.0);
+    }
+
+    #[test]
+    fn aura_container_stack_up_to_max() {
+        let mut container = AuraContainer::new();
+        let poison = def(0, StackingRule::Stack);
+        for _ in 0..5 {
+            container.apply(&poison, 1, 1.0);
+        }
+        assert_eq!(container.auras().len(), 1);
+        assert_eq!(container.auras()[0].stacks, 3);
+        assert_eq!(container.update(2.0), vec![ticked(0, 1, 15.0)]);
+    }
+
+    #[test]
+    fn aura_container_unique_per_source() {
+        let mut container = AuraContainer::new();
+        let dot = def(0, StackingRule::UniquePerSource);
+        container.apply(&dot, 1, 1.0);
+        container.apply(&dot, 2, 2.0);
+        container.apply(&dot, 1, 1.0);
+        assert_eq!(container.auras().len(), 2);
+        assert_eq!(
+            container.update(2.0),
+            vec![ticked(0, 1, 5.0), ticked(0, 2, 10.0)]
+        );
+    }
+
+    #[test]
+    fn aura_container_replace_weaker() {
+        let mut container = AuraContainer::new();
+        let shield = def(0, StackingRule::ReplaceWeaker);
+        container.apply(&shield, 1, 2.0);
+        assert_eq!(container.apply(&shield, 2, 1.0), vec![]);
+        assert_eq!(container.auras()[0].source, 1);
+        assert_eq!(
+            container.apply(&shield, 3, 3.0),
+            vec![AuraEvent::Applied {
+                aura_id: 0,
+                source: 3,
+                stacks: 1
+            }]
+        );
+        assert_eq!(container.auras().len(), 1);
+        assert_eq!(container.auras()[0].strength, 3.0);
+    }
+
+    #[test]
+    fn aura_container_dispel_and_permanent() {
+        let mut container = AuraContainer::new();
+        let magic = def(0, StackingRule::Refresh);
+        let mut well_fed = (*def(1, StackingRule::Refresh)).clone();
+        well_fed.aura_type = AuraType::None;
+        well_fed.duration = 0.0;
+        well_fed.tick_effect = TickEffect::None;
+        container.apply(&magic, 1, 1.0);
+        container.apply(&Arc::new(well_fed), 1, 1.0);
+
+        assert_eq!(
+            container.dispel(&AuraType::Magic),
+            vec![AuraEvent::Dispelled {
+                aura_id: 0,
+                source: 1
+            }]
+        );
+        assert_eq!(container.update(1000.0), vec![]);
+        assert!(container.contains(1));
+        assert_eq!(container.defs().len(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::ability::ability::AbilityLib;
+use crate::aura::aura::AuraLib;
+use crate::item::equipment::EquipmentSlot;
+use crate::item::item::ItemLib;
+use crate::loot::loot::LootTableLib;
+use crate::stat::stat::Stats;
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::Reflect;
+use game_system::asset::asset_lib::{AssetDef, AssetType, DefLib, LibDef};
+use game_system::asset::attribute::{MarkupText, MultiLine};
+use game_system::asset::identity::AssetReference;
+use game_system::asset::migration::Migration;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+pub type CharacterLib = DefLib<CharacterDef>;
+
+// A creature, NPC or player character.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+pub struct CharacterDef {
+    pub id: u32,
+    #[serde(default)]
+    pub guid: String,
+    pub name: String,
+    pub icon: String,
+    #[reflect(@MultiLine, @MarkupText)]
+    pub rules_text: String,
+    pub level: u32,
+    pub faction: String,
+    pub stats: Stats,
+    // The items the character starts with equipped, at most one per slot.
+    #[serde(default)]
+    pub equipment: Vec<EquipmentEntry>,
+    // `AbilityDef` ids.
+    #[serde(default)]
+    pub abilities: Vec<u32>,
+    // `AuraDef` ids of auras the character always has.
+    #[serde(default)]
+    pub auras: Vec<u32>,
+    // The `LootTableDef` id of the table rolled when the character dies, if it
+    // drops anything.
+    #[serde(default)]
+    pub loot_table_id: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+#[reflect(Default)]
+pub struct EquipmentEntry {
+    pub slot: EquipmentSlot,
+    // The `ItemDef` id of the equipped item.
+    pub item_id: u32,
+}
+
+// Why a character's equipment entry isn't valid.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EquipmentError {
+    MissingItem {
+        slot: EquipmentSlot,
+        item_id: u32,
+    },
+    // The item goes in `item_slot` rather than `slot`.
+    WrongSlot {
+        slot: EquipmentSlot,
+        item_id: u32,
+        item_slot: EquipmentSlot,
+    },
+    DuplicateSlot {
+        slot: EquipmentSlot,
+    },
+    // Only items that go in a slot can be equipped.
+    NoSlot {
+        item_id: u32,
+    },
+}
+
+#[derive(Debug)]
+pub struct Character {
+    pub def: Arc<CharacterDef>,
+}
+
+impl Default for EquipmentEntry {
+    fn default() -> Self {
+        EquipmentEntry {
+            slot: EquipmentSlot::None,
+            item_id: 0,
+        }
+    }
+}
+
+impl fmt::Display for EquipmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EquipmentError::MissingItem { slot, item_id } => {
+                write!(f, "{}: No item with id {}", slot, item_id)
+            }
+            EquipmentError::WrongSlot {
+                slot,
+                item_id,
+                item_slot,
+            } => write!(
+                f,
+                "{}: Item {} goes in the {} slot",
+                slot, item_id, item_slot
+            ),
+            EquipmentError::DuplicateSlot { slot } => {
+                write!(f, "{}: More than one item in this slot", slot)
+            }
+            EquipmentError::NoSlot { item_id } => {
+                write!(f, "Item {} isn't equipped in a slot", item_id)
+            }
+        }
+    }
+}
+
+impl AssetDef for CharacterDef {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon(&self) -> &str {
+        &self.icon
+    }
+}
+
+impl LibDef for CharacterDef {
+    const ASSET_TYPE: AssetType = AssetType::Character;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[
+        AssetReference {
+            path: "equipment[].item_id",
+            to: AssetType::Item,
+            when: None,
+        },
+        AssetReference {
+            path: "abilities[]",
+            to: AssetType::Ability,
+            when: None,
+        },
+        AssetReference {
+            path: "auras[]",
+            to: AssetType::Aura,
+            when: None,
+        },
+        AssetReference {
+            path: "loot_table_id",
+            to: AssetType::LootTable,
+            when: None,
+        },
+    ];
+}
+
+impl CharacterDef {
+    // Checks that every equipped item exists and goes in the slot it is
+    // placed in, and that no slot is used twice. Returns the index of each
+    // invalid entry with what's wrong with it.
+    pub fn validate_equipment(&self, item_lib: &ItemLib) -> Vec<(usize, EquipmentError)> {
+        let mut errors = vec![];
+        for (i, entry) in self.equipment.iter().enumerate() {
+            if entry.slot == EquipmentSlot::None {
+                errors.push((
+                    i,
+                    EquipmentError::NoSlot {
+                        item_id: entry.item_id,
+                    },
+                ));
+            } else if self.equipment[..i].iter().any(|e| e.slot == entry.slot) {
+                errors.push((
+                    i,
+                    EquipmentError::DuplicateSlot {
+                        slot: entry.slot.clone(),
+                    },
+                ));
+            }
+            if !item_lib.contains_id(entry.item_id) {
+                errors.push((
+                    i,
+                    EquipmentError::MissingItem {
+                        slot: entry.slot.clone(),
+                        item_id: entry.item_id,
+                    },
+                ));
+                continue;
+            }
+            let item_slot = &item_lib.id(entry.item_id).equipment_def.slot;
+            if entry.slot != EquipmentSlot::None && *item_slot != entry.slot {
+                errors.push((
+                    i,
+                    EquipmentError::WrongSlot {
+                        slot: entry.slot.clone(),
+                        item_id: entry.item_id,
+                        item_slot: item_slot.clone(),
+                    },
+                ));
+            }
+        }
+        errors
+    }
+
+    // The ids of abilities and auras the character has that don't exist.
+    pub fn missing_abilities(&self, ability_lib: &AbilityLib) -> Vec<u32> {
+        self.abilities
+            .iter()
+            .filter(|id| !ability_lib.contains_id(**id))
+            .copied()
+            .collect()
+    }
+
+    pub fn missing_auras(&self, aura_lib: &AuraLib) -> Vec<u32> {
+        self.auras
+            .iter()
+            .filter(|id| !aura_lib.contains_id(**id))
+            .copied()
+            .collect()
+    }
+
+    // The character's loot table id, if it doesn't exist.
+    pub fn missing_loot_table(&self, loot_table_lib: &LootTableLib) -> Option<u32> {
+        self.loot_table_id
+            .filter(|id| !loot_table_lib.contains_id(*id))
+    }
+}
+
+impl Character {
+    pub fn new(def: &Arc<CharacterDef>) -> Character {
+        Character { def: def.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::MECHANIC_TEST_DIR;
+    use game_system::asset::asset_lib::AssetLib;
+
+    fn character_lib() -> CharacterLib {
+        CharacterLib::new(&format!(
+            "{}/test/data/test_character.ron",
+            MECHANIC_TEST_DIR
+        ))
+    }
+
+    #[test]
+    fn characterlib_load_and_access() {
+        let character_lib = character_lib();
+        let goblin = character_lib.name("Goblin".to_string());
+        assert_eq!(goblin.level, 3);
+        assert_eq!(goblin.stats.health, 40.0);
+        assert_eq!(goblin.stats.haste, 0.0);
+        assert_eq!(
+            goblin.equipment,
+            vec![EquipmentEntry {
+                slot: EquipmentSlot::Feet,
+                item_id: 1,
+            }]
+        );
+        assert_eq!(goblin.loot_table_id, Some(0));
+        assert_eq!(character_lib.id(1).name, "Shopkeeper");
+        assert_eq!(character_lib.id(1).loot_table_id, None);
+    }
+
+    #[test]
+    fn characterlib_load_current_version() {
+        // Libs at the current version are read straight into their types, so
+        // `None` is read as an empty option rather than a variant name.
+        let path = std::env::temp_dir().join(format!("character_lib_{}.ron", std::process::id()));
+        std::fs::write(
+            &path,
+            "(
+                version: 1,
+                next_id: 1,
+                defs: [
+                    CharacterDef (
+                        id: 0,
+                        name: \"Rat\",
+                        icon: \"\",
+                        rules_text: \"\",
+                        level: 1,
+                        faction: \"\",
+                        stats: Stats (health: 5.0),
+                        equipment: [EquipmentEntry (slot: None, item_id: 0)],
+                        loot_table_id: None,
+                    ),
+                ],
+            )",
+        )
+        .unwrap();
+        let character_lib = CharacterLib::new(path.to_str().unwrap());
+        let rat = character_lib.id(0);
+        assert_eq!(rat.loot_table_id, None);
+        assert_eq!(rat.equipment[0].slot, EquipmentSlot::None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn character_validate_equipment() {
+        let character_lib = character_lib();
+        let item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        assert!(character_lib.id(0).validate_equipment(&item_lib).is_empty());
+        assert_eq!(
+            character_lib.id(1).validate_equipment(&item_lib),
+            vec![
+                (
+                    0,
+                    EquipmentError::WrongSlot {
+                        slot: EquipmentSlot::Head,
+                        item_id: 1,
+                        item_slot: EquipmentSlot::Feet,
+                    }
+                ),
+                (
+                    1,
+                    EquipmentError::DuplicateSlot {
+                        slot: EquipmentSlot::Head,
+                    }
+                ),
+                (
+                    1,
+                    EquipmentError::MissingItem {
+                        slot: EquipmentSlot::Head,
+                        item_id: 9,
+                    }
+                ),
+                (2, EquipmentError::NoSlot { item_id: 0 }),
+            ]
+        );
+    }
+}
@@ -3,7 +3,9 @@ use std::{fmt, str::FromStr};
 use bevy_reflect::Reflect;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Reflect)]
+use crate::stat::stat::StatModifier;
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Serialize, Deserialize, Reflect)]
 pub enum EquipmentSlot {
     MainHand,
     OffHand,
@@ -24,6 +26,8 @@ pub enum EquipmentSlot {
 pub struct EquipmentDef {
     pub slot: EquipmentSlot,
     pub armor: u32,
+    #[serde(default)]
+    pub modifiers: Vec<StatModifier>,
 }
 
 impl fmt::Display for EquipmentSlot {
@@ -60,6 +64,7 @@ impl Default for EquipmentDef {
         EquipmentDef {
             slot: EquipmentSlot::None,
             armor: 0,
+            modifiers: vec![],
         }
     }
 }
@@ -0,0 +1,450 @@
+use std::{cmp::min, collections::BTreeMap, fmt};
+
+use game_system::asset::format::{read_asset_file, write_asset_file};
+use serde::{Deserialize, Serialize};
+
+use super::equipment::EquipmentSlot;
+use super::item::ItemLib;
+
+// A quantity of one item in an inventory slot.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item_id: u32,
+    pub quantity: u32,
+}
+
+// A fixed number of slots holding item stacks, and the items equipped in each
+// equipment slot. Items are stored by id so inventories can be saved to RON
+// and loaded against the item lib.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+    #[serde(default)]
+    pub equipment: BTreeMap<EquipmentSlot, u32>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum InventoryError {
+    MissingItem(u32),
+    // There isn't room for `quantity` more of the item.
+    Full {
+        item_id: u32,
+        quantity: u32,
+    },
+    // There are fewer than `quantity` of the item, or in the slot.
+    NotEnough {
+        item_id: u32,
+        quantity: u32,
+    },
+    InvalidSlot(usize),
+    EmptySlot(usize),
+    // A stack holds more than the item's `max_stack`.
+    Overstacked(usize),
+    // The item goes in `item_slot` rather than `slot`.
+    WrongSlot {
+        slot: EquipmentSlot,
+        item_id: u32,
+        item_slot: EquipmentSlot,
+    },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InventoryError::MissingItem(item_id) => write!(f, "No item with id {}", item_id),
+            InventoryError::Full { item_id, quantity } => {
+                write!(f, "No room for {} of item {}", quantity, item_id)
+            }
+            InventoryError::NotEnough { item_id, quantity } => {
+                write!(f, "Fewer than {} of item {}", quantity, item_id)
+            }
+            InventoryError::InvalidSlot(slot) => write!(f, "No slot {}", slot),
+            InventoryError::EmptySlot(slot) => write!(f, "Slot {} is empty", slot),
+            InventoryError::Overstacked(slot) => {
+                write!(f, "Slot {} holds more than the item's max stack", slot)
+            }
+            InventoryError::WrongSlot {
+                slot,
+                item_id,
+                item_slot,
+            } => write!(
+                f,
+                "{}: Item {} goes in the {} slot",
+                slot, item_id, item_slot
+            ),
+        }
+    }
+}
+
+// The most of an item a single slot can hold. A `max_stack` of 0 is treated
+// as 1.
+fn max_stack(item_lib: &ItemLib, item_id: u32) -> Result<u32, InventoryError> {
+    if !item_lib.contains_id(item_id) {
+        return Err(InventoryError::MissingItem(item_id));
+    }
+    Ok(item_lib.id(item_id).max_stack.max(1))
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Self {
+        Inventory {
+            slots: vec![None; size],
+            equipment: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Inventory, String> {
+        read_asset_file(path)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        write_asset_file(path, self)
+    }
+
+    pub fn count(&self, item_id: u32) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.item_id == item_id)
+            .map(|stack| stack.quantity)
+            .sum()
+    }
+
+    // How many more of the item fit, topping up existing stacks and filling
+    // empty slots.
+    pub fn room_for(&self, item_lib: &ItemLib, item_id: u32) -> Result<u32, InventoryError> {
+        let max_stack = max_stack(item_lib, item_id)?;
+        Ok(self
+            .slots
+            .iter()
+            .map(|slot| match slot {
+                None => max_stack,
+                Some(stack) if stack.item_id == item_id => max_stack.saturating_sub(stack.quantity),
+                Some(_) => 0,
+            })
+            .sum())
+    }
+
+    // Adds the items to existing stacks first, then to empty slots. Nothing is
+    // added unless they all fit.
+    pub fn add(
+        &mut self,
+        item_lib: &ItemLib,
+        item_id: u32,
+        quantity: u32,
+    ) -> Result<(), InventoryError> {
+        if self.room_for(item_lib, item_id)? < quantity {
+            return Err(InventoryError::Full { item_id, quantity });
+        }
+        let max_stack = max_stack(item_lib, item_id)?;
+        let mut remaining = quantity;
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item_id == item_id && remaining > 0 {
+                let added = min(remaining, max_stack.saturating_sub(stack.quantity));
+                stack.quantity += added;
+                remaining -= added;
+            }
+        }
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let added = min(remaining, max_stack);
+            *slot = Some(ItemStack {
+                item_id,
+                quantity: added,
+            });
+            remaining -= added;
+        }
+        Ok(())
+    }
+
+    // Removes the items from the last stacks first. Nothing is removed unless
+    // there are enough.
+    pub fn remove(&mut self, item_id: u32, quantity: u32) -> Result<(), InventoryError> {
+        if self.count(item_id) < quantity {
+            return Err(InventoryError::NotEnough { item_id, quantity });
+        }
+        let mut remaining = quantity;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = slot.as_mut().filter(|stack| stack.item_id == item_id) {
+                let removed = min(remaining, stack.quantity);
+                stack.quantity -= removed;
+                remaining -= removed;
+                if stack.quantity == 0 {
+                    *slot = None;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Moves `quantity` from the stack in `slot` to the first empty slot,
+    // returning the new slot.
+    pub fn split(&mut self, slot: usize, quantity: u32) -> Result<usize, InventoryError> {
+        let stack = self
+            .slots
+            .get(slot)
+            .ok_or(InventoryError::InvalidSlot(slot))?
+            .as_ref()
+            .ok_or(InventoryError::EmptySlot(slot))?;
+        let item_id = stack.item_id;
+        if quantity == 0 || quantity >= stack.quantity {
+            return Err(InventoryError::NotEnough { item_id, quantity });
+        }
+        let empty = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_none())
+            .ok_or(InventoryError::Full { item_id, quantity })?;
+        if let Some(stack) = &mut self.slots[slot] {
+            stack.quantity -= quantity;
+        }
+        self.slots[empty] = Some(ItemStack { item_id, quantity });
+        Ok(empty)
+    }
+
+    // Moves the stack in `from` onto `to`. Stacks of the same item are merged
+    // up to `max_stack`, leaving the rest in `from`, and stacks of different
+    // items swap places.
+    pub fn move_stack(
+        &mut self,
+        item_lib: &ItemLib,
+        from: usize,
+        to: usize,
+    ) -> Result<(), InventoryError> {
+        for slot in [from, to] {
+            if slot >= self.slots.len() {
+                return Err(InventoryError::InvalidSlot(slot));
+            }
+        }
+        let Some(moving) = self.slots[from].clone() else {
+            return Err(InventoryError::EmptySlot(from));
+        };
+        if from == to {
+            return Ok(());
+        }
+        match &mut self.slots[to] {
+            Some(stack) if stack.item_id == moving.item_id => {
+                let max_stack = max_stack(item_lib, moving.item_id)?;
+                let moved = min(moving.quantity, max_stack.saturating_sub(stack.quantity));
+                stack.quantity += moved;
+                let left = moving.quantity - moved;
+                self.slots[from] = (left > 0).then_some(ItemStack {
+                    item_id: moving.item_id,
+                    quantity: left,
+                });
+            }
+            _ => self.slots.swap(from, to),
+        }
+        Ok(())
+    }
+
+    // Equips the item in `slot`, returning the item that was there before.
+    pub fn equip(
+        &mut self,
+        item_lib: &ItemLib,
+        slot: EquipmentSlot,
+        item_id: u32,
+    ) -> Result<Option<u32>, InventoryError> {
+        if !item_lib.contains_id(item_id) {
+            return Err(InventoryError::MissingItem(item_id));
+        }
+        let item_slot = &item_lib.id(item_id).equipment_def.slot;
+        if *item_slot != slot || slot == EquipmentSlot::None {
+            return Err(InventoryError::WrongSlot {
+                slot,
+                item_id,
+                item_slot: item_slot.clone(),
+            });
+        }
+        Ok(self.equipment.insert(slot, item_id))
+    }
+
+    pub fn unequip(&mut self, slot: &EquipmentSlot) -> Option<u32> {
+        self.equipment.remove(slot)
+    }
+
+    // Everything wrong with the inventory for the given items, such as a save
+    // file from before an item was removed or its `max_stack` lowered.
+    pub fn validate(&self, item_lib: &ItemLib) -> Vec<InventoryError> {
+        let mut errors = vec![];
+        for (i, stack) in self.slots.iter().enumerate() {
+            let Some(stack) = stack else {
+                continue;
+            };
+            match max_stack(item_lib, stack.item_id) {
+                Ok(max_stack) if stack.quantity > max_stack => {
+                    errors.push(InventoryError::Overstacked(i))
+                }
+                Ok(_) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+        for (slot, item_id) in &self.equipment {
+            if !item_lib.contains_id(*item_id) {
+                errors.push(InventoryError::MissingItem(*item_id));
+                continue;
+            }
+            let item_slot = &item_lib.id(*item_id).equipment_def.slot;
+            if item_slot != slot || *slot == EquipmentSlot::None {
+                errors.push(InventoryError::WrongSlot {
+                    slot: slot.clone(),
+                    item_id: *item_id,
+                    item_slot: item_slot.clone(),
+                });
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use game_system::asset::asset_lib::AssetLib;
+
+    // Red Potion (0) stacks to 50 and Shoe (1) to 1.
+    fn item_lib() -> ItemLib {
+        ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR))
+    }
+
+    fn stack(item_id: u32, quantity: u32) -> Option<ItemStack> {
+        Some(ItemStack { item_id, quantity })
+    }
+
+    #[test]
+    fn inventory_add_and_remove() {
+        let item_lib = item_lib();
+        let mut inventory = Inventory::new(3);
+        inventory.add(&item_lib, 0, 30).unwrap();
+        inventory.add(&item_lib, 1, 1).unwrap();
+        inventory.add(&item_lib, 0, 30).unwrap();
+        assert_eq!(
+            inventory.slots,
+            vec![stack(0, 50), stack(1, 1), stack(0, 10)]
+        );
+        assert_eq!(inventory.count(0), 60);
+
+        assert_eq!(
+            inventory.add(&item_lib, 0, 41),
+            Err(InventoryError::Full {
+                item_id: 0,
+                quantity: 41
+            })
+        );
+        assert_eq!(inventory.count(0), 60);
+        assert_eq!(
+            inventory.add(&item_lib, 7, 1),
+            Err(InventoryError::MissingItem(7))
+        );
+
+        inventory.remove(0, 15).unwrap();
+        assert_eq!(inventory.slots, vec![stack(0, 45), stack(1, 1), None]);
+        assert_eq!(
+            inventory.remove(1, 2),
+            Err(InventoryError::NotEnough {
+                item_id: 1,
+                quantity: 2
+            })
+        );
+    }
+
+    #[test]
+    fn inventory_split_and_move() {
+        let item_lib = item_lib();
+        let mut inventory = Inventory::new(3);
+        inventory.add(&item_lib, 0, 50).unwrap();
+        assert_eq!(inventory.split(0, 20), Ok(1));
+        assert_eq!(inventory.slots, vec![stack(0, 30), stack(0, 20), None]);
+        assert!(inventory.split(0, 30).is_err());
+        assert_eq!(inventory.split(2, 1), Err(InventoryError::EmptySlot(2)));
+
+        // Merging stops at the max stack.
+        inventory.slots[1] = stack(0, 40);
+        inventory.move_stack(&item_lib, 0, 1).unwrap();
+        assert_eq!(inventory.slots, vec![stack(0, 20), stack(0, 50), None]);
+
+        inventory.add(&item_lib, 1, 1).unwrap();
+        inventory.move_stack(&item_lib, 2, 0).unwrap();
+        assert_eq!(
+            inventory.slots,
+            vec![stack(1, 1), stack(0, 50), stack(0, 20)]
+        );
+    }
+
+    #[test]
+    fn inventory_equip() {
+        let item_lib = item_lib();
+        let mut inventory = Inventory::new(0);
+        assert_eq!(inventory.equip(&item_lib, EquipmentSlot::Feet, 1), Ok(None));
+        assert_eq!(
+            inventory.equip(&item_lib, EquipmentSlot::Feet, 1),
+            Ok(Some(1))
+        );
+        assert_eq!(
+            inventory.equip(&item_lib, EquipmentSlot::Head, 1),
+            Err(InventoryError::WrongSlot {
+                slot: EquipmentSlot::Head,
+                item_id: 1,
+                item_slot: EquipmentSlot::Feet
+            })
+        );
+        assert!(inventory.equip(&item_lib, EquipmentSlot::None, 0).is_err());
+        assert_eq!(inventory.unequip(&EquipmentSlot::Feet), Some(1));
+    }
+
+    #[test]
+    fn inventory_save_and_load() {
+        let item_lib = item_lib();
+        let inventory = Inventory::load(&format!(
+            "{}/test/data/test_inventory.ron",
+            MECHANIC_TEST_DIR
+        ))
+        .unwrap();
+        assert_eq!(inventory.slots, vec![stack(0, 12), None, stack(0, 60)]);
+        assert_eq!(inventory.equipment[&EquipmentSlot::Feet], 1);
+        assert_eq!(
+            inventory.validate(&item_lib),
+            vec![InventoryError::Overstacked(2)]
+        );
+
+        let path = std::env::temp_dir().join(format!("inventory_{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+        inventory.save(path).unwrap();
+        assert_eq!(Inventory::load(path), Ok(inventory));
+        std::fs::write(path, "(slots: [").unwrap();
+        assert!(Inventory::load(path).is_err());
+        std::fs::remove_file(path).unwrap();
+        assert!(Inventory::load(path).is_err());
+    }
+
+    #[test]
+    fn inventory_validate_equipment() {
+        let item_lib = item_lib();
+        let mut inventory = Inventory::new(0);
+        inventory.equipment.insert(EquipmentSlot::Head, 1);
+        inventory.equipment.insert(EquipmentSlot::None, 0);
+        inventory.equipment.insert(EquipmentSlot::Feet, 7);
+        assert_eq!(
+            inventory.validate(&item_lib),
+            vec![
+                InventoryError::WrongSlot {
+                    slot: EquipmentSlot::Head,
+                    item_id: 1,
+                    item_slot: EquipmentSlot::Feet
+                },
+                InventoryError::MissingItem(7),
+                InventoryError::WrongSlot {
+                    slot: EquipmentSlot::None,
+                    item_id: 0,
+                    item_slot: EquipmentSlot::None
+                },
+            ]
+        );
+    }
+}
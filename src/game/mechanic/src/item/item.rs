@@ -1,15 +1,10 @@
-use std::{
-    fmt,
-    fs::{File, OpenOptions},
-    io::{Read, Write},
-    str::FromStr,
-    sync::Arc,
-};
+use std::{fmt, str::FromStr, sync::Arc};
 
 use bevy_reflect::Reflect;
-use game_system::prelude::AssetLib;
+use game_system::prelude::{
+    AssetDef, AssetReference, AssetType, DefLib, LibDef, MarkupText, Migration, MultiLine,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 use super::equipment::EquipmentDef;
 
@@ -37,31 +32,36 @@ pub enum ItemRarity {
     Mythical,
 }
 
+impl ItemRarity {
+    pub const ALL: [ItemRarity; 6] = [
+        ItemRarity::Junk,
+        ItemRarity::Common,
+        ItemRarity::Uncommon,
+        ItemRarity::Rare,
+        ItemRarity::Epic,
+        ItemRarity::Mythical,
+    ];
+}
+
 impl fmt::Display for ItemRarity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ItemRon {
-    next_id: u32,
-    defs: Vec<ItemDef>,
-}
-
-#[derive(Debug, Default)]
-pub struct ItemLib {
-    next_id: u32,
-    name_map: HashMap<String, usize>,
-    id_map: HashMap<u32, usize>,
-    pub defs: Vec<Arc<ItemDef>>,
-}
+pub type ItemLib = DefLib<ItemDef>;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, Reflect)]
 pub struct ItemDef {
     pub id: u32,
+    // Unlike `id`, stays unique when defs are added on different branches.
+    // Empty for defs that don't have one.
+    #[serde(default)]
+    pub guid: String,
     pub name: String,
+    #[reflect(@MultiLine, @MarkupText)]
     pub rules_text: String,
+    #[reflect(@MultiLine)]
     pub flavor_text: String,
     pub icon: String,
     pub item_type: ItemType,
@@ -86,6 +86,7 @@ impl FromStr for ItemType {
         match input {
             "Equipment" => Ok(ItemType::Equipment),
             "Consumable" => Ok(ItemType::Consumable),
+            "Material" => Ok(ItemType::Material),
             "Miscellaneous" => Ok(ItemType::Miscellaneous),
             _ => Err(()),
         }
@@ -108,72 +109,43 @@ impl FromStr for ItemRarity {
     }
 }
 
-impl AssetLib<ItemLib> for ItemLib {
-    fn new(path: &str) -> Self {
-        let mut file = File::open(path).unwrap();
-        let mut data = String::new();
-        file.read_to_string(&mut data).unwrap();
-        let item_ron: ItemRon = ron::from_str(&data).expect("RON was not well-formatted");
-
-        let mut name_map = HashMap::new();
-        let mut id_map = HashMap::new();
-        let mut defs: Vec<Arc<ItemDef>> = vec![];
-        for (i, def) in item_ron.defs.into_iter().enumerate() {
-            name_map.insert(def.name.clone(), i);
-            id_map.insert(def.id, i);
-            defs.push(Arc::new(def));
-        }
-        ItemLib {
-            next_id: item_ron.next_id,
-            name_map,
-            id_map,
-            defs,
-        }
+impl AssetDef for ItemDef {
+    fn id(&self) -> u32 {
+        self.id
     }
 
-    fn save(&self, path: &str) {
-        let mut defs = vec![];
-        for def in self.defs.clone() {
-            defs.push((*def).clone());
-        }
-        let item_ron = ItemRon {
-            next_id: self.next_id,
-            defs,
-        };
-
-        let mut item_def_file = OpenOptions::new()
-            .truncate(true)
-            .write(true)
-            .create(true)
-            .open(path)
-            .unwrap();
-        let _ = item_def_file.write(
-            ron::ser::to_string_pretty(&item_ron, ron::ser::PrettyConfig::default())
-                .unwrap()
-                .as_bytes(),
-        );
+    fn guid(&self) -> &str {
+        &self.guid
     }
-}
 
-impl ItemLib {
-    pub fn id(&self, id: u32) -> &ItemDef {
-        &self.defs[self.id_map[&id]]
+    fn name(&self) -> &str {
+        &self.name
     }
 
-    pub fn update_def(&mut self, def: Arc<ItemDef>) {
-        let id = &def.id.clone();
-        self.defs[self.id_map[id]] = def
+    fn icon(&self) -> &str {
+        &self.icon
     }
+}
 
-    pub fn name(&self, name: String) -> &Arc<ItemDef> {
-        &self.defs[self.name_map[&name]]
-    }
+impl LibDef for ItemDef {
+    const ASSET_TYPE: AssetType = AssetType::Item;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[];
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::item::equipment::EquipmentSlot;
     use crate::prelude::*;
+    use game_system::asset::asset_lib::AssetLib;
+    use game_system::asset::bundle::{AssetBundle, BundleWriter};
+    use game_system::asset::format::AssetFormat;
+    use game_system::asset::identity::AssetKey;
+    use game_system::asset::layout::{AssetLayout, LibFiles};
 
     #[test]
     fn item_new() {
@@ -183,6 +155,7 @@ mod tests {
             flavor_text: "Something quite flavorful".to_string(),
             icon: "sprite/icon/cheese.png".to_string(),
             id: 1,
+            guid: String::new(),
             item_type: ItemType::Consumable,
             item_rarity: ItemRarity::Common,
             max_stack: 50,
@@ -214,6 +187,7 @@ mod tests {
             flavor_text: "A vibrant red potion. Probably safe to drink.".to_string(),
             icon: "sprite/icon/red_potion.png".to_string(),
             id: 0,
+            guid: String::new(),
             item_type: ItemType::Miscellaneous,
             item_rarity: ItemRarity::Common,
             max_stack: 50,
@@ -224,4 +198,108 @@ mod tests {
         assert_eq!(*item_lib.id(0), expected_item_def);
         assert_eq!(item_lib.name("Red Potion".to_string()).name, "Red Potion");
     }
+
+    #[test]
+    fn item_lib_find_by_id_or_guid() {
+        let item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        let guid = "0190a5c4-8f21-7d3e-9b6a-2f4c1e8d7a53";
+        assert_eq!(item_lib.guid(guid).name, "Shoe");
+        assert!(item_lib.contains_guid(guid));
+        assert!(!item_lib.contains_guid(""));
+        for key in ["1", guid] {
+            let key: AssetKey = key.parse().unwrap();
+            assert_eq!(item_lib.find(&key).unwrap().name, "Shoe");
+        }
+        assert_eq!(item_lib.find(&AssetKey::Id(9)), None);
+        assert_eq!(item_lib.find(&AssetKey::Guid("0190".to_string())), None);
+    }
+
+    #[test]
+    fn item_lib_directory_round_trip() {
+        let mut item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        let dir = std::env::temp_dir().join(format!("item_lib_dir_{}", std::process::id()));
+        let path = AssetLayout::Directory.lib_path(dir.to_str().unwrap(), "item", AssetFormat::Ron);
+        item_lib.save(&path).unwrap();
+        assert!(dir.join("item/1-shoe.ron").exists());
+        let loaded = ItemLib::new(&path);
+        assert_eq!(loaded.next_id(), item_lib.next_id());
+        assert_eq!(loaded.defs, item_lib.defs);
+
+        let mut shoe = (*item_lib.defs[1]).clone();
+        shoe.name = "Boot".to_string();
+        item_lib.update_def(Arc::new(shoe));
+        item_lib.save(&path).unwrap();
+        assert!(dir.join("item/1-boot.ron").exists());
+        assert!(!dir.join("item/1-shoe.ron").exists());
+        assert_eq!(ItemLib::new(&path).id(1).name, "Boot");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn item_lib_load_legacy_file() {
+        // Named structs and no version, as libs were saved before versioning.
+        let item_lib = ItemLib::new(&format!("{}/test/data/legacy_item.ron", MECHANIC_TEST_DIR));
+        assert_eq!(item_lib.next_id(), 2);
+        assert_eq!(item_lib.defs.len(), 2);
+        let potion = item_lib.name("Old Potion".to_string());
+        assert_eq!(potion.item_type, ItemType::Miscellaneous);
+        assert_eq!(potion.guid, "");
+        assert_eq!(potion.equipment_def, EquipmentDef::default());
+        let shoe = item_lib.id(1);
+        assert_eq!(shoe.item_rarity, ItemRarity::Uncommon);
+        assert_eq!(shoe.buy_value, 200);
+        assert_eq!(
+            shoe.equipment_def,
+            EquipmentDef {
+                slot: EquipmentSlot::Feet,
+                armor: 5,
+                modifiers: vec![StatModifier {
+                    stat: Stat::Haste,
+                    modifier_type: ModifierType::Flat,
+                    value: 10.0,
+                }],
+            }
+        );
+        assert_eq!(item_lib.to_value()["version"], 1);
+    }
+
+    #[test]
+    fn item_lib_value_round_trip() {
+        let path = format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR);
+        let item_lib = ItemLib::new(&path);
+        let value = item_lib.to_value();
+        assert_eq!(value["defs"][1]["name"], "Shoe");
+        assert_eq!(
+            ItemLib::from_value(value.clone()).unwrap().to_value(),
+            value
+        );
+        let files = LibFiles::read(&path).unwrap();
+        assert_eq!(ItemLib::read_files(&files).unwrap().to_value(), value);
+    }
+
+    #[test]
+    fn item_lib_bundle_round_trip() {
+        let mut item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        item_lib
+            .formulas
+            .set("sell_value", "buy_value / 2")
+            .unwrap();
+        let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
+        let mut writer = BundleWriter::new();
+        item_lib.add_to_bundle(&mut writer, "item").unwrap();
+        aura_lib.add_to_bundle(&mut writer, "aura").unwrap();
+        let bytes = writer.to_bytes().unwrap();
+
+        let bundle = AssetBundle::read(&bytes).unwrap();
+        let loaded = ItemLib::from_bundle(&bundle, "item").unwrap();
+        assert_eq!(loaded.next_id(), item_lib.next_id());
+        assert_eq!(loaded.formulas, item_lib.formulas);
+        assert_eq!(loaded.defs, item_lib.defs);
+        assert_eq!(loaded.name("Shoe".to_string()).id, 1);
+        assert_eq!(
+            AuraLib::from_bundle(&bundle, "aura").unwrap().defs,
+            aura_lib.defs
+        );
+        assert!(ItemLib::from_bundle(&bundle, "aura").is_err());
+    }
 }
@@ -1,2 +1,4 @@
 pub mod equipment;
+pub mod inventory;
+#[allow(clippy::module_inception)]
 pub mod item;
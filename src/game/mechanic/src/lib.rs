@@ -1,13 +1,133 @@
+pub mod ability;
 pub mod aura;
+pub mod character;
 pub mod item;
+pub mod loot;
+pub mod recipe;
+pub mod stat;
+pub mod text;
+pub mod vendor;
+
+// The formula engine is part of the mechanics, but it lives in game_system
+// because every lib stores its formulas and overrides there, and game_system
+// can't depend on game_mechanic.
+pub use game_system::formula;
 
 pub mod prelude {
     // Constants
     pub const MECHANIC_TEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
 
+    // Ability Modules
+    pub use crate::ability::ability::*;
+
     // Aura Modules
     pub use crate::aura::aura::*;
+    pub use crate::aura::container::*;
+
+    // Character Modules
+    pub use crate::character::character::*;
+
+    // Formula Modules
+    pub use game_system::formula::expression::*;
+    pub use game_system::formula::formula::*;
 
     // Item Modules
+    pub use crate::item::inventory::*;
     pub use crate::item::item::*;
+
+    // Loot Modules
+    pub use crate::loot::loot::*;
+    pub use crate::loot::rng::*;
+
+    // Recipe Modules
+    pub use crate::recipe::recipe::*;
+
+    // Stat Modules
+    pub use crate::stat::stat::*;
+
+    // Text Modules
+    pub use crate::text::markup::*;
+
+    // Vendor Modules
+    pub use crate::vendor::vendor::*;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use game_system::asset::asset_lib::{AssetLib, DefLib, LibDef};
+    use game_system::asset::format::AssetFormat;
+    use game_system::asset::layout::LibFiles;
+    use game_system::asset::migration::migration_report;
+    use std::fmt::Debug;
+
+    // Saves `test/data/test_<name>.ron` in each format, with the given
+    // formulas and an override on its first def, and checks that it loads back
+    // the same.
+    fn assert_lib_round_trips<D: LibDef + PartialEq + Debug>(
+        name: &str,
+        formulas: &[(&str, &str)],
+    ) {
+        let mut lib = DefLib::<D>::new(&format!(
+            "{}/test/data/test_{}.ron",
+            MECHANIC_TEST_DIR, name
+        ));
+        assert!(!lib.defs.is_empty(), "{}", name);
+        for (path, source) in formulas {
+            lib.formulas.set(path, source).unwrap();
+        }
+        let id = lib.defs[0].id();
+        lib.overrides.set(id, "name", true);
+        for format in AssetFormat::ALL {
+            let path = std::env::temp_dir().join(format!(
+                "{}_lib_{}.{}",
+                name,
+                std::process::id(),
+                format.extension()
+            ));
+            let path = path.to_str().unwrap();
+            lib.save(path).unwrap();
+            let loaded = DefLib::<D>::new(path);
+            assert_eq!(loaded.next_id(), lib.next_id(), "{} {}", name, format);
+            assert_eq!(loaded.formulas, lib.formulas, "{} {}", name, format);
+            assert_eq!(loaded.overrides, lib.overrides, "{} {}", name, format);
+            assert_eq!(loaded.defs, lib.defs, "{} {}", name, format);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    // Checks that the shipped lib and the test lib of the name are at the
+    // current version, so they're read straight into their types rather than
+    // migrated.
+    fn assert_lib_up_to_date<D: LibDef>(name: &str) {
+        for path in [
+            format!("{}/../../../asset/def/{}.ron", MECHANIC_TEST_DIR, name),
+            format!("{}/test/data/test_{}.ron", MECHANIC_TEST_DIR, name),
+        ] {
+            let report = migration_report(&LibFiles::read(&path).unwrap(), D::MIGRATIONS);
+            assert!(report.unwrap().is_up_to_date(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn libs_up_to_date() {
+        assert_lib_up_to_date::<AbilityDef>("ability");
+        assert_lib_up_to_date::<AuraDef>("aura");
+        assert_lib_up_to_date::<CharacterDef>("character");
+        assert_lib_up_to_date::<ItemDef>("item");
+        assert_lib_up_to_date::<LootTableDef>("loot_table");
+        assert_lib_up_to_date::<RecipeDef>("recipe");
+        assert_lib_up_to_date::<VendorDef>("vendor");
+    }
+
+    #[test]
+    fn libs_round_trip_formats() {
+        assert_lib_round_trips::<AbilityDef>("ability", &[]);
+        assert_lib_round_trips::<AuraDef>("aura", &[]);
+        assert_lib_round_trips::<CharacterDef>("character", &[]);
+        assert_lib_round_trips::<ItemDef>("item", &[("sell_value", "buy_value / 2")]);
+        assert_lib_round_trips::<LootTableDef>("loot_table", &[]);
+        assert_lib_round_trips::<RecipeDef>("recipe", &[]);
+        assert_lib_round_trips::<VendorDef>("vendor", &[]);
+    }
 }
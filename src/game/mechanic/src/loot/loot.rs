@@ -0,0 +1,386 @@
+use super::rng::Rng;
+use crate::item::item::{ItemLib, ItemRarity};
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::Reflect;
+use game_system::asset::asset_lib::{AssetDef, AssetType, DefLib, LibDef};
+use game_system::asset::attribute::{MarkupText, MultiLine};
+use game_system::asset::identity::AssetReference;
+use game_system::asset::migration::Migration;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// How deep loot tables can be nested inside each other before rolling stops,
+// so that tables that include themselves can't roll forever.
+const MAX_TABLE_DEPTH: u32 = 8;
+
+#[derive(Debug, Eq, PartialEq, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum LootEntryType {
+    // Drops nothing, to make the table's other entries less likely.
+    Nothing,
+    // Drops the item with the entry's id.
+    #[default]
+    Item,
+    // Rolls the loot table with the entry's id.
+    Table,
+    // Drops a random item of the entry's rarity.
+    Rarity,
+}
+
+pub type LootTableLib = DefLib<LootTableDef>;
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+pub struct LootTableDef {
+    pub id: u32,
+    #[serde(default)]
+    pub guid: String,
+    pub name: String,
+    pub icon: String,
+    #[reflect(@MultiLine, @MarkupText)]
+    pub rules_text: String,
+    // How many times a weighted entry is picked each time the table is rolled.
+    pub rolls: u32,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+#[reflect(Default)]
+pub struct LootEntry {
+    pub entry_type: LootEntryType,
+    // The `ItemDef` or `LootTableDef` id, depending on the entry type.
+    pub id: u32,
+    // Only used by `Rarity` entries.
+    pub rarity: ItemRarity,
+    // How likely the entry is to be picked, relative to the others.
+    pub weight: u32,
+    // Guaranteed entries drop every time the table is rolled, on top of the
+    // weighted picks.
+    pub guaranteed: bool,
+    // Clamped to the item's `max_stack`.
+    pub min_quantity: u32,
+    pub max_quantity: u32,
+}
+
+// An item that dropped, and how many of it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ItemDrop {
+    pub item_id: u32,
+    pub quantity: u32,
+}
+
+// The results of rolling a loot table many times.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LootSimulation {
+    pub rolls: u32,
+    // Each item that dropped at least once, most frequent first.
+    pub drops: Vec<DropFrequency>,
+    // The `sell_value` of everything that dropped.
+    pub total_value: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DropFrequency {
+    pub item_id: u32,
+    // How many of the rolls it dropped in.
+    pub times: u32,
+    pub total_quantity: u32,
+}
+
+impl Default for LootEntry {
+    fn default() -> Self {
+        LootEntry {
+            entry_type: LootEntryType::Item,
+            id: 0,
+            rarity: ItemRarity::Common,
+            weight: 1,
+            guaranteed: false,
+            min_quantity: 1,
+            max_quantity: 1,
+        }
+    }
+}
+
+impl FromStr for LootEntryType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<LootEntryType, Self::Err> {
+        match input {
+            "Nothing" => Ok(LootEntryType::Nothing),
+            "Item" => Ok(LootEntryType::Item),
+            "Table" => Ok(LootEntryType::Table),
+            "Rarity" => Ok(LootEntryType::Rarity),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for LootEntryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl AssetDef for LootTableDef {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon(&self) -> &str {
+        &self.icon
+    }
+}
+
+impl LibDef for LootTableDef {
+    const ASSET_TYPE: AssetType = AssetType::LootTable;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[
+        AssetReference {
+            path: "entries[].id",
+            to: AssetType::Item,
+            when: Some(("entry_type", "Item")),
+        },
+        AssetReference {
+            path: "entries[].id",
+            to: AssetType::LootTable,
+            when: Some(("entry_type", "Table")),
+        },
+    ];
+}
+
+impl LootTableDef {
+    // Rolls the table once. Drops of the same item are combined.
+    pub fn roll(
+        &self,
+        loot_lib: &LootTableLib,
+        item_lib: &ItemLib,
+        rng: &mut Rng,
+    ) -> Vec<ItemDrop> {
+        let mut drops = vec![];
+        self.roll_into(loot_lib, item_lib, rng, 0, &mut drops);
+        drops
+    }
+
+    fn roll_into(
+        &self,
+        loot_lib: &LootTableLib,
+        item_lib: &ItemLib,
+        rng: &mut Rng,
+        depth: u32,
+        drops: &mut Vec<ItemDrop>,
+    ) {
+        if depth > MAX_TABLE_DEPTH {
+            return;
+        }
+        for entry in self.entries.iter().filter(|entry| entry.guaranteed) {
+            entry.roll_into(loot_lib, item_lib, rng, depth, drops);
+        }
+        let weighted: Vec<&LootEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.guaranteed && entry.weight > 0)
+            .collect();
+        let total_weight: u32 = weighted.iter().map(|entry| entry.weight).sum();
+        if total_weight == 0 {
+            return;
+        }
+        for _ in 0..self.rolls {
+            let mut pick = rng.range(0, total_weight - 1);
+            for entry in &weighted {
+                if pick < entry.weight {
+                    entry.roll_into(loot_lib, item_lib, rng, depth, drops);
+                    break;
+                }
+                pick -= entry.weight;
+            }
+        }
+    }
+
+    // Rolls the table `rolls` times, counting how often each item drops.
+    pub fn simulate(
+        &self,
+        loot_lib: &LootTableLib,
+        item_lib: &ItemLib,
+        rolls: u32,
+        seed: u64,
+    ) -> LootSimulation {
+        let mut rng = Rng::new(seed);
+        let mut drops: Vec<DropFrequency> = vec![];
+        let mut total_value = 0;
+        for _ in 0..rolls {
+            for drop in self.roll(loot_lib, item_lib, &mut rng) {
+                total_value += item_lib.id(drop.item_id).sell_value as u64 * drop.quantity as u64;
+                match drops.iter_mut().find(|d| d.item_id == drop.item_id) {
+                    Some(frequency) => {
+                        frequency.times += 1;
+                        frequency.total_quantity += drop.quantity;
+                    }
+                    None => drops.push(DropFrequency {
+                        item_id: drop.item_id,
+                        times: 1,
+                        total_quantity: drop.quantity,
+                    }),
+                }
+            }
+        }
+        drops.sort_by(|a, b| b.times.cmp(&a.times).then(a.item_id.cmp(&b.item_id)));
+        LootSimulation {
+            rolls,
+            drops,
+            total_value,
+        }
+    }
+}
+
+impl LootEntry {
+    fn roll_into(
+        &self,
+        loot_lib: &LootTableLib,
+        item_lib: &ItemLib,
+        rng: &mut Rng,
+        depth: u32,
+        drops: &mut Vec<ItemDrop>,
+    ) {
+        let item_id = match self.entry_type {
+            LootEntryType::Nothing => return,
+            LootEntryType::Item if item_lib.contains_id(self.id) => self.id,
+            LootEntryType::Table if loot_lib.contains_id(self.id) => {
+                loot_lib
+                    .id(self.id)
+                    .roll_into(loot_lib, item_lib, rng, depth + 1, drops);
+                return;
+            }
+            LootEntryType::Rarity => {
+                let items: Vec<u32> = item_lib
+                    .defs
+                    .iter()
+                    .filter(|def| def.item_rarity == self.rarity)
+                    .map(|def| def.id)
+                    .collect();
+                if items.is_empty() {
+                    return;
+                }
+                items[rng.range(0, items.len() as u32 - 1) as usize]
+            }
+            _ => return,
+        };
+        let max_stack = item_lib.id(item_id).max_stack.max(1);
+        let quantity = rng
+            .range(self.min_quantity, self.max_quantity)
+            .clamp(1, max_stack);
+        match drops.iter_mut().find(|drop| drop.item_id == item_id) {
+            Some(drop) => drop.quantity += quantity,
+            None => drops.push(ItemDrop { item_id, quantity }),
+        }
+    }
+}
+
+impl LootSimulation {
+    // The average `sell_value` dropped per roll.
+    pub fn expected_value(&self) -> f32 {
+        if self.rolls == 0 {
+            return 0.0;
+        }
+        self.total_value as f32 / self.rolls as f32
+    }
+}
+
+impl DropFrequency {
+    // The fraction of rolls the item dropped in.
+    pub fn chance(&self, rolls: u32) -> f32 {
+        self.times as f32 / rolls.max(1) as f32
+    }
+
+    pub fn average_quantity(&self) -> f32 {
+        self.total_quantity as f32 / self.times.max(1) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::MECHANIC_TEST_DIR;
+    use game_system::asset::asset_lib::AssetLib;
+
+    fn libs() -> (LootTableLib, ItemLib) {
+        (
+            LootTableLib::new(&format!(
+                "{}/test/data/test_loot_table.ron",
+                MECHANIC_TEST_DIR
+            )),
+            ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR)),
+        )
+    }
+
+    #[test]
+    fn loot_roll_is_seeded() {
+        let (loot_lib, item_lib) = libs();
+        let table = loot_lib.name("Goblin".to_string());
+        let rolls = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..20)
+                .map(|_| table.roll(&loot_lib, &item_lib, &mut rng))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(1), rolls(1));
+    }
+
+    #[test]
+    fn loot_guaranteed_and_clamped() {
+        let (loot_lib, item_lib) = libs();
+        let table = loot_lib.name("Boss".to_string());
+        let mut rng = Rng::new(3);
+        for _ in 0..50 {
+            let drops = table.roll(&loot_lib, &item_lib, &mut rng);
+            // The shoe is guaranteed, and only stacks to 1.
+            assert!(drops.contains(&ItemDrop {
+                item_id: 1,
+                quantity: 1
+            }));
+        }
+    }
+
+    #[test]
+    fn loot_simulation_frequency() {
+        let (loot_lib, item_lib) = libs();
+        let simulation = loot_lib
+            .name("Goblin".to_string())
+            .simulate(&loot_lib, &item_lib, 10000, 42);
+        // Potions have 3 times the weight of nothing.
+        let potion = &simulation.drops[0];
+        assert_eq!(potion.item_id, 0);
+        assert!((potion.chance(simulation.rolls) - 0.75).abs() < 0.02);
+        assert!((potion.average_quantity() - 2.0).abs() < 0.05);
+        // Each potion sells for 5.
+        assert!((simulation.expected_value() - 7.5).abs() < 0.3);
+    }
+
+    #[test]
+    fn loot_nested_tables_and_rarity() {
+        let (loot_lib, item_lib) = libs();
+        let simulation = loot_lib
+            .name("Boss".to_string())
+            .simulate(&loot_lib, &item_lib, 1000, 9);
+        // Potions come from the nested goblin table, and the shoe is the only
+        // uncommon item.
+        assert_eq!(simulation.drops.len(), 2);
+        assert_eq!(simulation.drops[0].item_id, 1);
+        assert_eq!(simulation.drops[0].times, 1000);
+        // The looping table stops at the depth limit instead of overflowing.
+        let looping = loot_lib.name("Loop".to_string());
+        assert!(looping
+            .roll(&loot_lib, &item_lib, &mut Rng::new(0))
+            .is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+// A small seeded random number generator (SplitMix64), so that loot rolls and
+// simulations can be reproduced from their seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // A number from `min` to `max`, inclusive.
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max - min) as u64 + 1;
+        min + (self.next_u64() % span) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_is_seeded() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+        let a_rolls: Vec<u32> = (0..10).map(|_| a.range(1, 100)).collect();
+        let b_rolls: Vec<u32> = (0..10).map(|_| b.range(1, 100)).collect();
+        let c_rolls: Vec<u32> = (0..10).map(|_| c.range(1, 100)).collect();
+        assert_eq!(a_rolls, b_rolls);
+        assert_ne!(a_rolls, c_rolls);
+        assert!(a_rolls.iter().all(|roll| (1..=100).contains(roll)));
+        assert_eq!(a.range(5, 5), 5);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::item::item::ItemLib;
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::Reflect;
+use game_system::asset::asset_lib::{AssetDef, AssetType, DefLib, LibDef};
+use game_system::asset::attribute::{MarkupText, MultiLine};
+use game_system::asset::identity::AssetReference;
+use game_system::asset::migration::Migration;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Reflect)]
+pub enum CraftingStation {
+    // Can be crafted anywhere.
+    None,
+    Forge,
+    Workbench,
+    AlchemyTable,
+    Kitchen,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize, Reflect)]
+pub enum CraftingSkill {
+    None,
+    Smithing,
+    Leatherworking,
+    Alchemy,
+    Cooking,
+}
+
+pub type RecipeLib = DefLib<RecipeDef>;
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+pub struct RecipeDef {
+    pub id: u32,
+    #[serde(default)]
+    pub guid: String,
+    pub name: String,
+    pub icon: String,
+    #[reflect(@MultiLine, @MarkupText)]
+    pub rules_text: String,
+    #[serde(default)]
+    pub inputs: Vec<RecipeInput>,
+    pub output_item_id: u32,
+    pub output_quantity: u32,
+    pub station: CraftingStation,
+    pub skill: CraftingSkill,
+    pub skill_level: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Reflect)]
+#[reflect(Default)]
+pub struct RecipeInput {
+    pub item_id: u32,
+    pub count: u32,
+}
+
+// What it takes to craft an item from raw materials, which are items that no
+// recipe makes. `cost` is the `buy_value` of the raw materials for one of the
+// item, using the cheapest recipe at every step.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CraftingCost {
+    pub item_id: u32,
+    pub recipe_id: u32,
+    // Raw material item ids and how many of each one item takes.
+    pub raw_materials: BTreeMap<u32, f32>,
+    pub cost: f32,
+    // The item's `buy_value` minus `cost`.
+    pub margin: f32,
+}
+
+// Every item that a recipe makes, and the loops of items that are crafted
+// from each other.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecipeGraph {
+    // One per crafted item, by item id. `None` if every way of crafting the
+    // item goes through a cycle or an item that doesn't exist.
+    pub costs: BTreeMap<u32, Option<CraftingCost>>,
+    // Each cycle as the item ids around it, starting from the lowest.
+    pub cycles: Vec<Vec<u32>>,
+}
+
+#[derive(Debug)]
+pub struct Recipe {
+    pub def: Arc<RecipeDef>,
+}
+
+impl Default for RecipeInput {
+    fn default() -> Self {
+        RecipeInput {
+            item_id: 0,
+            count: 1,
+        }
+    }
+}
+
+impl FromStr for CraftingStation {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<CraftingStation, Self::Err> {
+        match input {
+            "None" => Ok(CraftingStation::None),
+            "Forge" => Ok(CraftingStation::Forge),
+            "Workbench" => Ok(CraftingStation::Workbench),
+            "AlchemyTable" => Ok(CraftingStation::AlchemyTable),
+            "Kitchen" => Ok(CraftingStation::Kitchen),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for CraftingStation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for CraftingSkill {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<CraftingSkill, Self::Err> {
+        match input {
+            "None" => Ok(CraftingSkill::None),
+            "Smithing" => Ok(CraftingSkill::Smithing),
+            "Leatherworking" => Ok(CraftingSkill::Leatherworking),
+            "Alchemy" => Ok(CraftingSkill::Alchemy),
+            "Cooking" => Ok(CraftingSkill::Cooking),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for CraftingSkill {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl AssetDef for RecipeDef {
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn icon(&self) -> &str {
+        &self.icon
+    }
+}
+
+impl LibDef for RecipeDef {
+    const ASSET_TYPE: AssetType = AssetType::Recipe;
+    const MIGRATIONS: &'static [Migration] = &[Migration {
+        description: "Added the version field",
+        steps: &[],
+    }];
+    const REFERENCES: &'static [AssetReference] = &[
+        AssetReference {
+            path: "inputs[].item_id",
+            to: AssetType::Item,
+            when: None,
+        },
+        AssetReference {
+            path: "output_item_id",
+            to: AssetType::Item,
+            when: None,
+        },
+    ];
+}
+
+impl RecipeDef {
+    // The input and output ids that don't match any item.
+    pub fn missing_items(&self, item_lib: &ItemLib) -> Vec<u32> {
+        self.inputs
+            .iter()
+            .map(|input| input.item_id)
+            .chain([self.output_item_id])
+            .filter(|id| !item_lib.contains_id(*id))
+            .collect()
+    }
+}
+
+impl RecipeGraph {
+    pub fn new(recipe_lib: &RecipeLib, item_lib: &ItemLib) -> Self {
+        let mut graph = RecipeGraph {
+            costs: BTreeMap::new(),
+            cycles: vec![],
+        };
+        let mut outputs: Vec<u32> = recipe_lib
+            .defs
+            .iter()
+            .map(|def| def.output_item_id)
+            .collect();
+        outputs.sort();
+        outputs.dedup();
+        for item_id in outputs {
+            graph.cost(recipe_lib, item_lib, item_id, &mut vec![]);
+        }
+        graph
+    }
+
+    // The raw materials for one of the item, or `None` if it can't be worked
+    // out. `path` is the items being crafted on the way to this one.
+    fn raw_materials(
+        &mut self,
+        recipe_lib: &RecipeLib,
+        item_lib: &ItemLib,
+        item_id: u32,
+        path: &mut Vec<u32>,
+    ) -> Option<BTreeMap<u32, f32>> {
+        if !item_lib.contains_id(item_id) {
+            return None;
+        }
+        if !recipe_lib
+            .defs
+            .iter()
+            .any(|def| def.output_item_id == item_id)
+        {
+            return Some(BTreeMap::from([(item_id, 1.0)]));
+        }
+        self.cost(recipe_lib, item_lib, item_id, path)
+            .map(|cost| cost.raw_materials)
+    }
+
+    fn cost(
+        &mut self,
+        recipe_lib: &RecipeLib,
+        item_lib: &ItemLib,
+        item_id: u32,
+        path: &mut Vec<u32>,
+    ) -> Option<CraftingCost> {
+        if let Some(start) = path.iter().position(|id| *id == item_id) {
+            self.add_cycle(&path[start..]);
+            return None;
+        }
+        if let Some(cost) = self.costs.get(&item_id) {
+            return cost.clone();
+        }
+        if !item_lib.contains_id(item_id) {
+            self.costs.insert(item_id, None);
+            return None;
+        }
+
+        path.push(item_id);
+        let mut cheapest: Option<CraftingCost> = None;
+        for recipe in recipe_lib
+            .defs
+            .iter()
+            .filter(|def| def.output_item_id == item_id && def.output_quantity > 0)
+        {
+            let mut raw_materials = BTreeMap::new();
+            let mut complete = true;
+            for input in &recipe.inputs {
+                let Some(materials) = self.raw_materials(recipe_lib, item_lib, input.item_id, path)
+                else {
+                    complete = false;
+                    continue;
+                };
+                for (material, count) in materials {
+                    *raw_materials.entry(material).or_insert(0.0) +=
+                        count * input.count as f32 / recipe.output_quantity as f32;
+                }
+            }
+            if !complete {
+                continue;
+            }
+            let cost = raw_materials
+                .iter()
+                .map(|(material, count)| item_lib.id(*material).buy_value as f32 * count)
+                .sum();
+            if cheapest
+                .as_ref()
+                .is_none_or(|cheapest| cost < cheapest.cost)
+            {
+                cheapest = Some(CraftingCost {
+                    item_id,
+                    recipe_id: recipe.id,
+                    raw_materials,
+                    cost,
+                    margin: item_lib.id(item_id).buy_value as f32 - cost,
+                });
+            }
+        }
+        path.pop();
+
+        // Costs found while inside a cycle depend on where the cycle was
+        // entered, so they are only kept for items outside of every cycle.
+        if !self.cycles.iter().any(|cycle| cycle.contains(&item_id)) || path.is_empty() {
+            self.costs.insert(item_id, cheapest.clone());
+        }
+        cheapest
+    }
+
+    fn add_cycle(&mut self, cycle: &[u32]) {
+        let start = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap_or(0);
+        let cycle: Vec<u32> = cycle[start..]
+            .iter()
+            .chain(&cycle[..start])
+            .copied()
+            .collect();
+        if !self.cycles.contains(&cycle) {
+            self.cycles.push(cycle);
+        }
+    }
+}
+
+impl Recipe {
+    pub fn new(def: &Arc<RecipeDef>) -> Recipe {
+        Recipe { def: def.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::MECHANIC_TEST_DIR;
+    use game_system::asset::asset_lib::AssetLib;
+
+    // Red Potion (0) has a buy value of 10 and Shoe (1) of 200. "Cobble Shoe"
+    // makes a shoe from potions and "Dissolve Shoe" makes potions from a shoe.
+    fn libs() -> (RecipeLib, ItemLib) {
+        (
+            RecipeLib::new(&format!("{}/test/data/test_recipe.ron", MECHANIC_TEST_DIR)),
+            ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR)),
+        )
+    }
+
+    #[test]
+    fn recipelib_load_and_access() {
+        let (recipe_lib, item_lib) = libs();
+        let recipe = recipe_lib.name("Cobble Shoe".to_string());
+        assert_eq!(recipe.output_item_id, 1);
+        assert_eq!(recipe.station, CraftingStation::Workbench);
+        assert_eq!(recipe.skill, CraftingSkill::Leatherworking);
+        assert_eq!(
+            recipe.inputs[0],
+            RecipeInput {
+                item_id: 0,
+                count: 5
+            }
+        );
+        assert_eq!(recipe_lib.id(2).missing_items(&item_lib), vec![7]);
+    }
+
+    #[test]
+    fn recipe_graph_cost_and_margin() {
+        let (mut recipe_lib, item_lib) = libs();
+        recipe_lib.defs.retain(|def| def.id == 0);
+        let graph = RecipeGraph::new(&recipe_lib, &item_lib);
+        assert!(graph.cycles.is_empty());
+        let cost = graph.costs[&1].clone().unwrap();
+        // Five potions make two shoes.
+        assert_eq!(cost.raw_materials, BTreeMap::from([(0, 2.5)]));
+        assert_eq!(cost.cost, 25.0);
+        assert_eq!(cost.margin, 175.0);
+    }
+
+    #[test]
+    fn recipe_graph_cycles() {
+        let (recipe_lib, item_lib) = libs();
+        let graph = RecipeGraph::new(&recipe_lib, &item_lib);
+        assert_eq!(graph.cycles, vec![vec![0, 1]]);
+        assert_eq!(graph.costs[&0], None);
+        assert_eq!(graph.costs[&1], None);
+        assert_eq!(graph.costs[&7], None);
+    }
+}
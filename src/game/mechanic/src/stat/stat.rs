@@ -0,0 +1,253 @@
+use crate::aura::aura::AuraDef;
+use crate::item::item::ItemDef;
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::Reflect;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// The stats of a character before any equipment or auras are applied.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct Stats {
+    pub health: f32,
+    pub mana: f32,
+    pub strength: f32,
+    pub agility: f32,
+    pub intellect: f32,
+    pub armor: f32,
+    pub haste: f32,
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum Stat {
+    #[default]
+    Health,
+    Mana,
+    Strength,
+    Agility,
+    Intellect,
+    Armor,
+    Haste,
+}
+
+// How a modifier changes a stat. Modifiers are applied in this order:
+// flat values are added to the base, then the total of the percents is
+// applied, then each multiplier.
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize, Reflect)]
+pub enum ModifierType {
+    // Adds `value`.
+    #[default]
+    Flat,
+    // Adds `value` percent, e.g. 30 for 30%. Percents add together before
+    // being applied.
+    Percent,
+    // Multiplies by `value`.
+    Multiplier,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize, Reflect)]
+#[reflect(Default)]
+pub struct StatModifier {
+    pub stat: Stat,
+    pub modifier_type: ModifierType,
+    pub value: f32,
+}
+
+impl Stats {
+    pub fn get(&self, stat: Stat) -> f32 {
+        match stat {
+            Stat::Health => self.health,
+            Stat::Mana => self.mana,
+            Stat::Strength => self.strength,
+            Stat::Agility => self.agility,
+            Stat::Intellect => self.intellect,
+            Stat::Armor => self.armor,
+            Stat::Haste => self.haste,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> &mut f32 {
+        match stat {
+            Stat::Health => &mut self.health,
+            Stat::Mana => &mut self.mana,
+            Stat::Strength => &mut self.strength,
+            Stat::Agility => &mut self.agility,
+            Stat::Intellect => &mut self.intellect,
+            Stat::Armor => &mut self.armor,
+            Stat::Haste => &mut self.haste,
+        }
+    }
+}
+
+impl Stat {
+    pub const ALL: [Stat; 7] = [
+        Stat::Health,
+        Stat::Mana,
+        Stat::Strength,
+        Stat::Agility,
+        Stat::Intellect,
+        Stat::Armor,
+        Stat::Haste,
+    ];
+}
+
+impl FromStr for Stat {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Stat, Self::Err> {
+        match input {
+            "Health" => Ok(Stat::Health),
+            "Mana" => Ok(Stat::Mana),
+            "Strength" => Ok(Stat::Strength),
+            "Agility" => Ok(Stat::Agility),
+            "Intellect" => Ok(Stat::Intellect),
+            "Armor" => Ok(Stat::Armor),
+            "Haste" => Ok(Stat::Haste),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for ModifierType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ModifierType, Self::Err> {
+        match input {
+            "Flat" => Ok(ModifierType::Flat),
+            "Percent" => Ok(ModifierType::Percent),
+            "Multiplier" => Ok(ModifierType::Multiplier),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ModifierType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Applies the modifiers to the base stats. For each stat this is
+// `(base + flat) * (1 + percent / 100) * multiplier * ...`, no matter what
+// order the modifiers are in.
+pub fn aggregate_stats<'a>(
+    base: &Stats,
+    modifiers: impl IntoIterator<Item = &'a StatModifier>,
+) -> Stats {
+    let modifiers: Vec<&StatModifier> = modifiers.into_iter().collect();
+    let mut stats = base.clone();
+    for stat in Stat::ALL {
+        let of_type = |modifier_type| {
+            modifiers
+                .iter()
+                .filter(move |m| m.stat == stat && m.modifier_type == modifier_type)
+                .map(|m| m.value)
+        };
+        let flat: f32 = of_type(ModifierType::Flat).sum();
+        let percent: f32 = of_type(ModifierType::Percent).sum();
+        let multiplier: f32 = of_type(ModifierType::Multiplier).product();
+        *stats.get_mut(stat) = (base.get(stat) + flat) * (1.0 + percent / 100.0) * multiplier;
+    }
+    stats
+}
+
+// The final stats of a character with the items equipped and auras active.
+// Each item's `equipment_def.armor` counts as a flat armor modifier.
+pub fn compute_stats(base: &Stats, equipment: &[&ItemDef], auras: &[&AuraDef]) -> Stats {
+    let armor: Vec<StatModifier> = equipment
+        .iter()
+        .map(|item| StatModifier {
+            stat: Stat::Armor,
+            modifier_type: ModifierType::Flat,
+            value: item.equipment_def.armor as f32,
+        })
+        .collect();
+    let modifiers = equipment
+        .iter()
+        .flat_map(|item| &item.equipment_def.modifiers)
+        .chain(auras.iter().flat_map(|aura| &aura.modifiers))
+        .chain(&armor);
+    aggregate_stats(base, modifiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use game_system::asset::asset_lib::AssetLib;
+
+    fn modifier(stat: Stat, modifier_type: ModifierType, value: f32) -> StatModifier {
+        StatModifier {
+            stat,
+            modifier_type,
+            value,
+        }
+    }
+
+    #[test]
+    fn stat_stacking_order() {
+        let base = Stats {
+            strength: 10.0,
+            ..Default::default()
+        };
+        let modifiers = vec![
+            modifier(Stat::Strength, ModifierType::Multiplier, 2.0),
+            modifier(Stat::Strength, ModifierType::Percent, 30.0),
+            modifier(Stat::Strength, ModifierType::Flat, 10.0),
+            modifier(Stat::Strength, ModifierType::Percent, 20.0),
+            modifier(Stat::Strength, ModifierType::Multiplier, 1.5),
+        ];
+        // (10 + 10) * 1.5 * 2 * 1.5
+        assert_eq!(aggregate_stats(&base, &modifiers).strength, 90.0);
+        let mut reversed = modifiers.clone();
+        reversed.reverse();
+        assert_eq!(
+            aggregate_stats(&base, &reversed),
+            aggregate_stats(&base, &modifiers)
+        );
+    }
+
+    #[test]
+    fn stat_modifiers_only_change_their_stat() {
+        let base = Stats {
+            health: 100.0,
+            haste: 0.0,
+            ..Default::default()
+        };
+        let stats = aggregate_stats(
+            &base,
+            &[
+                modifier(Stat::Haste, ModifierType::Flat, 5.0),
+                modifier(Stat::Health, ModifierType::Percent, -25.0),
+            ],
+        );
+        assert_eq!(stats.health, 75.0);
+        assert_eq!(stats.haste, 5.0);
+        assert_eq!(stats.mana, 0.0);
+        assert_eq!(aggregate_stats(&base, &[]), base);
+    }
+
+    #[test]
+    fn stat_compute_with_equipment_and_auras() {
+        let item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
+        let base = Stats {
+            armor: 10.0,
+            haste: 10.0,
+            ..Default::default()
+        };
+        let shoe = item_lib.name("Shoe".to_string());
+        let shocked = aura_lib.name("Shocked".to_string());
+        let stats = compute_stats(&base, &[shoe], &[shocked]);
+        // The shoe gives 5 armor and 10 haste, being shocked halves haste.
+        assert_eq!(stats.armor, 15.0);
+        assert_eq!(stats.haste, 10.0);
+    }
+}
@@ -0,0 +1,335 @@
+use std::{fmt, str::FromStr};
+
+use crate::aura::aura::AuraLib;
+use crate::item::item::ItemLib;
+
+// Rules text can reference other assets and game terms using tags wrapped in
+// braces, which the editor and the game render with their own colors.
+//
+// {aura:Shocked}     A reference to an aura, by name
+// {item:Red Potion}  A reference to an item, by name
+// {dmg:lightning}    A damage type
+// {stat:haste:30%}   A stat and the amount it changes by
+//
+// Use `{{` and `}}` to write literal braces.
+//
+// Tags can't be nested, as each one's arguments are plain text, so the parsed
+// markup is deliberately a flat list of tokens rather than a tree. Every
+// token is styled on its own, in the order it appears.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Markup {
+    pub tokens: Vec<MarkupToken>,
+    // Byte offset in the source text where each token starts.
+    offsets: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MarkupToken {
+    Text(String),
+    Aura(String),
+    Item(String),
+    Damage(DamageType),
+    Stat { stat: String, value: String },
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum DamageType {
+    Physical,
+    Magic,
+    Poison,
+    Fire,
+    Frost,
+    Lightning,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MarkupError {
+    // Byte offset of the tag the error was found in.
+    pub position: usize,
+    pub kind: MarkupErrorKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MarkupErrorKind {
+    UnclosedTag,
+    UnexpectedClose,
+    UnknownTag(String),
+    MissingArgument(String),
+    UnknownDamageType(String),
+    InvalidStatValue(String),
+    UnknownAura(String),
+    UnknownItem(String),
+}
+
+impl FromStr for DamageType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<DamageType, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "physical" => Ok(DamageType::Physical),
+            "magic" => Ok(DamageType::Magic),
+            "poison" => Ok(DamageType::Poison),
+            "fire" => Ok(DamageType::Fire),
+            "frost" => Ok(DamageType::Frost),
+            "lightning" => Ok(DamageType::Lightning),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for DamageType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_lowercase())
+    }
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            MarkupErrorKind::UnclosedTag => write!(f, "Unclosed tag"),
+            MarkupErrorKind::UnexpectedClose => write!(f, "Unexpected '}}', use '}}}}'"),
+            MarkupErrorKind::UnknownTag(tag) => write!(f, "Unknown tag '{}'", tag),
+            MarkupErrorKind::MissingArgument(tag) => write!(f, "Tag '{}' is missing a value", tag),
+            MarkupErrorKind::UnknownDamageType(name) => write!(f, "Unknown damage type '{}'", name),
+            MarkupErrorKind::InvalidStatValue(value) => write!(f, "Invalid stat value '{}'", value),
+            MarkupErrorKind::UnknownAura(name) => write!(f, "Unknown aura '{}'", name),
+            MarkupErrorKind::UnknownItem(name) => write!(f, "Unknown item '{}'", name),
+        }?;
+        write!(f, " at {}", self.position)
+    }
+}
+
+impl MarkupToken {
+    // The text the player sees for this token.
+    pub fn display_text(&self) -> String {
+        match self {
+            MarkupToken::Text(text) => text.clone(),
+            MarkupToken::Aura(name) => name.clone(),
+            MarkupToken::Item(name) => name.clone(),
+            MarkupToken::Damage(damage_type) => damage_type.to_string(),
+            MarkupToken::Stat { stat, value } => format!("{} {}", value, stat),
+        }
+    }
+}
+
+impl Markup {
+    pub fn parse(input: &str) -> Result<Markup, MarkupError> {
+        let mut markup = Markup::default();
+        let mut text = String::new();
+        let mut text_start = 0;
+        let mut chars = input.char_indices().peekable();
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|(_, c)| *c == '{').is_some() => text.push('{'),
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => text.push('}'),
+                '}' => {
+                    return Err(MarkupError {
+                        position,
+                        kind: MarkupErrorKind::UnexpectedClose,
+                    })
+                }
+                '{' => {
+                    let Some(length) = input[position..].find('}') else {
+                        return Err(MarkupError {
+                            position,
+                            kind: MarkupErrorKind::UnclosedTag,
+                        });
+                    };
+                    if !text.is_empty() {
+                        markup.push(MarkupToken::Text(std::mem::take(&mut text)), text_start);
+                    }
+                    let tag = parse_tag(&input[position + 1..position + length], position)?;
+                    markup.push(tag, position);
+                    text_start = position + length + 1;
+                    while chars.next_if(|(i, _)| *i < text_start).is_some() {}
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            markup.push(MarkupToken::Text(text), text_start);
+        }
+        Ok(markup)
+    }
+
+    // Markup holding only `text`, without looking for tags.
+    pub fn plain(text: &str) -> Markup {
+        let mut markup = Markup::default();
+        markup.push(MarkupToken::Text(text.to_string()), 0);
+        markup
+    }
+
+    // Checks that every aura and item referenced exists. Returns one error per
+    // missing reference.
+    pub fn validate(&self, aura_lib: &AuraLib, item_lib: &ItemLib) -> Vec<MarkupError> {
+        let mut errors = vec![];
+        for (token, position) in self.tokens.iter().zip(self.offsets.iter().copied()) {
+            let kind = match token {
+                MarkupToken::Aura(name) if !aura_lib.contains_name(name) => {
+                    MarkupErrorKind::UnknownAura(name.clone())
+                }
+                MarkupToken::Item(name) if !item_lib.contains_name(name) => {
+                    MarkupErrorKind::UnknownItem(name.clone())
+                }
+                _ => continue,
+            };
+            errors.push(MarkupError { position, kind });
+        }
+        errors
+    }
+
+    // The byte offset in the source text where a token starts.
+    pub fn offset(&self, token: usize) -> usize {
+        self.offsets[token]
+    }
+
+    // The text the player sees, with all tags replaced.
+    pub fn display_text(&self) -> String {
+        self.tokens.iter().map(MarkupToken::display_text).collect()
+    }
+}
+
+impl Markup {
+    fn push(&mut self, token: MarkupToken, offset: usize) {
+        self.tokens.push(token);
+        self.offsets.push(offset);
+    }
+}
+
+fn parse_tag(tag: &str, position: usize) -> Result<MarkupToken, MarkupError> {
+    let error = |kind| Err(MarkupError { position, kind });
+    let mut parts = tag.splitn(3, ':').map(str::trim);
+    let name = parts.next().unwrap_or_default().to_lowercase();
+    let Some(argument) = parts.next().filter(|argument| !argument.is_empty()) else {
+        return error(MarkupErrorKind::MissingArgument(name));
+    };
+    match name.as_str() {
+        "aura" => Ok(MarkupToken::Aura(argument.to_string())),
+        "item" => Ok(MarkupToken::Item(argument.to_string())),
+        "dmg" => match argument.parse::<DamageType>() {
+            Ok(damage_type) => Ok(MarkupToken::Damage(damage_type)),
+            Err(_) => error(MarkupErrorKind::UnknownDamageType(argument.to_string())),
+        },
+        "stat" => {
+            let value = parts.next().unwrap_or_default();
+            if value.trim_end_matches('%').parse::<f32>().is_err() {
+                return error(MarkupErrorKind::InvalidStatValue(value.to_string()));
+            }
+            Ok(MarkupToken::Stat {
+                stat: argument.to_string(),
+                value: value.to_string(),
+            })
+        }
+        _ => error(MarkupErrorKind::UnknownTag(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use game_system::prelude::AssetLib;
+
+    #[test]
+    fn markup_parse() {
+        let markup = Markup::parse("Haste increased by {stat:haste:30%}. {{Not a tag}}").unwrap();
+        assert_eq!(
+            markup.tokens,
+            vec![
+                MarkupToken::Text("Haste increased by ".to_string()),
+                MarkupToken::Stat {
+                    stat: "haste".to_string(),
+                    value: "30%".to_string()
+                },
+                MarkupToken::Text(". {Not a tag}".to_string()),
+            ]
+        );
+        assert_eq!(
+            markup.display_text(),
+            "Haste increased by 30% haste. {Not a tag}"
+        );
+
+        let markup = Markup::parse("{aura:Shocked}: deals {DMG: Lightning} damage").unwrap();
+        assert_eq!(
+            markup.tokens,
+            vec![
+                MarkupToken::Aura("Shocked".to_string()),
+                MarkupToken::Text(": deals ".to_string()),
+                MarkupToken::Damage(DamageType::Lightning),
+                MarkupToken::Text(" damage".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn markup_parse_errors() {
+        let kind = |input| Markup::parse(input).unwrap_err().kind;
+        assert_eq!(kind("Deals {dmg:lightning"), MarkupErrorKind::UnclosedTag);
+        assert_eq!(kind("Deals }"), MarkupErrorKind::UnexpectedClose);
+        assert_eq!(
+            kind("{dmg:cheese}"),
+            MarkupErrorKind::UnknownDamageType("cheese".to_string())
+        );
+        assert_eq!(
+            kind("{stat:haste:lots}"),
+            MarkupErrorKind::InvalidStatValue("lots".to_string())
+        );
+        assert_eq!(
+            kind("{aura}"),
+            MarkupErrorKind::MissingArgument("aura".to_string())
+        );
+        assert_eq!(
+            kind("{buff:Shocked}"),
+            MarkupErrorKind::UnknownTag("buff".to_string())
+        );
+        assert_eq!(Markup::parse("Deals {x:y}").unwrap_err().position, 6);
+    }
+
+    #[test]
+    fn markup_shipped_auras() {
+        let aura_lib = AuraLib::new(&format!(
+            "{}/../../../asset/def/aura.ron",
+            MECHANIC_TEST_DIR
+        ));
+        let item_lib = ItemLib::new(&format!(
+            "{}/../../../asset/def/item.ron",
+            MECHANIC_TEST_DIR
+        ));
+        let mut tags = 0;
+        for aura in &aura_lib.defs {
+            let markup = Markup::parse(&aura.rules_text).unwrap();
+            assert_eq!(
+                markup.validate(&aura_lib, &item_lib),
+                vec![],
+                "{}",
+                aura.name
+            );
+            tags += markup
+                .tokens
+                .iter()
+                .filter(|token| !matches!(token, MarkupToken::Text(_)))
+                .count();
+        }
+        assert!(tags > 0);
+    }
+
+    #[test]
+    fn markup_validate() {
+        let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
+        let item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        let markup = Markup::parse("{aura:Shocked} {aura:Frozen} {item:Shoe} {item:Boot}").unwrap();
+        assert_eq!(
+            markup.validate(&aura_lib, &item_lib),
+            vec![
+                MarkupError {
+                    position: 15,
+                    kind: MarkupErrorKind::UnknownAura("Frozen".to_string())
+                },
+                MarkupError {
+                    position: 41,
+                    kind: MarkupErrorKind::UnknownItem("Boot".to_string())
+                },
+            ]
+        );
+    }
+}
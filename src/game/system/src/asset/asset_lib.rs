@@ -1,10 +1,38 @@
+use crate::asset::bundle::{AssetBundle, BundleError, BundleWriter};
+use crate::asset::identity::{AssetKey, AssetReference};
+use crate::asset::layout::{write_asset_lib, LibFiles};
+use crate::asset::migration::{from_migrated_value, lib_version, Migration};
+use crate::asset::value::parse_asset_value;
+use crate::formula::formula::{FormulaOverrides, FormulaSet};
 use bevy_reflect::Reflect;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Reflect)]
 pub enum AssetType {
+    Ability,
     Aura,
+    Character,
     Item,
+    LootTable,
+    Recipe,
+    Vendor,
+}
+
+impl AssetType {
+    pub const ALL: [AssetType; 7] = [
+        AssetType::Ability,
+        AssetType::Aura,
+        AssetType::Character,
+        AssetType::Item,
+        AssetType::LootTable,
+        AssetType::Recipe,
+        AssetType::Vendor,
+    ];
 }
 
 impl fmt::Display for AssetType {
@@ -13,7 +41,337 @@ impl fmt::Display for AssetType {
     }
 }
 
-pub trait AssetLib<T> {
-    fn new(path: &str) -> Self;
-    fn save(&self, path: &str);
+// What every def has, whatever its asset type.
+pub trait AssetDef: Reflect {
+    fn id(&self) -> u32;
+    // Empty for defs that don't have one.
+    fn guid(&self) -> &str;
+    fn name(&self) -> &str;
+    fn icon(&self) -> &str;
+}
+
+// A def that is kept in a `DefLib` of its own asset type.
+pub trait LibDef: AssetDef + Clone + Serialize + DeserializeOwned {
+    const ASSET_TYPE: AssetType;
+    // Upgrades files saved by older versions, oldest first. See `Migration`.
+    const MIGRATIONS: &'static [Migration];
+    // The fields of its defs that hold the ids of other defs.
+    const REFERENCES: &'static [AssetReference];
+}
+
+// What the editor needs of a lib without knowing its asset type.
+pub trait AssetLib {
+    // Reads the lib at `path`, panicking if it can't be. See `read`.
+    fn new(path: &str) -> Self
+    where
+        Self: Sized;
+    // Reads the lib at `path`, in the format and layout given by the path.
+    fn read(path: &str) -> Result<Self, String>
+    where
+        Self: Sized;
+    // Reads a lib from its files. Libs at the current version are read
+    // straight into their types, and only older ones are migrated as values.
+    fn read_files(files: &LibFiles) -> Result<Self, String>
+    where
+        Self: Sized;
+    // Reads a lib from a value given by `to_value`.
+    fn from_value(lib: Value) -> Result<Self, String>
+    where
+        Self: Sized;
+    fn from_bundle(bundle: &AssetBundle, name: &str) -> Result<Self, BundleError>
+    where
+        Self: Sized;
+
+    fn asset_type(&self) -> AssetType;
+    fn migrations(&self) -> &'static [Migration];
+    fn references(&self) -> &'static [AssetReference];
+    // Replaces the lib with the one at `path`, as `read` does.
+    fn load(&mut self, path: &str) -> Result<(), String>;
+    // Replaces the lib with the one read from its files, as `read_files` does.
+    fn load_files(&mut self, files: &LibFiles) -> Result<(), String>;
+    // Replaces the lib with the one read from a value, as `from_value` does.
+    fn load_value(&mut self, lib: Value) -> Result<(), String>;
+    // The lib as a value, laid out the same way as one that was read.
+    fn to_value(&self) -> Value;
+    // Writes the lib to `path`, in the format and layout given by the path.
+    fn save(&self, path: &str) -> Result<(), String>;
+    // Encodes the lib into a bundle under `name`.
+    fn add_to_bundle(&self, bundle: &mut BundleWriter, name: &str) -> Result<(), BundleError>;
+
+    fn formulas(&self) -> &FormulaSet;
+    fn formulas_mut(&mut self) -> &mut FormulaSet;
+    fn overrides(&self) -> &FormulaOverrides;
+    fn overrides_mut(&mut self) -> &mut FormulaOverrides;
+    fn def(&self, id: u32) -> Option<&dyn AssetDef>;
+    fn all_defs(&self) -> Vec<&dyn AssetDef>;
+    // A copy of the def with the id, to be edited and put back with
+    // `replace_def`.
+    fn clone_def(&self, id: u32) -> Option<Box<dyn Reflect>>;
+    // Puts an edited copy of a def in place of the def with its id.
+    fn replace_def(&mut self, def: Box<dyn Reflect>) -> Result<(), String>;
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct LibRon<D> {
+    // Which of the def's `MIGRATIONS` the file has had. Files from before
+    // versioning have none.
+    #[serde(default)]
+    version: u32,
+    next_id: u32,
+    #[serde(default, skip_serializing_if = "FormulaSet::is_empty")]
+    formulas: FormulaSet,
+    #[serde(default, skip_serializing_if = "FormulaOverrides::is_empty")]
+    overrides: FormulaOverrides,
+    // Empty in the lib file of a directory lib, where defs have files of their
+    // own.
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    defs: Vec<D>,
+}
+
+// The defs of one asset type, looked up by id, name, or guid.
+#[derive(Debug)]
+pub struct DefLib<D> {
+    next_id: u32,
+    name_map: HashMap<String, usize>,
+    id_map: HashMap<u32, usize>,
+    guid_map: HashMap<String, usize>,
+    pub formulas: FormulaSet,
+    pub overrides: FormulaOverrides,
+    pub defs: Vec<Arc<D>>,
+}
+
+impl<D> Default for DefLib<D> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            name_map: HashMap::new(),
+            id_map: HashMap::new(),
+            guid_map: HashMap::new(),
+            formulas: FormulaSet::default(),
+            overrides: FormulaOverrides::default(),
+            defs: vec![],
+        }
+    }
+}
+
+impl<D: LibDef> AssetLib for DefLib<D> {
+    fn new(path: &str) -> Self {
+        Self::read(path)
+            .unwrap_or_else(|error| panic!("{} was not well-formatted: {}", path, error))
+    }
+
+    fn read(path: &str) -> Result<Self, String> {
+        Self::read_files(&LibFiles::read(path)?)
+    }
+
+    fn read_files(files: &LibFiles) -> Result<Self, String> {
+        read_lib_ron(files).map(Self::from_ron)
+    }
+
+    fn from_value(lib: Value) -> Result<Self, String> {
+        serde_json::from_value(lib)
+            .map(Self::from_ron)
+            .map_err(|error| error.to_string())
+    }
+
+    fn from_bundle(bundle: &AssetBundle, name: &str) -> Result<Self, BundleError> {
+        let (next_id, formulas, defs) = bundle.lib(name)?;
+        Ok(Self::from_ron(LibRon {
+            version: D::MIGRATIONS.len() as u32,
+            next_id,
+            formulas,
+            overrides: FormulaOverrides::default(),
+            defs,
+        }))
+    }
+
+    fn asset_type(&self) -> AssetType {
+        D::ASSET_TYPE
+    }
+
+    fn migrations(&self) -> &'static [Migration] {
+        D::MIGRATIONS
+    }
+
+    fn references(&self) -> &'static [AssetReference] {
+        D::REFERENCES
+    }
+
+    fn load(&mut self, path: &str) -> Result<(), String> {
+        *self = Self::read(path)?;
+        Ok(())
+    }
+
+    fn load_files(&mut self, files: &LibFiles) -> Result<(), String> {
+        *self = Self::read_files(files)?;
+        Ok(())
+    }
+
+    fn load_value(&mut self, lib: Value) -> Result<(), String> {
+        *self = Self::from_value(lib)?;
+        Ok(())
+    }
+
+    fn to_value(&self) -> Value {
+        serde_json::to_value(self.to_ron()).unwrap()
+    }
+
+    fn save(&self, path: &str) -> Result<(), String> {
+        write_asset_lib(path, &mut self.to_ron(), |lib_ron| &mut lib_ron.defs)
+    }
+
+    fn add_to_bundle(&self, bundle: &mut BundleWriter, name: &str) -> Result<(), BundleError> {
+        let lib_ron = self.to_ron();
+        bundle.add(name, &(lib_ron.next_id, lib_ron.formulas, lib_ron.defs))
+    }
+
+    fn formulas(&self) -> &FormulaSet {
+        &self.formulas
+    }
+
+    fn formulas_mut(&mut self) -> &mut FormulaSet {
+        &mut self.formulas
+    }
+
+    fn overrides(&self) -> &FormulaOverrides {
+        &self.overrides
+    }
+
+    fn overrides_mut(&mut self) -> &mut FormulaOverrides {
+        &mut self.overrides
+    }
+
+    fn def(&self, id: u32) -> Option<&dyn AssetDef> {
+        self.id_map
+            .get(&id)
+            .map(|i| self.defs[*i].as_ref() as &dyn AssetDef)
+    }
+
+    fn all_defs(&self) -> Vec<&dyn AssetDef> {
+        self.defs
+            .iter()
+            .map(|def| def.as_ref() as &dyn AssetDef)
+            .collect()
+    }
+
+    fn clone_def(&self, id: u32) -> Option<Box<dyn Reflect>> {
+        self.id_map
+            .get(&id)
+            .map(|i| Box::new(self.defs[*i].as_ref().clone()) as Box<dyn Reflect>)
+    }
+
+    fn replace_def(&mut self, def: Box<dyn Reflect>) -> Result<(), String> {
+        let def = def
+            .downcast::<D>()
+            .map_err(|_| format!("The def isn't a {}", D::ASSET_TYPE))?;
+        if !self.contains_id(def.id()) {
+            return Err(format!(
+                "There is no {} with id {}",
+                D::ASSET_TYPE,
+                def.id()
+            ));
+        }
+        self.update_def(Arc::new(*def));
+        Ok(())
+    }
+}
+
+// Reads a lib from its files. Only the version is read from the lib file
+// before it's parsed into its types. Older libs are read as values to be
+// migrated, which loses what values can't hold, such as the variant names of
+// enums with data, so they never take this path once they're up to date.
+fn read_lib_ron<D: LibDef>(files: &LibFiles) -> Result<LibRon<D>, String> {
+    let version = lib_version(&parse_asset_value(files.format, &files.lib)?)?;
+    if version != D::MIGRATIONS.len() as u32 {
+        return from_migrated_value(files.to_value()?, D::MIGRATIONS);
+    }
+    let mut lib: LibRon<D> = files.format.parse(&files.lib)?;
+    for (name, data) in &files.defs {
+        let def: D = files
+            .format
+            .parse(data)
+            .map_err(|error| format!("{}: {}", name, error))?;
+        lib.defs.push(def);
+    }
+    lib.defs.sort_by_key(|def| def.id());
+    Ok(lib)
+}
+
+impl<D: LibDef> DefLib<D> {
+    fn from_ron(lib_ron: LibRon<D>) -> Self {
+        let mut lib = Self {
+            next_id: lib_ron.next_id,
+            formulas: lib_ron.formulas,
+            overrides: lib_ron.overrides,
+            ..Self::default()
+        };
+        for (i, def) in lib_ron.defs.into_iter().enumerate() {
+            lib.name_map.insert(def.name().to_string(), i);
+            lib.id_map.insert(def.id(), i);
+            if !def.guid().is_empty() {
+                lib.guid_map.insert(def.guid().to_string(), i);
+            }
+            lib.defs.push(Arc::new(def));
+        }
+        lib
+    }
+
+    fn to_ron(&self) -> LibRon<D> {
+        LibRon {
+            version: D::MIGRATIONS.len() as u32,
+            next_id: self.next_id,
+            formulas: self.formulas.clone(),
+            overrides: self.overrides.clone(),
+            defs: self.defs.iter().map(|def| def.as_ref().clone()).collect(),
+        }
+    }
+
+    // The id the next def added will get.
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    pub fn id(&self, id: u32) -> &D {
+        &self.defs[self.id_map[&id]]
+    }
+
+    pub fn guid(&self, guid: &str) -> &D {
+        &self.defs[self.guid_map[guid]]
+    }
+
+    // Looks a def up by either its id or its guid.
+    pub fn find(&self, key: &AssetKey) -> Option<&Arc<D>> {
+        let i = match key {
+            AssetKey::Id(id) => self.id_map.get(id),
+            AssetKey::Guid(guid) => self.guid_map.get(guid),
+        };
+        i.map(|i| &self.defs[*i])
+    }
+
+    pub fn update_def(&mut self, def: Arc<D>) {
+        let i = self.id_map[&def.id()];
+        self.name_map.retain(|_, name_i| *name_i != i);
+        self.name_map.insert(def.name().to_string(), i);
+        self.guid_map.retain(|_, guid_i| *guid_i != i);
+        if !def.guid().is_empty() {
+            self.guid_map.insert(def.guid().to_string(), i);
+        }
+        self.defs[i] = def
+    }
+
+    pub fn name(&self, name: String) -> &Arc<D> {
+        &self.defs[self.name_map[&name]]
+    }
+
+    pub fn contains_id(&self, id: u32) -> bool {
+        self.id_map.contains_key(&id)
+    }
+
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.name_map.contains_key(name)
+    }
+
+    pub fn contains_guid(&self, guid: &str) -> bool {
+        self.guid_map.contains_key(guid)
+    }
 }
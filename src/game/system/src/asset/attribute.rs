@@ -0,0 +1,15 @@
+use bevy_reflect::Reflect;
+
+// Marks a `String` field on a def as holding paragraphs of text, such as rules
+// or flavor text, so that editors give it a multi-line text box.
+//
+// Usage: `#[reflect(@MultiLine)]` on the field.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Reflect)]
+pub struct MultiLine;
+
+// Marks a `String` field on a def as written in rules text markup, so that
+// editors color its tags and check the assets it references.
+//
+// Usage: `#[reflect(@MarkupText)]` on the field.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Reflect)]
+pub struct MarkupText;
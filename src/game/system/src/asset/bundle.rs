@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+
+// The first bytes of every bundle.
+pub const BUNDLE_MAGIC: [u8; 4] = *b"TAEB";
+// Bumped whenever the layout of a bundle, or of the defs in it, changes.
+pub const BUNDLE_VERSION: u32 = 2;
+const HEADER_LEN: usize = 16;
+
+// The start of a bundle: the magic bytes, then the version and a hash of
+// everything after the header, both little endian.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct BundleHeader {
+    pub version: u32,
+    pub hash: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BundleError {
+    Io(String),
+    NotABundle,
+    WrongVersion(u32),
+    // The hash in the header doesn't match the contents.
+    Corrupt,
+    MissingLib(String),
+    // A lib that couldn't be read to go in the bundle, by its path.
+    InvalidLib(String, String),
+    Encoding(String),
+}
+
+// Compiled asset libs for the game to load at runtime. A bundle indexes the
+// bytes it was read from without copying them, but decoding a lib copies its
+// strings into its defs. Defs can't borrow them, as they're kept behind an
+// `Arc` by a lib that outlives the bytes, and are the same types the editor
+// reads from files and edits.
+#[derive(Debug)]
+pub struct AssetBundle<'a> {
+    pub header: BundleHeader,
+    libs: Vec<(&'a str, &'a [u8])>,
+}
+
+// Builds a bundle one lib at a time.
+#[derive(Debug, Default)]
+pub struct BundleWriter {
+    libs: Vec<(String, Vec<u8>)>,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleError::Io(error) => write!(f, "{}", error),
+            BundleError::NotABundle => write!(f, "Not an asset bundle"),
+            BundleError::WrongVersion(version) => write!(
+                f,
+                "The bundle is version {}, expected {}",
+                version, BUNDLE_VERSION
+            ),
+            BundleError::Corrupt => write!(f, "The bundle's contents don't match its hash"),
+            BundleError::MissingLib(name) => write!(f, "The bundle has no {} lib", name),
+            BundleError::InvalidLib(path, error) => {
+                write!(f, "{} was not well-formatted: {}", path, error)
+            }
+            BundleError::Encoding(error) => write!(f, "The bundle could not be encoded: {}", error),
+        }
+    }
+}
+
+impl<'a> AssetBundle<'a> {
+    // Checks the header and hash, then indexes the libs in the bundle.
+    pub fn read(bytes: &'a [u8]) -> Result<Self, BundleError> {
+        if bytes.len() < HEADER_LEN || bytes[..4] != BUNDLE_MAGIC {
+            return Err(BundleError::NotABundle);
+        }
+        let header = BundleHeader {
+            version: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            hash: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+        };
+        if header.version != BUNDLE_VERSION {
+            return Err(BundleError::WrongVersion(header.version));
+        }
+        let body = &bytes[HEADER_LEN..];
+        if content_hash(body) != header.hash {
+            return Err(BundleError::Corrupt);
+        }
+        let libs =
+            postcard::from_bytes(body).map_err(|error| BundleError::Encoding(error.to_string()))?;
+        Ok(AssetBundle { header, libs })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.libs.iter().map(|(name, _)| *name)
+    }
+
+    // Decodes the lib with the name.
+    pub fn lib<T: Deserialize<'a>>(&self, name: &str) -> Result<T, BundleError> {
+        let (_, data) = self
+            .libs
+            .iter()
+            .find(|(lib_name, _)| *lib_name == name)
+            .ok_or(BundleError::MissingLib(name.to_string()))?;
+        postcard::from_bytes(data).map_err(|error| BundleError::Encoding(error.to_string()))
+    }
+}
+
+impl BundleWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Encodes a lib into the bundle. The lib must not skip serializing any of
+    // its fields, since the encoding doesn't store field names.
+    pub fn add<T: Serialize>(&mut self, name: &str, lib: &T) -> Result<(), BundleError> {
+        let data =
+            postcard::to_allocvec(lib).map_err(|error| BundleError::Encoding(error.to_string()))?;
+        self.libs.push((name.to_string(), data));
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BundleError> {
+        let libs: Vec<(&str, &[u8])> = self
+            .libs
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let body = postcard::to_allocvec(&libs)
+            .map_err(|error| BundleError::Encoding(error.to_string()))?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(&BUNDLE_MAGIC);
+        bytes.extend_from_slice(&BUNDLE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&content_hash(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    pub fn write(&self, path: &str) -> Result<BundleHeader, BundleError> {
+        let bytes = self.to_bytes()?;
+        fs::write(path, &bytes).map_err(|error| BundleError::Io(format!("{}: {}", path, error)))?;
+        Ok(BundleHeader {
+            version: BUNDLE_VERSION,
+            hash: content_hash(&bytes[HEADER_LEN..]),
+        })
+    }
+}
+
+// 64 bit FNV-1a, which is stable across platforms and Rust versions.
+fn content_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Def {
+        id: u32,
+        name: String,
+        weight: f32,
+        tags: Vec<String>,
+    }
+
+    fn bundle_bytes() -> Vec<u8> {
+        let defs = vec![
+            Def {
+                id: 0,
+                name: "Red Potion".to_string(),
+                weight: 0.5,
+                tags: vec!["potion".to_string()],
+            },
+            Def {
+                id: 1,
+                name: "Shoe".to_string(),
+                weight: 2.0,
+                tags: vec![],
+            },
+        ];
+        let mut writer = BundleWriter::new();
+        writer.add("item", &(2u32, &defs)).unwrap();
+        writer.add("aura", &(0u32, Vec::<Def>::new())).unwrap();
+        writer.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn asset_bundle_round_trip() {
+        let bytes = bundle_bytes();
+        let bundle = AssetBundle::read(&bytes).unwrap();
+        assert_eq!(bundle.header.version, BUNDLE_VERSION);
+        assert_eq!(bundle.names().collect::<Vec<_>>(), vec!["item", "aura"]);
+        let (next_id, defs): (u32, Vec<Def>) = bundle.lib("item").unwrap();
+        assert_eq!(next_id, 2);
+        assert_eq!(defs[0].name, "Red Potion");
+        assert_eq!(defs[1].weight, 2.0);
+        assert_eq!(
+            bundle.lib::<(u32, Vec<Def>)>("ability").unwrap_err(),
+            BundleError::MissingLib("ability".to_string())
+        );
+    }
+
+    #[test]
+    fn asset_bundle_header_checks() {
+        let mut bytes = bundle_bytes();
+        assert_eq!(
+            AssetBundle::read(b"(next_id: 0)").unwrap_err(),
+            BundleError::NotABundle
+        );
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert_eq!(AssetBundle::read(&bytes).unwrap_err(), BundleError::Corrupt);
+
+        bytes[4] = 9;
+        assert_eq!(
+            AssetBundle::read(&bytes).unwrap_err(),
+            BundleError::WrongVersion(9)
+        );
+    }
+}
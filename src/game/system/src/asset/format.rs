@@ -0,0 +1,174 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// A file format that asset libs can be loaded from and saved to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AssetFormat {
+    #[default]
+    Ron,
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl AssetFormat {
+    pub const ALL: [AssetFormat; 4] = [
+        AssetFormat::Ron,
+        AssetFormat::Json,
+        AssetFormat::Toml,
+        AssetFormat::Yaml,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AssetFormat::Ron => "ron",
+            AssetFormat::Json => "json",
+            AssetFormat::Toml => "toml",
+            AssetFormat::Yaml => "yaml",
+        }
+    }
+
+    // The format of a file, by its extension.
+    pub fn from_path(path: &str) -> Option<AssetFormat> {
+        Path::new(path).extension()?.to_str()?.parse().ok()
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, data: &str) -> Result<T, String> {
+        match self {
+            AssetFormat::Ron => ron::from_str(data).map_err(|error| error.to_string()),
+            AssetFormat::Json => serde_json::from_str(data).map_err(|error| error.to_string()),
+            AssetFormat::Toml => toml::from_str(data).map_err(|error| error.to_string()),
+            AssetFormat::Yaml => serde_yaml::from_str(data).map_err(|error| error.to_string()),
+        }
+    }
+
+    pub fn to_string_pretty<T: Serialize>(&self, value: &T) -> Result<String, String> {
+        match self {
+            AssetFormat::Ron => {
+                ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                    .map_err(|error| error.to_string())
+            }
+            AssetFormat::Json => {
+                serde_json::to_string_pretty(value).map_err(|error| error.to_string())
+            }
+            AssetFormat::Toml => toml::to_string_pretty(value).map_err(|error| error.to_string()),
+            AssetFormat::Yaml => serde_yaml::to_string(value).map_err(|error| error.to_string()),
+        }
+    }
+}
+
+impl FromStr for AssetFormat {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<AssetFormat, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "ron" => Ok(AssetFormat::Ron),
+            "json" => Ok(AssetFormat::Json),
+            "toml" => Ok(AssetFormat::Toml),
+            "yaml" | "yml" => Ok(AssetFormat::Yaml),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for AssetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension().to_uppercase())
+    }
+}
+
+// Reads an asset file in the format given by its extension. Files with an
+// unknown extension are read as RON.
+pub fn read_asset_file<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let format = AssetFormat::from_path(path).unwrap_or_default();
+    let data = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    format
+        .parse(&data)
+        .map_err(|error| format!("{} was not well-formatted: {}", path, error))
+}
+
+// Writes an asset file in the format given by its extension, replacing it.
+// The file is left as it was if the value can't be written in the format,
+// such as TOML, which has no way to write some values.
+pub fn write_asset_file<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    let format = AssetFormat::from_path(path).unwrap_or_default();
+    let data = format
+        .to_string_pretty(value)
+        .map_err(|error| format!("{}: {}", path, error))?;
+    fs::write(path, data).map_err(|error| format!("{}: {}", path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Plain,
+        Fancy,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        kind: Kind,
+        weight: f32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Lib {
+        next_id: u32,
+        names: BTreeMap<String, String>,
+        entries: Vec<Entry>,
+    }
+
+    #[test]
+    fn asset_format_round_trip() {
+        let lib = Lib {
+            next_id: 2,
+            names: BTreeMap::from([("a".to_string(), "b / 2".to_string())]),
+            entries: vec![
+                Entry {
+                    kind: Kind::Plain,
+                    weight: 0.1,
+                },
+                Entry {
+                    kind: Kind::Fancy,
+                    weight: 3600.0,
+                },
+            ],
+        };
+        for format in AssetFormat::ALL {
+            let data = format.to_string_pretty(&lib).unwrap();
+            assert_eq!(format.parse::<Lib>(&data).as_ref(), Ok(&lib), "{}", format);
+        }
+    }
+
+    #[test]
+    fn asset_format_from_path() {
+        assert_eq!(
+            AssetFormat::from_path("asset/def/item.json"),
+            Some(AssetFormat::Json)
+        );
+        assert_eq!(AssetFormat::from_path("item.YML"), Some(AssetFormat::Yaml));
+        assert_eq!(AssetFormat::from_path("item"), None);
+        assert_eq!("toml".parse(), Ok(AssetFormat::Toml));
+    }
+
+    #[test]
+    fn write_asset_file_errors() {
+        let path = std::env::temp_dir().join(format!("write_asset_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_asset_file(path, &BTreeMap::from([("next_id", 2)])).unwrap();
+        // TOML files can't hold a bare list, and the file is left as it was.
+        assert!(write_asset_file(path, &vec![1, 2]).is_err());
+        assert_eq!(fs::read_to_string(path).unwrap(), "next_id = 2\n");
+        assert!(write_asset_file("no/such/dir/item.ron", &vec![1, 2]).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
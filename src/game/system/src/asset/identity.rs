@@ -0,0 +1,494 @@
+use crate::asset::asset_lib::AssetType;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+// Makes a guid for a new def. These are version 7 UUIDs, which like ULIDs
+// start with the time they were made, so they sort by age.
+pub fn new_guid() -> String {
+    Uuid::now_v7().to_string()
+}
+
+// A def's numeric id or its guid, either of which can be used to look it up.
+// Ids are only unique within a branch, while guids are unique everywhere.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AssetKey {
+    Id(u32),
+    Guid(String),
+}
+
+// A field of a def that holds the id of another def. Paths are field names
+// separated by `.`, where `field[]` stands for each entry of a list. `when`
+// limits the reference to entries where a sibling field has a value, for
+// fields that can refer to different asset types.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct AssetReference {
+    pub path: &'static str,
+    pub to: AssetType,
+    pub when: Option<(&'static str, &'static str)>,
+}
+
+// One asset lib after a merge, along with the lib on each side of the merge,
+// all as values (see `AssetLib::to_value`). Sides that didn't have the lib
+// are `Value::Null`.
+#[derive(Debug, Clone)]
+pub struct MergedLib {
+    pub asset_type: AssetType,
+    pub references: &'static [AssetReference],
+    pub lib: Value,
+    pub ours: Value,
+    pub theirs: Value,
+}
+
+// A def given a new id because another def had the same one.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenumberedDef {
+    pub asset_type: AssetType,
+    pub name: String,
+    pub old_id: u32,
+    pub new_id: u32,
+}
+
+// A reference to a renumbered id, in the def of `asset_type` named `name`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReferenceChange {
+    pub asset_type: AssetType,
+    pub name: String,
+    pub path: &'static str,
+    pub old_id: u32,
+    pub new_id: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RenumberReport {
+    pub renumbered: Vec<RenumberedDef>,
+    pub rewritten: Vec<ReferenceChange>,
+    // References in defs that were changed on both sides of the merge, so it
+    // isn't known which def they mean. These are left alone to be checked.
+    pub ambiguous: Vec<ReferenceChange>,
+}
+
+// Which side of a merge a def came from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum Side {
+    Ours,
+    Theirs,
+    // On both sides, neither, or changed by the merge.
+    Unknown,
+}
+
+impl FromStr for AssetKey {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<AssetKey, Self::Err> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(());
+        }
+        Ok(match input.parse() {
+            Ok(id) => AssetKey::Id(id),
+            Err(_) => AssetKey::Guid(input.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for AssetKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetKey::Id(id) => write!(f, "{}", id),
+            AssetKey::Guid(guid) => write!(f, "{}", guid),
+        }
+    }
+}
+
+impl RenumberReport {
+    pub fn is_empty(&self) -> bool {
+        self.renumbered.is_empty() && self.rewritten.is_empty() && self.ambiguous.is_empty()
+    }
+}
+
+impl fmt::Display for RenumberReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No ids collide");
+        }
+        let mut lines = vec![];
+        for def in &self.renumbered {
+            lines.push(format!(
+                "Renumbered {} \"{}\" from {} to {}",
+                def.asset_type, def.name, def.old_id, def.new_id
+            ));
+        }
+        for change in &self.rewritten {
+            lines.push(format!(
+                "Changed {} \"{}\" {} from {} to {}",
+                change.asset_type, change.name, change.path, change.old_id, change.new_id
+            ));
+        }
+        for change in &self.ambiguous {
+            lines.push(format!(
+                "Check {} \"{}\" {}: {} may mean {}",
+                change.asset_type, change.name, change.path, change.old_id, change.new_id
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+// Gives every def in a lib without a guid a new one, returning how many were
+// given one.
+pub fn assign_guids(lib: &mut Value) -> usize {
+    let Some(Value::Array(defs)) = lib.get_mut("defs") else {
+        return 0;
+    };
+    let mut count = 0;
+    for def in defs {
+        if let Value::Object(fields) = def {
+            if fields
+                .get("guid")
+                .and_then(Value::as_str)
+                .unwrap_or("")
+                .is_empty()
+            {
+                fields.insert("guid".to_string(), Value::String(new_guid()));
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+// Renumbers defs that share an id after a merge, which happens when defs are
+// added on two branches from the same `next_id`. Of the defs with an id, ours
+// keeps it and the others get new ids. References in defs from their side are
+// then changed to the new ids.
+pub fn renumber_collisions(libs: &mut [MergedLib]) -> RenumberReport {
+    let mut report = RenumberReport::default();
+    // Sides are worked out before anything changes, since they compare defs
+    // to the ones on each side.
+    let sides: Vec<Vec<Side>> = libs
+        .iter()
+        .map(|lib| {
+            defs(&lib.lib)
+                .iter()
+                .map(|def| side(def, defs(&lib.ours), defs(&lib.theirs)))
+                .collect()
+        })
+        .collect();
+    // The renumbered defs, along with which side they came from.
+    let mut renumbered: Vec<(RenumberedDef, Side)> = vec![];
+    for (lib, sides) in libs.iter_mut().zip(&sides) {
+        let next_id = lib.lib.get("next_id").and_then(Value::as_u64);
+        let Some(Value::Array(defs)) = lib.lib.get_mut("defs") else {
+            continue;
+        };
+        let mut by_id: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+        for (i, def) in defs.iter().enumerate() {
+            if let Some(id) = def_id(def) {
+                by_id.entry(id).or_default().push(i);
+            }
+        }
+        let max_id = by_id.keys().next_back().map_or(0, |id| id + 1);
+        let mut next_id = next_id.map_or(0, |id| id as u32).max(max_id);
+        for (id, indices) in by_id.into_iter().filter(|(_, indices)| indices.len() > 1) {
+            let keep = indices
+                .iter()
+                .position(|i| sides[*i] == Side::Ours)
+                .unwrap_or(0);
+            for (n, i) in indices.into_iter().enumerate() {
+                if n == keep {
+                    continue;
+                }
+                defs[i]["id"] = Value::from(next_id);
+                let def = RenumberedDef {
+                    asset_type: lib.asset_type,
+                    name: def_name(&defs[i]),
+                    old_id: id,
+                    new_id: next_id,
+                };
+                renumbered.push((def, sides[i]));
+                next_id += 1;
+            }
+        }
+        lib.lib["next_id"] = Value::from(next_id);
+    }
+    report.renumbered = renumbered.iter().map(|(def, _)| def.clone()).collect();
+    if renumbered.is_empty() {
+        return report;
+    }
+
+    for (lib, sides) in libs.iter_mut().zip(&sides) {
+        let asset_type = lib.asset_type;
+        let Some(Value::Array(defs)) = lib.lib.get_mut("defs") else {
+            continue;
+        };
+        for (def, def_side) in defs.iter_mut().zip(sides) {
+            let name = def_name(def);
+            for reference in lib.references {
+                let path: Vec<&str> = reference.path.split('.').collect();
+                for_each_reference(def, &path, reference.when, &mut |value| {
+                    let Some(id) = value.as_u64().map(|id| id as u32) else {
+                        return;
+                    };
+                    let candidates = renumbered
+                        .iter()
+                        .filter(|(def, _)| def.asset_type == reference.to && def.old_id == id);
+                    for (renumbered_def, renumbered_side) in candidates {
+                        let change = ReferenceChange {
+                            asset_type,
+                            name: name.clone(),
+                            path: reference.path,
+                            old_id: id,
+                            new_id: renumbered_def.new_id,
+                        };
+                        if *def_side == Side::Unknown {
+                            report.ambiguous.push(change);
+                        } else if def_side == renumbered_side {
+                            *value = Value::from(renumbered_def.new_id);
+                            report.rewritten.push(change);
+                            return;
+                        }
+                    }
+                });
+            }
+        }
+    }
+    report
+}
+
+fn defs(lib: &Value) -> &[Value] {
+    match lib.get("defs") {
+        Some(Value::Array(defs)) => defs,
+        _ => &[],
+    }
+}
+
+fn def_id(def: &Value) -> Option<u32> {
+    def.get("id").and_then(Value::as_u64).map(|id| id as u32)
+}
+
+fn def_name(def: &Value) -> String {
+    def.get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+// A def is from one side if it's the same as a def on that side but not the
+// other. Defs edited while resolving the merge are matched by guid instead.
+fn side(def: &Value, ours: &[Value], theirs: &[Value]) -> Side {
+    let side_of = |in_ours: bool, in_theirs: bool| match (in_ours, in_theirs) {
+        (true, false) => Side::Ours,
+        (false, true) => Side::Theirs,
+        _ => Side::Unknown,
+    };
+    let by_value = side_of(ours.contains(def), theirs.contains(def));
+    let guid = def.get("guid").and_then(Value::as_str).unwrap_or("");
+    if by_value != Side::Unknown || guid.is_empty() {
+        return by_value;
+    }
+    let has_guid = |defs: &[Value]| {
+        defs.iter()
+            .any(|other| other.get("guid").and_then(Value::as_str) == Some(guid))
+    };
+    side_of(has_guid(ours), has_guid(theirs))
+}
+
+// Calls `visit` with each id at `path` in a def.
+fn for_each_reference(
+    value: &mut Value,
+    path: &[&str],
+    when: Option<(&str, &str)>,
+    visit: &mut dyn FnMut(&mut Value),
+) {
+    let Some((first, rest)) = path.split_first() else {
+        visit(value);
+        return;
+    };
+    let Value::Object(fields) = value else {
+        return;
+    };
+    if let (true, Some((field, variant))) = (rest.is_empty(), when) {
+        if fields.get(field).and_then(Value::as_str) != Some(variant) {
+            return;
+        }
+    }
+    match first.strip_suffix("[]") {
+        Some(list) => {
+            if let Some(Value::Array(entries)) = fields.get_mut(list) {
+                for entry in entries {
+                    for_each_reference(entry, rest, when, visit);
+                }
+            }
+        }
+        None => {
+            if let Some(field) = fields.get_mut(*first) {
+                for_each_reference(field, rest, when, visit);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CHARACTER_REFERENCES: &[AssetReference] = &[
+        AssetReference {
+            path: "equipment[].item_id",
+            to: AssetType::Item,
+            when: None,
+        },
+        AssetReference {
+            path: "auras[]",
+            to: AssetType::Aura,
+            when: None,
+        },
+    ];
+    const LOOT_TABLE_REFERENCES: &[AssetReference] = &[AssetReference {
+        path: "entries[].id",
+        to: AssetType::Item,
+        when: Some(("entry_type", "Item")),
+    }];
+
+    fn merged_lib(
+        asset_type: AssetType,
+        references: &'static [AssetReference],
+        lib: Value,
+        ours: Value,
+        theirs: Value,
+    ) -> MergedLib {
+        MergedLib {
+            asset_type,
+            references,
+            lib,
+            ours,
+            theirs,
+        }
+    }
+
+    #[test]
+    fn asset_key_from_str() {
+        assert_eq!("12".parse(), Ok(AssetKey::Id(12)));
+        assert_eq!(
+            " 0190-abc ".parse(),
+            Ok(AssetKey::Guid("0190-abc".to_string()))
+        );
+        assert_eq!("".parse::<AssetKey>(), Err(()));
+        assert_ne!(new_guid(), new_guid());
+    }
+
+    #[test]
+    fn assign_missing_guids() {
+        let mut lib =
+            json!({ "defs": [{ "id": 0 }, { "id": 1, "guid": "a" }, { "id": 2, "guid": "" }] });
+        assert_eq!(assign_guids(&mut lib), 2);
+        assert_eq!(lib["defs"][1]["guid"], "a");
+        assert_eq!(lib["defs"][2]["guid"].as_str().unwrap().len(), 36);
+        assert_eq!(assign_guids(&mut lib), 0);
+    }
+
+    #[test]
+    fn renumber_colliding_ids() {
+        let sword = json!({ "id": 2, "name": "Sword", "guid": "s" });
+        let shield = json!({ "id": 2, "name": "Shield", "guid": "h" });
+        let base_items = vec![json!({ "id": 0, "name": "Potion" })];
+        let items = |new: &[&Value]| {
+            let mut defs = base_items.clone();
+            defs.extend(new.iter().map(|def| (*def).clone()));
+            json!({ "next_id": 3, "defs": defs })
+        };
+        // Their shield was edited while resolving the merge, so it's matched
+        // by guid.
+        let mut merged_shield = shield.clone();
+        merged_shield["name"] = json!("Big Shield");
+
+        let goblin =
+            json!({ "id": 0, "name": "Goblin", "equipment": [{ "item_id": 2 }], "auras": [] });
+        let orc =
+            json!({ "id": 1, "name": "Orc", "equipment": [{ "item_id": 2 }, { "item_id": 0 }] });
+        let mut both = orc.clone();
+        both["equipment"][1]["item_id"] = json!(2);
+        let table = json!({ "id": 0, "name": "Chest", "entries": [
+            { "entry_type": "Item", "id": 2 },
+            { "entry_type": "Table", "id": 2 },
+        ] });
+
+        let mut libs = vec![
+            merged_lib(
+                AssetType::Item,
+                &[],
+                items(&[&sword, &merged_shield]),
+                items(&[&sword]),
+                items(&[&shield]),
+            ),
+            merged_lib(
+                AssetType::Character,
+                CHARACTER_REFERENCES,
+                json!({ "next_id": 2, "defs": [goblin, both] }),
+                json!({ "next_id": 1, "defs": [goblin] }),
+                json!({ "next_id": 2, "defs": [orc] }),
+            ),
+            merged_lib(
+                AssetType::LootTable,
+                LOOT_TABLE_REFERENCES,
+                json!({ "next_id": 1, "defs": [table] }),
+                Value::Null,
+                json!({ "next_id": 1, "defs": [table] }),
+            ),
+        ];
+        let report = renumber_collisions(&mut libs);
+
+        assert_eq!(libs[0].lib["next_id"], 4);
+        assert_eq!(libs[0].lib["defs"][1]["id"], 2);
+        assert_eq!(libs[0].lib["defs"][2]["id"], 3);
+        assert_eq!(
+            report.renumbered,
+            vec![RenumberedDef {
+                asset_type: AssetType::Item,
+                name: "Big Shield".to_string(),
+                old_id: 2,
+                new_id: 3,
+            }]
+        );
+        // Our goblin keeps our sword, their chest gets their shield, and the
+        // orc was changed on both sides so is left to be checked.
+        assert_eq!(libs[1].lib["defs"][0]["equipment"][0]["item_id"], 2);
+        assert_eq!(libs[2].lib["defs"][0]["entries"][0]["id"], 3);
+        assert_eq!(libs[2].lib["defs"][0]["entries"][1]["id"], 2);
+        assert_eq!(
+            report.rewritten,
+            vec![ReferenceChange {
+                asset_type: AssetType::LootTable,
+                name: "Chest".to_string(),
+                path: "entries[].id",
+                old_id: 2,
+                new_id: 3,
+            }]
+        );
+        assert_eq!(report.ambiguous.len(), 2);
+        assert_eq!(
+            report.to_string().lines().last(),
+            Some("Check Character \"Orc\" equipment[].item_id: 2 may mean 3")
+        );
+    }
+
+    #[test]
+    fn renumber_without_collisions() {
+        let lib = json!({ "next_id": 1, "defs": [{ "id": 0 }] });
+        let mut libs = vec![merged_lib(
+            AssetType::Item,
+            &[],
+            lib.clone(),
+            lib.clone(),
+            lib.clone(),
+        )];
+        let report = renumber_collisions(&mut libs);
+        assert!(report.is_empty());
+        assert_eq!(report.to_string(), "No ids collide");
+        assert_eq!(libs[0].lib, lib);
+    }
+}
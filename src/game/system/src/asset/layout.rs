@@ -0,0 +1,347 @@
+use crate::asset::asset_lib::AssetDef;
+use crate::asset::format::{write_asset_file, AssetFormat};
+use crate::asset::value::parse_asset_value;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// The name, without its extension, of the file that holds everything in a
+// directory lib other than its defs.
+pub const LIB_FILE_STEM: &str = "lib";
+
+// How an asset lib is laid out on disk.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AssetLayout {
+    // One file with every def, e.g. `asset/def/item.ron`.
+    #[default]
+    File,
+    // A directory with a file per def, e.g. `asset/def/item/4-iron-bar.ron`,
+    // along with `lib.ron` for the lib's version, next id and formulas. Defs
+    // are changed without touching each other's files, so they don't conflict
+    // when merged.
+    Directory,
+}
+
+// How asset libs are stored: in which format, and in which layout.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssetStorage {
+    pub format: AssetFormat,
+    pub layout: AssetLayout,
+}
+
+impl AssetStorage {
+    pub fn lib_path(&self, dir: &str, name: &str) -> String {
+        self.layout.lib_path(dir, name, self.format)
+    }
+}
+
+impl AssetLayout {
+    pub const ALL: [AssetLayout; 2] = [AssetLayout::File, AssetLayout::Directory];
+
+    // Where the lib with the name is stored in `dir`. Directory libs are
+    // opened by the path of their lib file.
+    pub fn lib_path(&self, dir: &str, name: &str, format: AssetFormat) -> String {
+        match self {
+            AssetLayout::File => format!("{}/{}.{}", dir, name, format.extension()),
+            AssetLayout::Directory => {
+                format!("{}/{}/{}.{}", dir, name, LIB_FILE_STEM, format.extension())
+            }
+        }
+    }
+
+    // The layout of the lib stored at a path.
+    pub fn from_path(path: &str) -> AssetLayout {
+        let path = Path::new(path);
+        let in_dir = path.parent().is_some_and(|dir| !dir.as_os_str().is_empty());
+        if in_dir && path.file_stem().and_then(|stem| stem.to_str()) == Some(LIB_FILE_STEM) {
+            AssetLayout::Directory
+        } else {
+            AssetLayout::File
+        }
+    }
+}
+
+impl FromStr for AssetLayout {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<AssetLayout, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "file" => Ok(AssetLayout::File),
+            "directory" | "dir" => Ok(AssetLayout::Directory),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for AssetLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// The name of a def's file in a directory lib, from its id and name, e.g.
+// `4-iron-bar`.
+pub fn def_file_stem(id: u32, name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().filter(|c| !matches!(c, '\'' | '"')) {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        id.to_string()
+    } else {
+        format!("{}-{}", id, slug)
+    }
+}
+
+// A lib's files as they are stored, before they're read: the lib file, and in
+// the directory layout the def files beside it, as `(file name, contents)`
+// ordered by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibFiles {
+    pub format: AssetFormat,
+    pub lib: String,
+    pub defs: Vec<(String, String)>,
+}
+
+impl LibFiles {
+    // Reads the files of the lib at `path`, in the format and layout given by
+    // the path. Directory libs are opened by the path of their lib file.
+    pub fn read(path: &str) -> Result<LibFiles, String> {
+        let format = AssetFormat::from_path(path).unwrap_or_default();
+        let read = |path: &Path| {
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))
+        };
+        let lib = read(Path::new(path))?;
+        if AssetLayout::from_path(path) == AssetLayout::File {
+            return Ok(LibFiles::from_file(format, lib));
+        }
+        let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+        let mut files = vec![];
+        for entry in fs::read_dir(dir).map_err(|error| format!("{}: {}", dir.display(), error))? {
+            let entry = entry.map_err(|error| error.to_string())?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_file() && is_def_file(format, &name) {
+                files.push((name, read(&entry.path())?));
+            }
+        }
+        Ok(LibFiles::from_dir(format, lib, files))
+    }
+
+    // A lib stored in one file.
+    pub fn from_file(format: AssetFormat, lib: String) -> LibFiles {
+        LibFiles {
+            format,
+            lib,
+            defs: vec![],
+        }
+    }
+
+    // A directory lib, from its lib file and the files in its directory, such
+    // as ones read from git. Files that don't hold a def are left out.
+    pub fn from_dir(format: AssetFormat, lib: String, files: Vec<(String, String)>) -> LibFiles {
+        let mut defs: Vec<(String, String)> = files
+            .into_iter()
+            .filter(|(name, _)| is_def_file(format, name))
+            .collect();
+        defs.sort();
+        LibFiles { format, lib, defs }
+    }
+
+    // The files read into one value laid out like a single file lib, with
+    // defs ordered by id. This is only for migrating older libs, as values
+    // read from RON lose some of what the files hold (see `parse_asset_value`).
+    pub(crate) fn to_value(&self) -> Result<Value, String> {
+        let mut lib = parse_asset_value(self.format, &self.lib)?;
+        if self.defs.is_empty() {
+            return Ok(lib);
+        }
+        let mut defs = vec![];
+        for (name, data) in &self.defs {
+            let def = parse_asset_value(self.format, data)
+                .map_err(|error| format!("{}: {}", name, error))?;
+            defs.push((def.get("id").and_then(Value::as_u64), def));
+        }
+        defs.sort_by_key(|(id, _)| *id);
+        let Value::Object(fields) = &mut lib else {
+            return Err("The lib file should hold a struct".to_string());
+        };
+        fields.insert(
+            "defs".to_string(),
+            Value::Array(defs.into_iter().map(|(_, def)| def).collect()),
+        );
+        Ok(lib)
+    }
+}
+
+// Whether a file in a directory lib, by its name, holds a def.
+fn is_def_file(format: AssetFormat, name: &str) -> bool {
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|stem| stem.to_str());
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    stem != Some(LIB_FILE_STEM) && extension == Some(format.extension())
+}
+
+// Writes a lib in the layout given by its path. In the directory layout,
+// `defs` takes the defs out of the lib, which is written to the lib file, and
+// each def is written to its own file. Only files whose contents change are
+// written, and the files of defs that are gone are removed. Nothing is written
+// unless every file can be written in the format.
+pub fn write_asset_lib<L: Serialize, D: Serialize + AssetDef>(
+    path: &str,
+    lib: &mut L,
+    defs: impl FnOnce(&mut L) -> &mut Vec<D>,
+) -> Result<(), String> {
+    if AssetLayout::from_path(path) == AssetLayout::File {
+        return write_asset_file(path, lib);
+    }
+    let format = AssetFormat::from_path(path).unwrap_or_default();
+    let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let io_error = |path: &Path, error: std::io::Error| format!("{}: {}", path.display(), error);
+    let defs = std::mem::take(defs(lib));
+    let mut files = vec![(
+        Path::new(path).to_path_buf(),
+        format
+            .to_string_pretty(lib)
+            .map_err(|error| format!("{}: {}", path, error))?,
+    )];
+    for def in &defs {
+        let name = format!(
+            "{}.{}",
+            def_file_stem(def.id(), def.name()),
+            format.extension()
+        );
+        let data = format
+            .to_string_pretty(def)
+            .map_err(|error| format!("{}: {}", name, error))?;
+        files.push((dir.join(name), data));
+    }
+    fs::create_dir_all(dir).map_err(|error| io_error(dir, error))?;
+    for entry in fs::read_dir(dir)
+        .map_err(|error| io_error(dir, error))?
+        .flatten()
+    {
+        let stale = entry
+            .path()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            == Some(format.extension())
+            && !files.iter().any(|(path, _)| *path == entry.path());
+        if stale {
+            fs::remove_file(entry.path()).map_err(|error| io_error(&entry.path(), error))?;
+        }
+    }
+    for (path, data) in files {
+        if fs::read_to_string(&path).ok().as_ref() != Some(&data) {
+            fs::write(&path, data).map_err(|error| io_error(&path, error))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_reflect::Reflect;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize)]
+    struct Lib {
+        next_id: u32,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        defs: Vec<Def>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Reflect)]
+    struct Def {
+        id: u32,
+        name: String,
+    }
+
+    impl AssetDef for Def {
+        fn id(&self) -> u32 {
+            self.id
+        }
+
+        fn guid(&self) -> &str {
+            ""
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn icon(&self) -> &str {
+            ""
+        }
+    }
+
+    #[test]
+    fn asset_layout_paths() {
+        let path = AssetLayout::Directory.lib_path("asset/def", "item", AssetFormat::Ron);
+        assert_eq!(path, "asset/def/item/lib.ron");
+        assert_eq!(AssetLayout::from_path(&path), AssetLayout::Directory);
+        let path = AssetLayout::File.lib_path("asset/def", "item", AssetFormat::Json);
+        assert_eq!(path, "asset/def/item.json");
+        assert_eq!(AssetLayout::from_path(&path), AssetLayout::File);
+        assert_eq!(AssetLayout::from_path("lib.ron"), AssetLayout::File);
+        assert_eq!("dir".parse(), Ok(AssetLayout::Directory));
+
+        assert_eq!(def_file_stem(4, "Iron Bar"), "4-iron-bar");
+        assert_eq!(
+            def_file_stem(0, "Cheesemonger's Stall!"),
+            "0-cheesemongers-stall"
+        );
+        assert_eq!(def_file_stem(7, "??"), "7");
+    }
+
+    #[test]
+    fn asset_dir_round_trip() {
+        let dir = std::env::temp_dir().join(format!("asset_dir_{}", std::process::id()));
+        let path = dir.join("lib.json");
+        let path = path.to_str().unwrap();
+        let def = |id: u32, name: &str| Def {
+            id,
+            name: name.to_string(),
+        };
+        let mut lib = Lib {
+            next_id: 11,
+            defs: vec![def(10, "Shoe"), def(2, "Red Potion")],
+        };
+        write_asset_lib(path, &mut lib, |lib| &mut lib.defs).unwrap();
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["10-shoe.json", "2-red-potion.json", "lib.json"]);
+        assert_eq!(
+            LibFiles::read(path).and_then(|files| files.to_value()),
+            Ok(json!({
+                "next_id": 11,
+                "defs": [{ "id": 2, "name": "Red Potion" }, { "id": 10, "name": "Shoe" }],
+            }))
+        );
+
+        // Unchanged defs aren't written again, and renamed ones move.
+        let shoe = dir.join("10-shoe.json");
+        let modified = fs::metadata(&shoe).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mut lib = Lib {
+            next_id: 11,
+            defs: vec![def(10, "Shoe"), def(2, "Blue Potion")],
+        };
+        write_asset_lib(path, &mut lib, |lib| &mut lib.defs).unwrap();
+        assert_eq!(fs::metadata(&shoe).unwrap().modified().unwrap(), modified);
+        assert!(dir.join("2-blue-potion.json").exists());
+        assert!(!dir.join("2-red-potion.json").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,415 @@
+use serde_json::{Map, Value};
+use std::fmt;
+
+// Which side's version of a conflicting field a merge keeps.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MergeSide {
+    Ours,
+    Theirs,
+}
+
+// A field that both sides of a three-way merge changed, to different values.
+// `id` and `name` are those of the def it's in, or `None` and empty for fields
+// of the lib itself, such as formulas. `path` is field names separated by `.`
+// from the def down, and is empty when the whole def conflicts, e.g. when one
+// side removed it and the other changed it. Values are `None` on sides
+// without the field.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MergeConflict {
+    pub id: Option<u32>,
+    pub name: String,
+    pub path: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+    // The side kept when the merge is resolved.
+    pub take: MergeSide,
+}
+
+// An asset lib merged from two versions of a common base, all read as values
+// (see `AssetLib::to_value`). Conflicting fields hold our values until the merge
+// is resolved.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LibMerge {
+    pub lib: Value,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl fmt::Display for MergeSide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{} {}", id, self.name)?,
+            None => write!(f, "The lib")?,
+        }
+        if !self.path.is_empty() {
+            write!(f, " {}", self.path)?;
+        }
+        write!(
+            f,
+            ": ours {}, theirs {} (base {})",
+            merge_value_text(self.ours.as_ref()),
+            merge_value_text(self.theirs.as_ref()),
+            merge_value_text(self.base.as_ref())
+        )
+    }
+}
+
+impl LibMerge {
+    // The merged lib, with the side each conflict takes.
+    pub fn resolve(self) -> Value {
+        let mut lib = self.lib;
+        for conflict in &self.conflicts {
+            if conflict.take == MergeSide::Theirs {
+                set_conflict_value(&mut lib, conflict, conflict.theirs.clone());
+            }
+        }
+        lib
+    }
+}
+
+// How a merged value is shown: strings without quotes, and `(none)` for a
+// missing value.
+pub fn merge_value_text(value: Option<&Value>) -> String {
+    match value {
+        None => "(none)".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+// Merges the changes `ours` and `theirs` each made to `base`. Defs are matched
+// by id, and each field takes the side that changed it. The next id is the
+// larger of the two, so new defs on either side keep their ids. Defs that both
+// sides added with the same id conflict as a whole.
+pub fn merge_libs(base: &Value, ours: &Value, theirs: &Value) -> LibMerge {
+    let mut conflicts = vec![];
+    let mut lib = Map::new();
+    for key in union_keys([base, ours, theirs]) {
+        let [base_field, ours_field, theirs_field] = [base, ours, theirs].map(|lib| lib.get(&key));
+        let merged = match key.as_str() {
+            "defs" => Some(Value::Array(merge_defs(
+                base_field,
+                ours_field,
+                theirs_field,
+                &mut conflicts,
+            ))),
+            "next_id" => ours_field
+                .and_then(Value::as_u64)
+                .max(theirs_field.and_then(Value::as_u64))
+                .map(Value::from),
+            _ => merge_value(
+                base_field,
+                ours_field,
+                theirs_field,
+                &key,
+                &mut |path, base, ours, theirs| {
+                    conflicts.push(MergeConflict {
+                        id: None,
+                        name: String::new(),
+                        path,
+                        base,
+                        ours,
+                        theirs,
+                        take: MergeSide::Ours,
+                    })
+                },
+            ),
+        };
+        if let Some(merged) = merged {
+            lib.insert(key, merged);
+        }
+    }
+    LibMerge {
+        lib: Value::Object(lib),
+        conflicts,
+    }
+}
+
+// The keys of each value that's a map, in order of first appearance.
+fn union_keys<const N: usize>(values: [&Value; N]) -> Vec<String> {
+    let mut keys: Vec<String> = vec![];
+    for value in values {
+        if let Value::Object(fields) = value {
+            for key in fields.keys() {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+    }
+    keys
+}
+
+fn merge_defs(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Vec<Value> {
+    let defs = |defs: Option<&Value>| match defs {
+        Some(Value::Array(defs)) => defs.clone(),
+        _ => vec![],
+    };
+    let (base, ours, theirs) = (defs(base), defs(ours), defs(theirs));
+    let find = |defs: &[Value], id: u64| {
+        defs.iter()
+            .find(|def| def.get("id").and_then(Value::as_u64) == Some(id))
+            .cloned()
+    };
+    let mut ids = vec![];
+    for def in ours.iter().chain(&theirs).chain(&base) {
+        if let Some(id) = def.get("id").and_then(Value::as_u64) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    let mut merged = vec![];
+    for id in ids {
+        let [base_def, ours_def, theirs_def] = [&base, &ours, &theirs].map(|defs| find(defs, id));
+        let name = [&ours_def, &theirs_def, &base_def]
+            .into_iter()
+            .flatten()
+            .find_map(|def| def.get("name").and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string();
+        let mut conflict = |path, base, ours, theirs| {
+            conflicts.push(MergeConflict {
+                id: Some(id as u32),
+                name: name.clone(),
+                path,
+                base,
+                ours,
+                theirs,
+                take: MergeSide::Ours,
+            })
+        };
+        let def = match (&base_def, &ours_def, &theirs_def) {
+            // Two different defs that happened to get the same id.
+            (None, Some(ours), Some(theirs)) if ours != theirs => {
+                conflict(String::new(), None, ours_def.clone(), theirs_def.clone());
+                ours_def
+            }
+            _ => merge_value(
+                base_def.as_ref(),
+                ours_def.as_ref(),
+                theirs_def.as_ref(),
+                "",
+                &mut conflict,
+            ),
+        };
+        merged.extend(def);
+    }
+    merged
+}
+
+// Merges one value, calling `conflict` with the path and the values on each
+// side for every field both sides changed. Maps are merged key by key, while
+// anything else, lists included, is changed as a whole.
+fn merge_value(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    path: &str,
+    conflict: &mut dyn FnMut(String, Option<Value>, Option<Value>, Option<Value>),
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+    match (base, ours, theirs) {
+        (
+            Some(Value::Object(_)) | None,
+            Some(ours @ Value::Object(_)),
+            Some(theirs @ Value::Object(_)),
+        ) => {
+            let empty = Value::Object(Map::new());
+            let base = base.unwrap_or(&empty);
+            let mut merged = Map::new();
+            for key in union_keys([ours, theirs, base]) {
+                let field_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                let field = merge_value(
+                    base.get(&key),
+                    ours.get(&key),
+                    theirs.get(&key),
+                    &field_path,
+                    conflict,
+                );
+                if let Some(field) = field {
+                    merged.insert(key, field);
+                }
+            }
+            Some(Value::Object(merged))
+        }
+        _ => {
+            conflict(
+                path.to_string(),
+                base.cloned(),
+                ours.cloned(),
+                theirs.cloned(),
+            );
+            ours.cloned()
+        }
+    }
+}
+
+// Sets the field a conflict is about to `value`, removing it for `None`.
+fn set_conflict_value(lib: &mut Value, conflict: &MergeConflict, value: Option<Value>) {
+    let mut target = match conflict.id {
+        None => lib,
+        Some(id) => {
+            let Some(Value::Array(defs)) = lib.get_mut("defs") else {
+                return;
+            };
+            let index = defs
+                .iter()
+                .position(|def| def.get("id").and_then(Value::as_u64) == Some(id as u64));
+            match (index, conflict.path.is_empty()) {
+                (Some(index), true) => {
+                    match value {
+                        Some(value) => defs[index] = value,
+                        None => {
+                            defs.remove(index);
+                        }
+                    }
+                    return;
+                }
+                (None, true) => {
+                    defs.extend(value);
+                    return;
+                }
+                (Some(index), false) => &mut defs[index],
+                (None, false) => return,
+            }
+        }
+    };
+    let mut keys = conflict.path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Value::Object(fields) = target else {
+            return;
+        };
+        if keys.peek().is_none() {
+            match value {
+                Some(value) => fields.insert(key.to_string(), value),
+                None => fields.remove(key),
+            };
+            return;
+        }
+        let Some(field) = fields.get_mut(key) else {
+            return;
+        };
+        target = field;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_libs_by_field() {
+        let base = json!({
+            "next_id": 3,
+            "formulas": { "sell_value": "buy_value / 2" },
+            "defs": [
+                { "id": 0, "name": "Red Potion", "buy_value": 10, "tags": ["potion"] },
+                { "id": 1, "name": "Shoe", "buy_value": 5, "equipment_def": { "armor": 1 } },
+                { "id": 2, "name": "Cheese", "buy_value": 1 },
+            ],
+        });
+        let ours = json!({
+            "next_id": 4,
+            "formulas": { "sell_value": "buy_value / 2" },
+            "defs": [
+                { "id": 0, "name": "Red Potion", "buy_value": 12, "tags": ["potion"] },
+                { "id": 1, "name": "Shoe", "buy_value": 5, "equipment_def": { "armor": 2 } },
+                { "id": 3, "name": "Bread", "buy_value": 2 },
+            ],
+        });
+        let theirs = json!({
+            "next_id": 3,
+            "formulas": { "sell_value": "buy_value / 3" },
+            "defs": [
+                { "id": 0, "name": "Red Potion", "buy_value": 10, "tags": ["potion", "red"] },
+                { "id": 1, "name": "Old Shoe", "buy_value": 5, "equipment_def": { "armor": 3 } },
+                { "id": 2, "name": "Cheese", "buy_value": 1 },
+            ],
+        });
+        let merge = merge_libs(&base, &ours, &theirs);
+        assert_eq!(
+            merge.conflicts,
+            vec![MergeConflict {
+                id: Some(1),
+                name: "Shoe".to_string(),
+                path: "equipment_def.armor".to_string(),
+                base: Some(json!(1)),
+                ours: Some(json!(2)),
+                theirs: Some(json!(3)),
+                take: MergeSide::Ours,
+            }]
+        );
+        assert_eq!(
+            merge.conflicts[0].to_string(),
+            "1 Shoe equipment_def.armor: ours 2, theirs 3 (base 1)"
+        );
+        let mut merge = merge;
+        merge.conflicts[0].take = MergeSide::Theirs;
+        assert_eq!(
+            merge.resolve(),
+            json!({
+                "next_id": 4,
+                "formulas": { "sell_value": "buy_value / 3" },
+                "defs": [
+                    { "id": 0, "name": "Red Potion", "buy_value": 12, "tags": ["potion", "red"] },
+                    { "id": 1, "name": "Old Shoe", "buy_value": 5, "equipment_def": { "armor": 3 } },
+                    { "id": 3, "name": "Bread", "buy_value": 2 },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn merge_libs_whole_def_conflicts() {
+        let base = json!({ "defs": [{ "id": 0, "name": "Shoe", "buy_value": 5 }] });
+        let ours = json!({
+            "defs": [
+                { "id": 0, "name": "Shoe", "buy_value": 6 },
+                { "id": 1, "name": "Bread" },
+            ],
+        });
+        let theirs = json!({ "defs": [{ "id": 1, "name": "Cheese" }] });
+        let mut merge = merge_libs(&base, &ours, &theirs);
+        let paths: Vec<(Option<u32>, &str, bool)> = merge
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                (
+                    conflict.id,
+                    conflict.path.as_str(),
+                    conflict.theirs.is_none(),
+                )
+            })
+            .collect();
+        assert_eq!(paths, vec![(Some(0), "", true), (Some(1), "", false)]);
+        for conflict in &mut merge.conflicts {
+            conflict.take = MergeSide::Theirs;
+        }
+        assert_eq!(
+            merge.resolve(),
+            json!({ "defs": [{ "id": 1, "name": "Cheese" }] })
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use crate::asset::layout::LibFiles;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fmt;
+
+// A change to every def in a lib. Paths are field names separated by `.`,
+// where `field[]` stands for each entry of a list, e.g. `modifiers[].stat`.
+pub enum MigrationStep {
+    // Gives the field at `path` a new name, keeping its value.
+    RenameField {
+        path: &'static str,
+        to: &'static str,
+    },
+    // Replaces the field at `path` with the fields `split` gives for its
+    // value, alongside it.
+    SplitField {
+        path: &'static str,
+        split: fn(&Value) -> Vec<(&'static str, Value)>,
+    },
+    // Renames enum variants in the field at `path`, as `(old, new)` pairs.
+    MapValues {
+        path: &'static str,
+        values: &'static [(&'static str, &'static str)],
+    },
+}
+
+// Upgrades a lib by one version. A lib's migrations are listed in order, the
+// first upgrading files without a version to version 1, so the current version
+// is the number of migrations.
+pub struct Migration {
+    pub description: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+// What migrating a lib changed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<String>,
+}
+
+impl MigrationStep {
+    // Applies the step to a def, returning whether it changed anything.
+    fn apply(&self, def: &mut Value) -> bool {
+        let path = match self {
+            MigrationStep::RenameField { path, .. }
+            | MigrationStep::SplitField { path, .. }
+            | MigrationStep::MapValues { path, .. } => path,
+        };
+        let mut applied = false;
+        for_each_field(def, path, &mut |parent, field| {
+            let Some(value) = parent.get_mut(field) else {
+                return;
+            };
+            match self {
+                MigrationStep::RenameField { to, .. } => {
+                    let value = parent.remove(field).unwrap();
+                    parent.insert(to.to_string(), value);
+                    applied = true;
+                }
+                MigrationStep::SplitField { split, .. } => {
+                    let fields = split(value);
+                    parent.remove(field);
+                    for (name, value) in fields {
+                        parent.insert(name.to_string(), value);
+                    }
+                    applied = true;
+                }
+                MigrationStep::MapValues { values, .. } => {
+                    let new = values.iter().find(|(old, _)| Some(*old) == value.as_str());
+                    if let Some((_, new)) = new {
+                        *value = Value::String(new.to_string());
+                        applied = true;
+                    }
+                }
+            }
+        });
+        applied
+    }
+}
+
+impl fmt::Display for MigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationStep::RenameField { path, to } => write!(f, "Renamed {} to {}", path, to),
+            MigrationStep::SplitField { path, .. } => write!(f, "Split {}", path),
+            MigrationStep::MapValues { path, values } => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|(old, new)| format!("{} -> {}", old, new))
+                    .collect();
+                write!(f, "Changed {} values ({})", path, values.join(", "))
+            }
+        }
+    }
+}
+
+impl MigrationReport {
+    pub fn is_up_to_date(&self) -> bool {
+        self.from_version == self.to_version
+    }
+}
+
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_up_to_date() {
+            return write!(f, "Up to date (version {})", self.to_version);
+        }
+        write!(f, "Version {} -> {}", self.from_version, self.to_version)?;
+        for change in &self.changes {
+            write!(f, "\n  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+// Calls `edit` with the parent of each field at `path` in `value`, and the
+// field's name.
+fn for_each_field(
+    value: &mut Value,
+    path: &str,
+    edit: &mut dyn FnMut(&mut Map<String, Value>, &str),
+) {
+    let (first, rest) = match path.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    let Value::Object(fields) = value else {
+        return;
+    };
+    match (first.strip_suffix("[]"), rest) {
+        (Some(list), Some(rest)) => {
+            if let Some(Value::Array(entries)) = fields.get_mut(list) {
+                for entry in entries {
+                    for_each_field(entry, rest, edit);
+                }
+            }
+        }
+        (None, Some(rest)) => {
+            if let Some(field) = fields.get_mut(first) {
+                for_each_field(field, rest, edit);
+            }
+        }
+        (_, None) => edit(fields, first),
+    }
+}
+
+// Upgrades a lib, read as a value, to the latest version by running each of
+// the migrations after its `version` in turn.
+pub fn migrate(lib: &mut Value, migrations: &[Migration]) -> Result<MigrationReport, String> {
+    let to_version = migrations.len() as u32;
+    let from_version = lib_version(lib)?;
+    if from_version > to_version {
+        return Err(format!(
+            "Version {} is newer than this editor supports ({})",
+            from_version, to_version
+        ));
+    }
+    let mut changes = vec![];
+    for (version, migration) in migrations.iter().enumerate().skip(from_version as usize) {
+        changes.push(format!("{}: {}", version + 1, migration.description));
+        for step in migration.steps {
+            let mut count = 0;
+            if let Some(Value::Array(defs)) = lib.get_mut("defs") {
+                for def in defs {
+                    if step.apply(def) {
+                        count += 1;
+                    }
+                }
+            }
+            changes.push(format!("  {} on {} defs", step, count));
+        }
+    }
+    if let Value::Object(fields) = lib {
+        fields.insert("version".to_string(), Value::from(to_version));
+    }
+    Ok(MigrationReport {
+        from_version,
+        to_version,
+        changes,
+    })
+}
+
+// What migrating a lib's files to the latest version would change, without
+// changing them.
+pub fn migration_report(
+    files: &LibFiles,
+    migrations: &[Migration],
+) -> Result<MigrationReport, String> {
+    migrate(&mut files.to_value()?, migrations)
+}
+
+// The version of a lib read as a value. Files from before versioning have
+// none, so are version 0.
+pub fn lib_version(lib: &Value) -> Result<u32, String> {
+    match lib.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .map(|version| version as u32)
+            .ok_or(format!("{} is not a valid version", version)),
+    }
+}
+
+// Reads a lib from a value, first migrating it to the latest version.
+pub fn from_migrated_value<T: DeserializeOwned>(
+    mut lib: Value,
+    migrations: &[Migration],
+) -> Result<T, String> {
+    migrate(&mut lib, migrations)?;
+    serde_json::from_value(lib).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            description: "Files from before they were versioned",
+            steps: &[],
+        },
+        Migration {
+            description: "Armour is armor, and value is split into buy and sell",
+            steps: &[
+                MigrationStep::RenameField {
+                    path: "equipment_def.armour",
+                    to: "armor",
+                },
+                MigrationStep::SplitField {
+                    path: "value",
+                    split: |value| {
+                        let value = value.as_u64().unwrap_or(0);
+                        vec![
+                            ("buy_value", Value::from(value)),
+                            ("sell_value", Value::from(value / 2)),
+                        ]
+                    },
+                },
+            ],
+        },
+        Migration {
+            description: "Rare items are epic",
+            steps: &[MigrationStep::MapValues {
+                path: "modifiers[].rarity",
+                values: &[("Rare", "Epic")],
+            }],
+        },
+    ];
+
+    #[test]
+    fn migrate_step_by_step() {
+        let mut lib = json!({
+            "next_id": 2,
+            "defs": [
+                {
+                    "id": 0,
+                    "value": 10,
+                    "equipment_def": { "armour": 5 },
+                    "modifiers": [{ "rarity": "Rare" }, { "rarity": "Common" }],
+                },
+                { "id": 1, "value": 3 },
+            ],
+        });
+        let report = migrate(&mut lib, MIGRATIONS).unwrap();
+        assert_eq!(
+            lib,
+            json!({
+                "version": 3,
+                "next_id": 2,
+                "defs": [
+                    {
+                        "id": 0,
+                        "buy_value": 10,
+                        "sell_value": 5,
+                        "equipment_def": { "armor": 5 },
+                        "modifiers": [{ "rarity": "Epic" }, { "rarity": "Common" }],
+                    },
+                    { "id": 1, "buy_value": 3, "sell_value": 1 },
+                ],
+            })
+        );
+        assert_eq!(report.from_version, 0);
+        assert_eq!(
+            report.changes,
+            vec![
+                "1: Files from before they were versioned",
+                "2: Armour is armor, and value is split into buy and sell",
+                "  Renamed equipment_def.armour to armor on 1 defs",
+                "  Split value on 2 defs",
+                "3: Rare items are epic",
+                "  Changed modifiers[].rarity values (Rare -> Epic) on 1 defs",
+            ]
+        );
+
+        // Only the migrations after the file's version run.
+        let mut lib = json!({ "version": 2, "defs": [{ "value": 10 }] });
+        let report = migrate(&mut lib, MIGRATIONS).unwrap();
+        assert_eq!(lib["defs"][0]["value"], 10);
+        assert_eq!(report.changes.len(), 2);
+        assert!(migrate(&mut lib, MIGRATIONS).unwrap().is_up_to_date());
+    }
+
+    #[test]
+    fn migrate_newer_version() {
+        let mut lib = json!({ "version": 4, "defs": [] });
+        assert_eq!(
+            migrate(&mut lib, MIGRATIONS),
+            Err("Version 4 is newer than this editor supports (3)".to_string())
+        );
+    }
+}
@@ -1 +1,9 @@
 pub mod asset_lib;
+pub mod attribute;
+pub mod bundle;
+pub mod format;
+pub mod identity;
+pub mod layout;
+pub mod merge;
+pub mod migration;
+pub mod value;
@@ -0,0 +1,384 @@
+use crate::asset::format::AssetFormat;
+use serde_json::{Map, Number, Value};
+
+// Reads the contents of an asset file into a tree of values that doesn't
+// depend on the types of its defs. This is only for migrating older libs (see
+// `LibFiles::to_value`), and for reading the version of a lib before it's read
+// into its types.
+//
+// Structs become maps, dropping any struct names, and enum variants without
+// data become strings. In RON, `None` is also read as a string, since defs use
+// it as a variant name (e.g. `EquipmentSlot::None`), and enum variants with
+// data lose their names.
+pub(crate) fn parse_asset_value(format: AssetFormat, data: &str) -> Result<Value, String> {
+    match format {
+        // RON's own untyped parsing can't tell enum variants from `()`, so it
+        // has its own parser.
+        AssetFormat::Ron => RonParser::new(data).parse(),
+        _ => format.parse(data),
+    }
+}
+
+struct RonParser<'a> {
+    data: &'a str,
+    position: usize,
+}
+
+impl<'a> RonParser<'a> {
+    fn new(data: &'a str) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn parse(mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        // Extensions such as `#![enable(implicit_some)]`
+        while self.rest().starts_with("#!") {
+            self.expect_char('#')?;
+            self.expect_char('!')?;
+            self.expect_char('[')?;
+            while !self.eat(']') {
+                self.next_char()?;
+            }
+            self.skip_whitespace();
+        }
+        let value = self.value()?;
+        self.skip_whitespace();
+        if !self.rest().is_empty() {
+            return Err(self.error("Expected the end of the file"));
+        }
+        Ok(value)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.data[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next_char(&mut self) -> Result<char, String> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("Unexpected end of file"))?;
+        self.position += c.len_utf8();
+        Ok(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn expect_char(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", c)))
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        let before = &self.data[..self.position];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        format!("{}:{}: {}", line, column, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if trimmed.starts_with("/*") {
+                self.position += trimmed.find("*/").map_or(trimmed.len(), |end| end + 2);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Option<&'a str> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        self.position += len;
+        Some(&rest[..len])
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some('\'') => self.char_literal().map(Value::String),
+            Some('r') if self.rest()[1..].starts_with(['"', '#']) => {
+                self.raw_string().map(Value::String)
+            }
+            Some('[') => self.list(),
+            Some('{') => self.map(),
+            Some('(') => self.parens(),
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => self.number(),
+            Some(_) => {
+                let Some(ident) = self.identifier() else {
+                    return Err(self.error("Expected a value"));
+                };
+                self.skip_whitespace();
+                match ident {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "Some" => {
+                        self.expect_char('(')?;
+                        let value = self.value()?;
+                        self.skip_whitespace();
+                        self.eat(',');
+                        self.skip_whitespace();
+                        self.expect_char(')')?;
+                        Ok(value)
+                    }
+                    // A named struct or tuple struct.
+                    _ if self.peek() == Some('(') => self.parens(),
+                    // An enum variant without data.
+                    _ => Ok(Value::String(ident.to_string())),
+                }
+            }
+            None => Err(self.error("Unexpected end of file")),
+        }
+    }
+
+    // The fields of a struct, or the elements of a tuple.
+    fn parens(&mut self) -> Result<Value, String> {
+        self.expect_char('(')?;
+        self.skip_whitespace();
+        let start = self.position;
+        let is_struct = self.identifier().is_some() && {
+            self.skip_whitespace();
+            self.peek() == Some(':')
+        };
+        self.position = start;
+        if is_struct || self.peek() == Some(')') {
+            let mut fields = Map::new();
+            while !self.end_of(')')? {
+                let name = self
+                    .identifier()
+                    .ok_or_else(|| self.error("Expected a field name"))?;
+                self.skip_whitespace();
+                self.expect_char(':')?;
+                fields.insert(name.to_string(), self.value()?);
+                self.separator(')')?;
+            }
+            Ok(Value::Object(fields))
+        } else {
+            let mut elements = vec![];
+            while !self.end_of(')')? {
+                elements.push(self.value()?);
+                self.separator(')')?;
+            }
+            Ok(Value::Array(elements))
+        }
+    }
+
+    fn list(&mut self) -> Result<Value, String> {
+        self.expect_char('[')?;
+        let mut elements = vec![];
+        while !self.end_of(']')? {
+            elements.push(self.value()?);
+            self.separator(']')?;
+        }
+        Ok(Value::Array(elements))
+    }
+
+    // Maps with keys that aren't strings, such as ids, have them written as
+    // strings.
+    fn map(&mut self) -> Result<Value, String> {
+        self.expect_char('{')?;
+        let mut entries = Map::new();
+        while !self.end_of('}')? {
+            let key = match self.value()? {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            self.skip_whitespace();
+            self.expect_char(':')?;
+            entries.insert(key, self.value()?);
+            self.separator('}')?;
+        }
+        Ok(Value::Object(entries))
+    }
+
+    // Whether the closing bracket is next, consuming it if so.
+    fn end_of(&mut self, close: char) -> Result<bool, String> {
+        self.skip_whitespace();
+        if self.peek().is_none() {
+            return Err(self.error(&format!("Expected '{}'", close)));
+        }
+        Ok(self.eat(close))
+    }
+
+    // The comma after an element, which is optional after the last one.
+    fn separator(&mut self, close: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.eat(',') || self.peek() == Some(close) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected ',' or '{}'", close)))
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.' | '_')))
+            .unwrap_or(rest.len());
+        let text = rest[..len].replace('_', "");
+        let number = if let Ok(number) = text.parse::<u64>() {
+            Number::from(number)
+        } else if let Ok(number) = text.parse::<i64>() {
+            Number::from(number)
+        } else {
+            text.parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .ok_or_else(|| self.error(&format!("'{}' is not a number", text)))?
+        };
+        self.position += len;
+        Ok(Value::Number(number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect_char('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next_char()? {
+                '"' => return Ok(string),
+                '\\' => string.push(self.escape()?),
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn char_literal(&mut self) -> Result<String, String> {
+        self.expect_char('\'')?;
+        let c = match self.next_char()? {
+            '\\' => self.escape()?,
+            c => c,
+        };
+        self.expect_char('\'')?;
+        Ok(c.to_string())
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = match self.next_char()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'x' => {
+                let hex = self.rest().get(..2).unwrap_or_default();
+                self.position += hex.len();
+                u8::from_str_radix(hex, 16)
+                    .map_err(|_| self.error("Invalid escape"))?
+                    .into()
+            }
+            'u' => {
+                self.expect_char('{')?;
+                let rest = self.rest();
+                let len = rest.find('}').ok_or_else(|| self.error("Expected '}'"))?;
+                self.position += len + 1;
+                u32::from_str_radix(&rest[..len], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("Invalid escape"))?
+            }
+            c => c,
+        };
+        Ok(c)
+    }
+
+    fn raw_string(&mut self) -> Result<String, String> {
+        self.expect_char('r')?;
+        let mut hashes = 0;
+        while self.eat('#') {
+            hashes += 1;
+        }
+        self.expect_char('"')?;
+        let end = format!("\"{}", "#".repeat(hashes));
+        let rest = self.rest();
+        let len = rest
+            .find(&end)
+            .ok_or_else(|| self.error("Unterminated raw string"))?;
+        self.position += len + end.len();
+        Ok(rest[..len].to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_ron_value() {
+        let data = r##"
+            #![enable(implicit_some)]
+            ItemRon (
+                next_id: 2, // Comments are skipped
+                formulas: { "sell_value": "buy_value / 2", 3: "x" },
+                defs: [
+                    ItemDef (
+                        id: 0,
+                        name: "Red \"Potion\"\n\u{2764}",
+                        item_rarity: Common,
+                        weight: -0.5e1,
+                        icon: r#"sprite/"icon".png"#,
+                        equipment_def: (slot: None, armor: 5),
+                        pair: (1, 'c', Some(true)),
+                        empty: (),
+                    ),
+                    /* An empty def */ (),
+                ],
+            )
+        "##;
+        assert_eq!(
+            parse_asset_value(AssetFormat::Ron, data),
+            Ok(json!({
+                "next_id": 2,
+                "formulas": { "sell_value": "buy_value / 2", "3": "x" },
+                "defs": [
+                    {
+                        "id": 0,
+                        "name": "Red \"Potion\"\n\u{2764}",
+                        "item_rarity": "Common",
+                        "weight": -5.0,
+                        "icon": "sprite/\"icon\".png",
+                        "equipment_def": { "slot": "None", "armor": 5 },
+                        "pair": [1, "c", true],
+                        "empty": {},
+                    },
+                    {},
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_ron_value_errors() {
+        assert_eq!(
+            parse_asset_value(AssetFormat::Ron, "(\n  id: 0\n  name: \"\",\n)"),
+            Err("3:3: Expected ',' or ')'".to_string())
+        );
+        assert_eq!(
+            parse_asset_value(AssetFormat::Ron, "[1, 2"),
+            Err("1:6: Expected ',' or ']'".to_string())
+        );
+        assert!(parse_asset_value(AssetFormat::Ron, "(id: 0) 1").is_err());
+    }
+}
@@ -0,0 +1,475 @@
+use std::fmt;
+
+// A small arithmetic expression language for computing def values from other
+// fields, e.g. `buy_value / 2` or `if(item_rarity == "Rare", 20, 10)`.
+//
+// Supports numbers, "strings", field paths (`equipment_def.armor`), the
+// operators `+ - * / %`, comparisons `== != < <= > >=` (which give 1 or 0),
+// parentheses and the functions `min`, `max`, `round`, `floor`, `ceil`, `abs`
+// and `if(condition, then, else)`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    Number(f64),
+    Text(String),
+    Variable(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExpressionError {
+    UnexpectedEnd,
+    UnexpectedCharacter(char),
+    UnexpectedToken(String),
+    UnknownVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount(String, usize),
+    NotANumber(String),
+    DivideByZero,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Identifier(String),
+    Operator(Operator),
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpressionError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            ExpressionError::UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c),
+            ExpressionError::UnexpectedToken(token) => write!(f, "Unexpected '{}'", token),
+            ExpressionError::UnknownVariable(name) => write!(f, "Unknown field '{}'", name),
+            ExpressionError::UnknownFunction(name) => write!(f, "Unknown function '{}'", name),
+            ExpressionError::WrongArgumentCount(name, count) => {
+                write!(f, "'{}' takes {} arguments", name, count)
+            }
+            ExpressionError::NotANumber(value) => write!(f, "'{}' is not a number", value),
+            ExpressionError::DivideByZero => write!(f, "Divide by zero"),
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::Remainder => "%",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+        })
+    }
+}
+
+// Tokens as they're written in the expression.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{}", number),
+            Token::Text(text) => write!(f, "\"{}\"", text),
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Operator(operator) => write!(f, "{}", operator),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+impl Value {
+    // Parses a def value as a number if possible, otherwise as text.
+    pub fn from_field(value: &str) -> Value {
+        match value.parse::<f64>() {
+            Ok(number) => Value::Number(number),
+            Err(_) => Value::Text(value.to_string()),
+        }
+    }
+
+    pub fn as_number(&self) -> Result<f64, ExpressionError> {
+        match self {
+            Value::Number(number) => Ok(*number),
+            Value::Text(text) => Err(ExpressionError::NotANumber(text.clone())),
+        }
+    }
+}
+
+impl Operator {
+    // How tightly the operator binds, higher binds tighter.
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Equal
+            | Operator::NotEqual
+            | Operator::Less
+            | Operator::LessEqual
+            | Operator::Greater
+            | Operator::GreaterEqual => 1,
+            Operator::Add | Operator::Subtract => 2,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 3,
+        }
+    }
+
+    pub fn apply(&self, left: &Value, right: &Value) -> Result<Value, ExpressionError> {
+        let truth = |b: bool| Ok(Value::Number(if b { 1.0 } else { 0.0 }));
+        match self {
+            Operator::Equal => return truth(left == right),
+            Operator::NotEqual => return truth(left != right),
+            _ => {}
+        }
+        let (left, right) = (left.as_number()?, right.as_number()?);
+        match self {
+            Operator::Add => Ok(Value::Number(left + right)),
+            Operator::Subtract => Ok(Value::Number(left - right)),
+            Operator::Multiply => Ok(Value::Number(left * right)),
+            Operator::Divide | Operator::Remainder if right == 0.0 => {
+                Err(ExpressionError::DivideByZero)
+            }
+            Operator::Divide => Ok(Value::Number(left / right)),
+            Operator::Remainder => Ok(Value::Number(left % right)),
+            Operator::Less => truth(left < right),
+            Operator::LessEqual => truth(left <= right),
+            Operator::Greater => truth(left > right),
+            Operator::GreaterEqual => truth(left >= right),
+            Operator::Equal | Operator::NotEqual => unreachable!(),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Expression, ExpressionError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let expression = parser.expression(0)?;
+        match parser.tokens.get(parser.index) {
+            Some(token) => Err(ExpressionError::UnexpectedToken(token.to_string())),
+            None => Ok(expression),
+        }
+    }
+
+    // Evaluates the expression, looking up field paths with `variable`.
+    pub fn eval(&self, variable: &dyn Fn(&str) -> Option<Value>) -> Result<Value, ExpressionError> {
+        match self {
+            Expression::Number(number) => Ok(Value::Number(*number)),
+            Expression::Text(text) => Ok(Value::Text(text.clone())),
+            Expression::Variable(name) => {
+                variable(name).ok_or(ExpressionError::UnknownVariable(name.clone()))
+            }
+            Expression::Negate(inner) => Ok(Value::Number(-inner.eval(variable)?.as_number()?)),
+            Expression::Binary(left, operator, right) => {
+                operator.apply(&left.eval(variable)?, &right.eval(variable)?)
+            }
+            Expression::Call(name, arguments) => call(name, arguments, variable),
+        }
+    }
+
+    // Every field path the expression reads.
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = vec![];
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Expression::Variable(name) if !variables.contains(name) => variables.push(name.clone()),
+            Expression::Negate(inner) => inner.collect_variables(variables),
+            Expression::Binary(left, _, right) => {
+                left.collect_variables(variables);
+                right.collect_variables(variables);
+            }
+            Expression::Call(_, arguments) => {
+                for argument in arguments {
+                    argument.collect_variables(variables);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn call(
+    name: &str,
+    arguments: &[Expression],
+    variable: &dyn Fn(&str) -> Option<Value>,
+) -> Result<Value, ExpressionError> {
+    let expected = match name {
+        "round" | "floor" | "ceil" | "abs" => 1,
+        "min" | "max" => 2,
+        "if" => 3,
+        _ => return Err(ExpressionError::UnknownFunction(name.to_string())),
+    };
+    if arguments.len() != expected {
+        return Err(ExpressionError::WrongArgumentCount(
+            name.to_string(),
+            expected,
+        ));
+    }
+    // Only the branch that is taken is evaluated.
+    if name == "if" {
+        let condition = arguments[0].eval(variable)?.as_number()? != 0.0;
+        return arguments[if condition { 1 } else { 2 }].eval(variable);
+    }
+    let mut numbers = vec![];
+    for argument in arguments {
+        numbers.push(argument.eval(variable)?.as_number()?);
+    }
+    Ok(Value::Number(match name {
+        "round" => numbers[0].round(),
+        "floor" => numbers[0].floor(),
+        "ceil" => numbers[0].ceil(),
+        "abs" => numbers[0].abs(),
+        "min" => numbers[0].min(numbers[1]),
+        _ => numbers[0].max(numbers[1]),
+    }))
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '%' => Token::Operator(Operator::Remainder),
+            '=' | '!' | '<' | '>' => {
+                let equals = chars.next_if_eq(&'=').is_some();
+                Token::Operator(match (c, equals) {
+                    ('=', true) => Operator::Equal,
+                    ('!', true) => Operator::NotEqual,
+                    ('<', false) => Operator::Less,
+                    ('<', true) => Operator::LessEqual,
+                    ('>', false) => Operator::Greater,
+                    ('>', true) => Operator::GreaterEqual,
+                    _ => return Err(ExpressionError::UnexpectedCharacter(c)),
+                })
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(ExpressionError::UnexpectedEnd),
+                    }
+                }
+                Token::Text(text)
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| ExpressionError::NotANumber(number))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut identifier = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+                {
+                    identifier.push(c);
+                }
+                Token::Identifier(identifier)
+            }
+            c => return Err(ExpressionError::UnexpectedCharacter(c)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+// Precedence climbing parser over the tokens.
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<Token, ExpressionError> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token.ok_or(ExpressionError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExpressionError> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    fn expression(&mut self, min_precedence: u8) -> Result<Expression, ExpressionError> {
+        let mut left = self.primary()?;
+        while let Some(Token::Operator(operator)) = self.tokens.get(self.index).cloned() {
+            if operator.precedence() < min_precedence.max(1) {
+                break;
+            }
+            self.index += 1;
+            let right = self.expression(operator.precedence() + 1)?;
+            left = Expression::Binary(Box::new(left), operator, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Expression, ExpressionError> {
+        match self.next()? {
+            Token::Number(number) => Ok(Expression::Number(number)),
+            Token::Text(text) => Ok(Expression::Text(text)),
+            Token::Operator(Operator::Subtract) => {
+                Ok(Expression::Negate(Box::new(self.primary()?)))
+            }
+            Token::Open => {
+                let inner = self.expression(0)?;
+                self.expect(Token::Close)?;
+                Ok(inner)
+            }
+            Token::Identifier(name) if self.tokens.get(self.index) == Some(&Token::Open) => {
+                self.index += 1;
+                let mut arguments = vec![];
+                if self.tokens.get(self.index) == Some(&Token::Close) {
+                    self.index += 1;
+                } else {
+                    loop {
+                        arguments.push(self.expression(0)?);
+                        match self.next()? {
+                            Token::Comma => continue,
+                            Token::Close => break,
+                            token => {
+                                return Err(ExpressionError::UnexpectedToken(token.to_string()))
+                            }
+                        }
+                    }
+                }
+                Ok(Expression::Call(name, arguments))
+            }
+            Token::Identifier(name) => Ok(Expression::Variable(name)),
+            token => Err(ExpressionError::UnexpectedToken(token.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str) -> Result<Value, ExpressionError> {
+        Expression::parse(input)?.eval(&|name| match name {
+            "buy_value" => Some(Value::Number(200.0)),
+            "equipment_def.armor" => Some(Value::Number(5.0)),
+            "item_rarity" => Some(Value::Text("Rare".to_string())),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn expression_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Number(7.0)));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(Value::Number(9.0)));
+        assert_eq!(eval("10 - 4 - 3"), Ok(Value::Number(3.0)));
+        assert_eq!(eval("-2 * -3"), Ok(Value::Number(6.0)));
+        assert_eq!(eval("7 % 4"), Ok(Value::Number(3.0)));
+        assert_eq!(eval("buy_value / 2"), Ok(Value::Number(100.0)));
+        assert_eq!(eval("equipment_def.armor * 1.5"), Ok(Value::Number(7.5)));
+        assert_eq!(eval("1 + 1 == 2"), Ok(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn expression_functions() {
+        assert_eq!(eval("round(2.5)"), Ok(Value::Number(3.0)));
+        assert_eq!(eval("max(floor(2.7), ceil(1.1))"), Ok(Value::Number(2.0)));
+        assert_eq!(
+            eval("if(item_rarity == \"Rare\", buy_value, 0)"),
+            Ok(Value::Number(200.0))
+        );
+        assert_eq!(
+            eval("if(1 < 0, unknown, \"no\")"),
+            Ok(Value::Text("no".to_string()))
+        );
+    }
+
+    #[test]
+    fn expression_errors() {
+        assert_eq!(eval("1 +"), Err(ExpressionError::UnexpectedEnd));
+        assert_eq!(eval("1 / 0"), Err(ExpressionError::DivideByZero));
+        assert_eq!(
+            eval("sell_value"),
+            Err(ExpressionError::UnknownVariable("sell_value".to_string()))
+        );
+        assert_eq!(
+            eval("item_rarity + 1"),
+            Err(ExpressionError::NotANumber("Rare".to_string()))
+        );
+        assert_eq!(
+            eval("min(1)"),
+            Err(ExpressionError::WrongArgumentCount("min".to_string(), 2))
+        );
+        assert_eq!(
+            eval("1 $ 2"),
+            Err(ExpressionError::UnexpectedCharacter('$'))
+        );
+        // Tokens are shown as they're written.
+        let message = |input| eval(input).unwrap_err().to_string();
+        assert_eq!(message("(1 + 2))"), "Unexpected ')'");
+        assert_eq!(message("max(1 2)"), "Unexpected '2'");
+        assert_eq!(message("1 + <= 2"), "Unexpected '<='");
+        assert_eq!(message("min(1, 2) \"a\""), "Unexpected '\"a\"'");
+    }
+
+    #[test]
+    fn expression_variables() {
+        let expression =
+            Expression::parse("if(item_rarity == \"Rare\", buy_value, buy_value / 2)").unwrap();
+        assert_eq!(expression.variables(), vec!["item_rarity", "buy_value"]);
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::expression::{Expression, ExpressionError, Value};
+
+// Formulas for computing the fields of every def in a lib from its other
+// fields, keyed by field path, e.g. `sell_value: "buy_value / 2"`.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FormulaSet {
+    formulas: BTreeMap<String, String>,
+}
+
+// The defs, by id, that were given a value of their own for a field with a
+// formula, keyed by field path. Recomputing the field leaves them alone.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FormulaOverrides {
+    overrides: BTreeMap<String, BTreeSet<u32>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FormulaError {
+    Invalid(ExpressionError),
+    // The formula would depend on its own field through these paths.
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormulaError::Invalid(error) => write!(f, "{}", error),
+            FormulaError::Cycle(paths) => {
+                write!(f, "Formula depends on itself: {}", paths.join(" -> "))
+            }
+        }
+    }
+}
+
+impl FormulaSet {
+    pub fn is_empty(&self) -> bool {
+        self.formulas.is_empty()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.formulas.get(path).map(|source| source.as_str())
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.formulas.keys()
+    }
+
+    // Sets the formula for `path`, as long as it parses and doesn't make any
+    // formula depend on itself.
+    pub fn set(&mut self, path: &str, source: &str) -> Result<(), FormulaError> {
+        Expression::parse(source).map_err(FormulaError::Invalid)?;
+        let previous = self
+            .formulas
+            .insert(path.to_string(), source.trim().to_string());
+        if let Some(cycle) = self.find_cycle(path) {
+            match previous {
+                Some(previous) => self.formulas.insert(path.to_string(), previous),
+                None => self.formulas.remove(path),
+            };
+            return Err(FormulaError::Cycle(cycle));
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        self.formulas.remove(path);
+    }
+
+    // Evaluates the formula for `path`, if it has one.
+    pub fn compute(
+        &self,
+        path: &str,
+        variable: &dyn Fn(&str) -> Option<Value>,
+    ) -> Option<Result<Value, ExpressionError>> {
+        let source = self.formulas.get(path)?;
+        Some(Expression::parse(source).and_then(|expression| expression.eval(variable)))
+    }
+
+    // The field paths that the formula for `path` reads.
+    fn inputs(&self, path: &str) -> Vec<String> {
+        self.formulas
+            .get(path)
+            .and_then(|source| Expression::parse(source).ok())
+            .map(|expression| expression.variables())
+            .unwrap_or_default()
+    }
+
+    // Every field with a formula that reads `path`, directly or through other
+    // formulas, in the order they should be recomputed.
+    pub fn dependents(&self, path: &str) -> Vec<String> {
+        let mut dependents: Vec<String> = vec![];
+        let mut stack = vec![path.to_string()];
+        while let Some(changed) = stack.pop() {
+            for formula in self.formulas.keys() {
+                if !dependents.contains(formula) && self.inputs(formula).contains(&changed) {
+                    dependents.push(formula.clone());
+                    stack.push(formula.clone());
+                }
+            }
+        }
+        self.order()
+            .into_iter()
+            .filter(|formula| dependents.contains(formula))
+            .collect()
+    }
+
+    // Every field with a formula, ordered so each comes after the formulas it
+    // reads from.
+    pub fn order(&self) -> Vec<String> {
+        let mut order = vec![];
+        for path in self.formulas.keys() {
+            self.visit(path, &mut order, &mut vec![]);
+        }
+        order
+    }
+
+    fn visit(&self, path: &str, order: &mut Vec<String>, visiting: &mut Vec<String>) {
+        if order.iter().any(|p| p == path)
+            || visiting.iter().any(|p| p == path)
+            || !self.formulas.contains_key(path)
+        {
+            return;
+        }
+        visiting.push(path.to_string());
+        for input in self.inputs(path) {
+            self.visit(&input, order, visiting);
+        }
+        visiting.pop();
+        order.push(path.to_string());
+    }
+
+    // The chain of formulas leading from `path` back to itself, if any.
+    fn find_cycle(&self, path: &str) -> Option<Vec<String>> {
+        let mut stack = vec![vec![path.to_string()]];
+        while let Some(chain) = stack.pop() {
+            for input in self.inputs(chain.last().unwrap()) {
+                let mut next = chain.clone();
+                next.push(input.clone());
+                if input == path {
+                    return Some(next);
+                }
+                if !chain.contains(&input) {
+                    stack.push(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl FormulaOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn contains(&self, id: u32, path: &str) -> bool {
+        self.overrides
+            .get(path)
+            .is_some_and(|ids| ids.contains(&id))
+    }
+
+    // The overridden fields of the def with the id.
+    pub fn paths(&self, id: u32) -> Vec<String> {
+        self.overrides
+            .iter()
+            .filter(|(_, ids)| ids.contains(&id))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    // Marks the field of the def as overridden, or as following its formula.
+    pub fn set(&mut self, id: u32, path: &str, overridden: bool) {
+        if overridden {
+            self.overrides
+                .entry(path.to_string())
+                .or_default()
+                .insert(id);
+        } else if let Some(ids) = self.overrides.get_mut(path) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.overrides.remove(path);
+            }
+        }
+    }
+
+    // Drops the overrides of a field, such as when its formula is removed.
+    pub fn clear(&mut self, path: &str) {
+        self.overrides.remove(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formulas() -> FormulaSet {
+        let mut formulas = FormulaSet::default();
+        formulas.set("sell_value", "buy_value / 2").unwrap();
+        formulas.set("buy_value", "base_value * 4").unwrap();
+        formulas.set("scrap_value", "sell_value / 5").unwrap();
+        formulas
+    }
+
+    #[test]
+    fn formula_compute() {
+        let formulas = formulas();
+        let variable = |name: &str| match name {
+            "buy_value" => Some(Value::Number(200.0)),
+            _ => None,
+        };
+        assert_eq!(
+            formulas.compute("sell_value", &variable),
+            Some(Ok(Value::Number(100.0)))
+        );
+        assert_eq!(formulas.compute("max_stack", &variable), None);
+        assert_eq!(
+            formulas.compute("buy_value", &variable),
+            Some(Err(ExpressionError::UnknownVariable(
+                "base_value".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn formula_dependents() {
+        let formulas = formulas();
+        assert_eq!(
+            formulas.dependents("base_value"),
+            vec!["buy_value", "sell_value", "scrap_value"]
+        );
+        assert_eq!(formulas.dependents("sell_value"), vec!["scrap_value"]);
+        assert!(formulas.dependents("scrap_value").is_empty());
+    }
+
+    #[test]
+    fn formula_rejects_cycles() {
+        let mut formulas = formulas();
+        assert_eq!(
+            formulas.set("base_value", "scrap_value * 10"),
+            Err(FormulaError::Cycle(vec![
+                "base_value".to_string(),
+                "scrap_value".to_string(),
+                "sell_value".to_string(),
+                "buy_value".to_string(),
+                "base_value".to_string(),
+            ]))
+        );
+        assert_eq!(formulas.get("base_value"), None);
+        assert!(matches!(
+            formulas.set("sell_value", "buy_value /"),
+            Err(FormulaError::Invalid(_))
+        ));
+        assert_eq!(formulas.get("sell_value"), Some("buy_value / 2"));
+    }
+
+    #[test]
+    fn formula_overrides() {
+        let mut overrides = FormulaOverrides::default();
+        overrides.set(3, "sell_value", true);
+        overrides.set(3, "scrap_value", true);
+        overrides.set(4, "sell_value", true);
+        assert!(overrides.contains(3, "sell_value"));
+        assert!(!overrides.contains(5, "sell_value"));
+        assert_eq!(overrides.paths(3), vec!["scrap_value", "sell_value"]);
+        overrides.set(3, "scrap_value", false);
+        overrides.clear("sell_value");
+        assert!(overrides.is_empty());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod expression;
+#[allow(clippy::module_inception)]
+pub mod formula;
@@ -1,6 +1,18 @@
 pub mod asset;
+pub mod formula;
 
 pub mod prelude {
     // Asset Modules
     pub use crate::asset::asset_lib::*;
+    pub use crate::asset::attribute::*;
+    pub use crate::asset::bundle::*;
+    pub use crate::asset::format::*;
+    pub use crate::asset::identity::*;
+    pub use crate::asset::layout::*;
+    pub use crate::asset::merge::*;
+    pub use crate::asset::migration::*;
+
+    // Formula Modules
+    pub use crate::formula::expression::*;
+    pub use crate::formula::formula::*;
 }
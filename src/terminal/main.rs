@@ -1,15 +1,238 @@
-use term_screen::database::Database;
+use game_system::prelude::{AssetFormat, AssetLayout};
+use term_screen::database::{
+    assign_asset_guids, bundle_asset_libs, convert_asset_libs, diff_asset_lib, merge_asset_lib,
+    migrate_asset_libs, renumber_asset_libs, Database,
+};
+use term_screen::git;
 use term_screen::menu::Menu;
+use term_screen::project::{Project, PROJECT_FILE};
 use term_system::tui;
 use term_system::window::{Screen, Window, WindowName};
 
 use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-fn main() -> io::Result<()> {
+const USAGE: &str = "\
+Usage: terminal [options]
+       terminal [options] convert [<format>] [<layout>]
+       terminal [options] bundle <path>
+       terminal [options] migrate
+       terminal [options] guids
+       terminal [options] renumber [<ours> <theirs>]
+       terminal diff <old> <new> [<lib>]
+       terminal merge <base> <ours> <theirs> [<lib>]
+       terminal merge-driver
+
+Options:
+  --project <path>   The project file, which says where the assets are. Defaults to the
+                     asset-editor.toml in the current directory or the closest one above it,
+                     or the assets in ./asset without one
+  --format <format>  The format of every asset lib: ron, json, toml or yaml. Defaults to the
+                     project's, or ron
+  --layout <layout>  How every asset lib is laid out: file, with one file per lib, or
+                     directory, with a directory per lib and a file per asset. Defaults to the
+                     project's, or file
+
+Commands:
+  convert            Saves every asset lib in another format or layout
+  bundle <path>      Compiles every asset lib into a binary bundle for the game
+  migrate            Upgrades every asset lib saved by an older version
+  guids              Gives every def without a guid a new one
+  renumber           Renumbers defs whose ids collide after a git merge, and the references
+                     to them. Uses the merge in progress, or the last commit, unless the
+                     revisions of both sides are given
+  diff               Lists the assets that differ between two versions of an asset lib, and
+                     their changed fields. <lib> is where the lib is stored, which gives its
+                     asset type and format, and defaults to <new>
+  merge              Merges the changes <ours> and <theirs> made to <base>, three versions of
+                     an asset lib, field by field into <ours>. Fields both changed keep ours
+                     and are listed as conflicts
+  merge-driver       Registers merge as the git merge driver for the asset libs";
+
+// What git calls the merge driver, in its config and `.gitattributes`.
+const MERGE_DRIVER: &str = "asset-editor";
+
+// Which project to open, and how to override how its asset libs are stored,
+// from the command line arguments.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    project: Option<String>,
+    format: Option<AssetFormat>,
+    layout: Option<AssetLayout>,
+}
+
+// What to do, from the command line arguments.
+#[derive(Debug, PartialEq)]
+enum Command {
+    Edit,
+    Convert {
+        format: Option<AssetFormat>,
+        layout: Option<AssetLayout>,
+    },
+    Bundle {
+        path: String,
+    },
+    Migrate,
+    Guids,
+    Renumber {
+        sides: Option<(String, String)>,
+    },
+    Diff {
+        old: String,
+        new: String,
+        lib: String,
+    },
+    Merge {
+        base: String,
+        ours: String,
+        theirs: String,
+        lib: String,
+    },
+    MergeDriver,
+}
+
+fn parse_format(value: Option<String>) -> Result<AssetFormat, String> {
+    let value = value.ok_or("Expected a format")?;
+    value
+        .parse()
+        .map_err(|_| format!("Unknown format '{}'", value))
+}
+
+fn parse_layout(value: Option<String>) -> Result<AssetLayout, String> {
+    let value = value.ok_or("Expected a layout")?;
+    value
+        .parse()
+        .map_err(|_| format!("Unknown layout '{}'", value))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<(Options, Command), String> {
+    let mut args = args.into_iter().peekable();
+    let mut options = Options::default();
+    let mut convert_to = None;
+    let mut bundle_path = None;
+    let mut migrate = false;
+    let mut guids = false;
+    let mut renumber = None;
+    let mut diff = None;
+    let mut merge = None;
+    let mut merge_driver = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => options.project = Some(args.next().ok_or("Expected a project file")?),
+            "--format" => options.format = Some(parse_format(args.next())?),
+            "--layout" => options.layout = Some(parse_layout(args.next())?),
+            "convert" => {
+                let mut to_format = None;
+                let mut to_layout = None;
+                while let Some(arg) = args.next_if(|arg| !arg.starts_with('-')) {
+                    if let Ok(format) = arg.parse::<AssetFormat>() {
+                        to_format = Some(format);
+                    } else if let Ok(layout) = arg.parse::<AssetLayout>() {
+                        to_layout = Some(layout);
+                    } else {
+                        return Err(format!("Unknown format or layout '{}'", arg));
+                    }
+                }
+                if to_format.is_none() && to_layout.is_none() {
+                    return Err("Expected a format or layout".to_string());
+                }
+                convert_to = Some((to_format, to_layout));
+            }
+            "bundle" => bundle_path = Some(args.next().ok_or("Expected a bundle path")?),
+            "migrate" => migrate = true,
+            "guids" => guids = true,
+            "renumber" => {
+                renumber = Some(match args.next_if(|arg| !arg.starts_with('-')) {
+                    Some(ours) => Some((ours, args.next().ok_or("Expected their revision")?)),
+                    None => None,
+                })
+            }
+            "diff" => {
+                let old = args.next().ok_or("Expected the old version")?;
+                let new = args.next().ok_or("Expected the new version")?;
+                let lib = args.next_if(|arg| !arg.starts_with('-'));
+                diff = Some(Command::Diff {
+                    lib: lib.unwrap_or(new.clone()),
+                    old,
+                    new,
+                });
+            }
+            "merge" => {
+                let base = args.next().ok_or("Expected the base version")?;
+                let ours = args.next().ok_or("Expected our version")?;
+                let theirs = args.next().ok_or("Expected their version")?;
+                let lib = args.next_if(|arg| !arg.starts_with('-'));
+                merge = Some(Command::Merge {
+                    lib: lib.unwrap_or(ours.clone()),
+                    base,
+                    ours,
+                    theirs,
+                });
+            }
+            "merge-driver" => merge_driver = true,
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+    let commands = [
+        convert_to.is_some(),
+        bundle_path.is_some(),
+        migrate,
+        guids,
+        renumber.is_some(),
+        diff.is_some(),
+        merge.is_some(),
+        merge_driver,
+    ];
+    if commands.iter().filter(|command| **command).count() > 1 {
+        return Err(
+            "Use only one of convert, bundle, migrate, guids, renumber, diff, merge or merge-driver"
+                .to_string(),
+        );
+    }
+    let command = if let Some((format, layout)) = convert_to {
+        Command::Convert { format, layout }
+    } else if let Some(path) = bundle_path {
+        Command::Bundle { path }
+    } else if migrate {
+        Command::Migrate
+    } else if guids {
+        Command::Guids
+    } else if let Some(sides) = renumber {
+        Command::Renumber { sides }
+    } else if let Some(diff) = diff {
+        diff
+    } else if let Some(merge) = merge {
+        merge
+    } else if merge_driver {
+        Command::MergeDriver
+    } else {
+        Command::Edit
+    };
+    Ok((options, command))
+}
+
+// Opens the project given in the options, or the one found from the current
+// directory, with the storage overridden by the options.
+fn open_project(options: &Options) -> Result<Project, String> {
+    let mut project = match &options.project {
+        Some(path) => Project::open(path)?,
+        None => Project::find()?,
+    };
+    project.set_storage(options.format, options.layout);
+    Ok(project)
+}
+
+fn run_editor(project: Project) -> io::Result<()> {
+    let window = Window {
+        theme: project.theme,
+        keymap: project.keymap,
+        ..Window::default()
+    };
     let mut terminal = tui::init()?;
     let mut current_window = WindowName::Menu;
-    let mut menu = Menu::new(Window::default());
-    let mut database = Database::new(Window::default());
+    let mut menu = Menu::new(window);
+    let mut database = Database::with_project(window, project);
     while current_window != WindowName::None {
         let window_result = match current_window {
             WindowName::Menu => menu.run(&mut terminal),
@@ -29,5 +252,332 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
+fn main() -> ExitCode {
+    let (options, command) = match parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let project = match open_project(&options) {
+        Ok(project) => project,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    match command {
+        Command::Edit => match run_editor(project) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Convert { format, layout } => {
+            let mut to = project.clone();
+            to.set_storage(format, layout);
+            let mut failed = false;
+            for (path, converted) in convert_asset_libs(&project, &to) {
+                match converted {
+                    Ok(()) => println!("Wrote {}", path),
+                    Err(error) => {
+                        eprintln!("{}: {}", path, error);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Command::Bundle { path } => {
+            match bundle_asset_libs(&project).and_then(|bundle| bundle.write(&path)) {
+                Ok(header) => {
+                    println!(
+                        "Wrote {} (version {}, hash {:016x})",
+                        path, header.version, header.hash
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Command::Migrate => {
+            let mut failed = false;
+            for (path, report) in migrate_asset_libs(&project) {
+                match report {
+                    Ok(report) => println!("{}: {}", path, report),
+                    Err(error) => {
+                        eprintln!("{}: {}", path, error);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Command::Guids => {
+            let mut failed = false;
+            for (path, count) in assign_asset_guids(&project) {
+                match count {
+                    Ok(count) => println!("{}: {} defs given a guid", path, count),
+                    Err(error) => {
+                        eprintln!("{}: {}", path, error);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Command::Renumber { sides } => {
+            let report = sides
+                .map_or_else(git::merge_sides, Ok)
+                .and_then(|(ours, theirs)| renumber_asset_libs(&project, &ours, &theirs));
+            match report {
+                Ok(report) => {
+                    println!("{}", report);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Command::Diff { old, new, lib } => match diff_asset_lib(&project, &old, &new, &lib) {
+            Ok(changes) => {
+                if changes.is_empty() {
+                    println!("No assets changed");
+                }
+                for change in changes {
+                    println!("{}", change);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        },
+        // Git takes any exit code but 0 to mean the merge has conflicts.
+        Command::Merge {
+            base,
+            ours,
+            theirs,
+            lib,
+        } => match merge_asset_lib(&project, &base, &ours, &theirs, &lib) {
+            Ok(conflicts) if conflicts.is_empty() => ExitCode::SUCCESS,
+            Ok(conflicts) => {
+                eprintln!(
+                    "{}: {} conflicts, keeping ours, listed at the top of the file:",
+                    lib,
+                    conflicts.len()
+                );
+                for conflict in conflicts {
+                    eprintln!("  {}", conflict);
+                }
+                ExitCode::FAILURE
+            }
+            Err(error) => {
+                eprintln!("{}: {}", lib, error);
+                ExitCode::FAILURE
+            }
+        },
+        // The driver is given the project file, as git runs it from the top of
+        // the repository.
+        Command::MergeDriver => {
+            let command = std::env::current_exe()
+                .map_err(|error| error.to_string())
+                .and_then(|exe| {
+                    let mut command = format!("\"{}\"", exe.display());
+                    let project_file = options
+                        .project
+                        .map(PathBuf::from)
+                        .unwrap_or(project.dir.join(PROJECT_FILE));
+                    if let Ok(project_file) = project_file.canonicalize() {
+                        command.push_str(&format!(" --project \"{}\"", project_file.display()));
+                    }
+                    command.push_str(" merge %O %A %B %P");
+                    git::install_merge_driver(
+                        MERGE_DRIVER,
+                        "Asset lib merge by field",
+                        &command,
+                        &project.dir,
+                        &project.lib_patterns(),
+                    )
+                });
+            match command {
+                Ok(added) => {
+                    println!("Registered the {} merge driver", MERGE_DRIVER);
+                    for line in added {
+                        println!("Added '{}' to .gitattributes", line);
+                    }
+                    ExitCode::SUCCESS
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<(Options, Command), String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(format: Option<AssetFormat>, layout: Option<AssetLayout>) -> Options {
+        Options {
+            project: None,
+            format,
+            layout,
+        }
+    }
+
+    #[test]
+    fn parse_args_commands() {
+        assert_eq!(parse(&[]), Ok((Options::default(), Command::Edit)));
+        assert_eq!(
+            parse(&["--format", "json"]),
+            Ok((options(Some(AssetFormat::Json), None), Command::Edit))
+        );
+        assert_eq!(
+            parse(&["--project", "game/asset-editor.toml"]),
+            Ok((
+                Options {
+                    project: Some("game/asset-editor.toml".to_string()),
+                    ..Options::default()
+                },
+                Command::Edit
+            ))
+        );
+        assert!(parse(&["--project"]).is_err());
+        assert_eq!(
+            parse(&["convert", "yaml"]),
+            Ok((
+                Options::default(),
+                Command::Convert {
+                    format: Some(AssetFormat::Yaml),
+                    layout: None
+                }
+            ))
+        );
+        assert_eq!(
+            parse(&["--format", "toml", "convert", "ron"]),
+            Ok((
+                options(Some(AssetFormat::Toml), None),
+                Command::Convert {
+                    format: Some(AssetFormat::Ron),
+                    layout: None
+                }
+            ))
+        );
+        assert_eq!(
+            parse(&["bundle", "asset/assets.bundle"]),
+            Ok((
+                Options::default(),
+                Command::Bundle {
+                    path: "asset/assets.bundle".to_string()
+                }
+            ))
+        );
+        assert!(parse(&["bundle"]).is_err());
+        assert_eq!(
+            parse(&["--format", "yaml", "migrate"]),
+            Ok((options(Some(AssetFormat::Yaml), None), Command::Migrate))
+        );
+        assert_eq!(parse(&["guids"]), Ok((Options::default(), Command::Guids)));
+        assert_eq!(
+            parse(&["renumber", "--format", "json"]),
+            Ok((
+                options(Some(AssetFormat::Json), None),
+                Command::Renumber { sides: None }
+            ))
+        );
+        assert_eq!(
+            parse(&["renumber", "main", "feature"]),
+            Ok((
+                Options::default(),
+                Command::Renumber {
+                    sides: Some(("main".to_string(), "feature".to_string()))
+                }
+            ))
+        );
+        assert!(parse(&["renumber", "main"]).is_err());
+        assert_eq!(
+            parse(&["--format", "json", "convert", "dir", "--layout", "dir"]),
+            Ok((
+                options(Some(AssetFormat::Json), Some(AssetLayout::Directory)),
+                Command::Convert {
+                    format: None,
+                    layout: Some(AssetLayout::Directory)
+                }
+            ))
+        );
+        assert_eq!(
+            parse(&["--layout", "directory", "convert", "file", "ron"]),
+            Ok((
+                options(None, Some(AssetLayout::Directory)),
+                Command::Convert {
+                    format: Some(AssetFormat::Ron),
+                    layout: Some(AssetLayout::File)
+                }
+            ))
+        );
+        assert_eq!(
+            parse(&["diff", "old.ron", "asset/def/item.ron"]),
+            Ok((
+                Options::default(),
+                Command::Diff {
+                    old: "old.ron".to_string(),
+                    new: "asset/def/item.ron".to_string(),
+                    lib: "asset/def/item.ron".to_string()
+                }
+            ))
+        );
+        assert_eq!(
+            parse(&["merge", "%O", "%A", "%B", "asset/def/item.ron"]),
+            Ok((
+                Options::default(),
+                Command::Merge {
+                    base: "%O".to_string(),
+                    ours: "%A".to_string(),
+                    theirs: "%B".to_string(),
+                    lib: "asset/def/item.ron".to_string()
+                }
+            ))
+        );
+        assert!(parse(&["merge", "base.ron", "ours.ron"]).is_err());
+        assert_eq!(
+            parse(&["merge-driver"]),
+            Ok((Options::default(), Command::MergeDriver))
+        );
+        assert!(parse(&["convert"]).is_err());
+        assert!(parse(&["convert", "folder"]).is_err());
+        assert!(parse(&["--layout", "folder"]).is_err());
+        assert!(parse(&["convert", "json", "bundle", "a"]).is_err());
+        assert!(parse(&["migrate", "bundle", "a"]).is_err());
+        assert!(parse(&["--format", "xml"]).is_err());
+        assert!(parse(&["--format"]).is_err());
+        assert!(parse(&["edit"]).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::cmp::min;
+
+use game_mechanic::prelude::*;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+};
+use term_system::text_input::TextInput;
+use term_system::window::Window;
+
+// How a bulk edit changes each field.
+//   `value`        Sets the field to `value`
+//   `= expression` Sets the field to the result of `expression`
+//   `* expression` Applies the operator to the field's current value, the same
+//                  goes for `+`, `-` and `/`
+#[derive(Debug, PartialEq, Clone)]
+pub enum BulkChange {
+    Value(String),
+    Assign(Expression),
+    Apply(Operator, Expression),
+}
+
+// A row of the bulk edit preview. `old` is `None` for assets that don't have
+// the field, and `new` is `None` until a change has been entered.
+pub struct BulkRow {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<Result<String, String>>,
+}
+
+pub struct BulkEdit {
+    pub path: TextInput,
+    pub change: TextInput,
+    // Whether the change is being typed, rather than the field path.
+    pub editing_change: bool,
+    pub state: TableState,
+}
+
+impl BulkChange {
+    pub fn parse(input: &str) -> Result<BulkChange, ExpressionError> {
+        let input = input.trim();
+        let operator = match input.chars().next() {
+            Some('=') => return Ok(BulkChange::Assign(Expression::parse(&input[1..])?)),
+            Some('+') => Operator::Add,
+            // `-5` is a negative value rather than subtracting 5.
+            Some('-') if !input[1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                Operator::Subtract
+            }
+            Some('*') => Operator::Multiply,
+            Some('/') => Operator::Divide,
+            _ => return Ok(BulkChange::Value(input.to_string())),
+        };
+        Ok(BulkChange::Apply(operator, Expression::parse(&input[1..])?))
+    }
+
+    // The new value for a field that currently holds `old`. Field paths in
+    // expressions are looked up with `variable`.
+    pub fn apply(
+        &self,
+        old: &str,
+        variable: &dyn Fn(&str) -> Option<Value>,
+    ) -> Result<Value, ExpressionError> {
+        match self {
+            BulkChange::Value(value) => Ok(Value::Text(value.clone())),
+            BulkChange::Assign(expression) => expression.eval(variable),
+            BulkChange::Apply(operator, expression) => {
+                operator.apply(&Value::from_field(old), &expression.eval(variable)?)
+            }
+        }
+    }
+}
+
+impl BulkEdit {
+    pub fn new() -> Self {
+        Self {
+            path: TextInput::new(),
+            change: TextInput::new(),
+            editing_change: false,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn input(&mut self) -> &mut TextInput {
+        if self.editing_change {
+            &mut self.change
+        } else {
+            &mut self.path
+        }
+    }
+
+    pub fn next(&mut self, num_rows: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // Draws the bulk edit over `area`, returning where the cursor should be.
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        window: Window,
+        rows: &[BulkRow],
+    ) -> Position {
+        let theme = window.theme;
+        let block = Block::default()
+            .title(format!("Bulk Edit ({} selected)", rows.len()))
+            .title_bottom("Enter to apply, Tab to switch input, Esc to cancel")
+            .borders(Borders::ALL)
+            .border_type(window.border_type)
+            .style(Style::default().fg(theme.white))
+            .title_style(Style::default().fg(theme.green));
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.bg(theme.black_dark).render(area, buf);
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Length(2),
+                Constraint::Fill(1),
+            ])
+            .split(inner);
+
+        let label_style =
+            |active: bool| Style::default().fg(if active { theme.green } else { theme.blue });
+        Paragraph::new(Line::from(vec![
+            Span::styled("Field:  ", label_style(!self.editing_change)),
+            Span::raw(self.path.text().to_string()),
+        ]))
+        .render(sections[0], buf);
+        Paragraph::new(Line::from(vec![
+            Span::styled("Change: ", label_style(self.editing_change)),
+            Span::raw(self.change.text().to_string()),
+        ]))
+        .render(sections[1], buf);
+        Paragraph::new("e.g. Common, = buy_value / 2, * 1.1 or + 5")
+            .fg(theme.white_dark)
+            .render(sections[2], buf);
+
+        let table_rows = rows.iter().map(|row| {
+            let (new, new_style) = match &row.new {
+                _ if row.old.is_none() => ("skipped".to_string(), theme.white_dark),
+                None => (String::new(), theme.white),
+                Some(Ok(new)) if Some(new) == row.old.as_ref() => (new.clone(), theme.white),
+                Some(Ok(new)) => (new.clone(), theme.yellow_light),
+                Some(Err(error)) => (error.clone(), theme.red),
+            };
+            Row::new(vec![
+                Cell::from(row.name.clone()).style(Style::default().fg(theme.blue)),
+                Cell::from(row.old.clone().unwrap_or("—".to_string()))
+                    .style(Style::default().fg(theme.white)),
+                Cell::from(new).style(Style::default().fg(new_style)),
+            ])
+        });
+        let name_width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0) as u16;
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Length(name_width + 1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(vec!["", "Old", "New"]).style(Style::default().fg(theme.green).bold()))
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+        StatefulWidget::render(table, sections[3], buf, &mut self.state);
+
+        let (section, label) = if self.editing_change {
+            (sections[1], "Change: ")
+        } else {
+            (sections[0], "Field:  ")
+        };
+        let (column, _) = self.input().cursor_position(u16::MAX, 0);
+        Position {
+            x: section.x + min(label.len() as u16 + column, section.width.saturating_sub(1)),
+            y: section.y,
+        }
+    }
+}
+
+impl Default for BulkEdit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(change: &str, old: &str) -> Result<Value, ExpressionError> {
+        BulkChange::parse(change)?.apply(old, &|name| match name {
+            "buy_value" => Some(Value::Number(100.0)),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn bulk_change_apply() {
+        assert_eq!(apply("= buy_value / 2", "10"), Ok(Value::Number(50.0)));
+        assert_eq!(apply("* 1.5", "10"), Ok(Value::Number(15.0)));
+        assert_eq!(apply("+ 5", "10"), Ok(Value::Number(15.0)));
+        assert_eq!(apply("- 5", "10"), Ok(Value::Number(5.0)));
+        assert_eq!(apply("/ 4", "10"), Ok(Value::Number(2.5)));
+        assert_eq!(apply("-5", "10"), Ok(Value::Text("-5".to_string())));
+        assert_eq!(apply("Rare", "Common"), Ok(Value::Text("Rare".to_string())));
+        assert_eq!(
+            apply("* 2", "Common"),
+            Err(ExpressionError::NotANumber("Common".to_string()))
+        );
+    }
+}
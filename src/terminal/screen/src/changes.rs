@@ -0,0 +1,258 @@
+use std::cmp::min;
+
+use game_system::prelude::AssetType;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use std::collections::HashMap;
+use std::fmt;
+use term_system::window::Window;
+
+// How an asset differs from the committed version of its lib.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+// A field of a modified asset, by its reflected path. The values are `None`
+// when only one side has the field, such as an added list entry.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AssetChange {
+    pub asset_type: AssetType,
+    pub id: u32,
+    pub name: String,
+    pub kind: ChangeKind,
+    // The changed fields of modified assets. Added and removed assets have
+    // none.
+    pub fields: Vec<FieldChange>,
+}
+
+// The assets that differ from a revision of their libs, with a row for each
+// asset and one under it for each of its changed fields.
+pub struct ChangesView {
+    // The revision compared against, e.g. `HEAD (efaf240)`.
+    pub revision: String,
+    // Each lib at the revision as a value, by lib name, or `Value::Null` if it
+    // didn't exist.
+    pub committed: HashMap<&'static str, serde_json::Value>,
+    pub changes: Vec<AssetChange>,
+    pub state: TableState,
+}
+
+impl ChangeKind {
+    fn symbol(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "+",
+            ChangeKind::Removed => "-",
+            ChangeKind::Modified => "~",
+        }
+    }
+}
+
+// An asset's kind of change, type, id and name, then a line per changed
+// field, e.g. `~ Item 1 Bread` and `    buy_value: 3 → 1`.
+impl fmt::Display for AssetChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.kind.symbol(),
+            self.asset_type,
+            self.id,
+            self.name
+        )?;
+        for field in &self.fields {
+            write!(
+                f,
+                "\n    {}: {} → {}",
+                field.path,
+                field.old.as_deref().unwrap_or("—"),
+                field.new.as_deref().unwrap_or("—")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl ChangesView {
+    pub fn new(
+        revision: String,
+        committed: HashMap<&'static str, serde_json::Value>,
+        changes: Vec<AssetChange>,
+    ) -> Self {
+        Self {
+            revision,
+            committed,
+            changes,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    // Each row as the index of its asset's change, and of the field for field
+    // rows.
+    fn rows(&self) -> Vec<(usize, Option<usize>)> {
+        let mut rows = vec![];
+        for (i, change) in self.changes.iter().enumerate() {
+            rows.push((i, None));
+            rows.extend((0..change.fields.len()).map(|field| (i, Some(field))));
+        }
+        rows
+    }
+
+    // The selected asset change, and the selected field if a field row is
+    // selected.
+    pub fn selected(&self) -> Option<(&AssetChange, Option<&FieldChange>)> {
+        let (change, field) = *self.rows().get(self.state.selected()?)?;
+        let change = &self.changes[change];
+        Some((change, field.map(|field| &change.fields[field])))
+    }
+
+    // Replaces the changes after some are reverted, keeping the selection in
+    // place as far as possible.
+    pub fn set_changes(&mut self, changes: Vec<AssetChange>) {
+        self.changes = changes;
+        let num_rows = self.rows().len();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected, num_rows.saturating_sub(1))));
+    }
+
+    pub fn next(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, self.rows().len().saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, window: Window) {
+        let theme = window.theme;
+        let count = |kind: ChangeKind| {
+            self.changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .count()
+        };
+        let title = format!(
+            "Changes since {}: {} added, {} removed, {} modified",
+            self.revision,
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Modified)
+        );
+        let rows = self.rows().into_iter().map(|(change, field)| {
+            let change = &self.changes[change];
+            match field {
+                None => {
+                    let color = match change.kind {
+                        ChangeKind::Added => theme.green,
+                        ChangeKind::Removed => theme.red,
+                        ChangeKind::Modified => theme.yellow,
+                    };
+                    Row::new(vec![
+                        Cell::from(format!(
+                            "{} {} {} {}",
+                            change.kind.symbol(),
+                            change.asset_type,
+                            change.id,
+                            change.name
+                        ))
+                        .style(Style::default().fg(color).bold()),
+                        Cell::from(""),
+                        Cell::from(""),
+                    ])
+                }
+                Some(field) => {
+                    let field = &change.fields[field];
+                    Row::new(vec![
+                        Cell::from(format!("    {}", field.path))
+                            .style(Style::default().fg(theme.blue)),
+                        Cell::from(field.old.clone().unwrap_or("—".to_string()))
+                            .style(Style::default().fg(theme.white)),
+                        Cell::from(format!(
+                            "→ {}",
+                            field.new.clone().unwrap_or("—".to_string())
+                        ))
+                        .style(Style::default().fg(theme.yellow_light)),
+                    ])
+                }
+            }
+        });
+        let footer = if self.changes.is_empty() {
+            "Nothing has changed. Esc to close"
+        } else {
+            "r to revert the selected field or asset, Esc to close"
+        };
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+            ],
+        )
+        .header(
+            Row::new(vec!["Asset", self.revision.as_str(), "Now"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title(title)
+                .title_bottom(footer)
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green)),
+        )
+        .bg(theme.black_dark)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_change_display() {
+        let change = AssetChange {
+            asset_type: AssetType::Item,
+            id: 1,
+            name: "Stale Bread".to_string(),
+            kind: ChangeKind::Modified,
+            fields: vec![
+                FieldChange {
+                    path: "buy_value".to_string(),
+                    old: Some("3".to_string()),
+                    new: Some("1".to_string()),
+                },
+                FieldChange {
+                    path: "modifiers[0].amount".to_string(),
+                    old: None,
+                    new: Some("5".to_string()),
+                },
+            ],
+        };
+        assert_eq!(
+            change.to_string(),
+            "~ Item 1 Stale Bread\n    buy_value: 3 → 1\n    modifiers[0].amount: — → 5"
+        );
+    }
+}
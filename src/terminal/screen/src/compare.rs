@@ -0,0 +1,163 @@
+use std::cmp::min;
+
+use bevy_reflect::Reflect;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+use crate::database::paired_fields;
+
+// Which side of the comparison a value is copied to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CompareSide {
+    Left,
+    Right,
+}
+
+// A row of the comparison. The values are `None` for fields that only one of
+// the two defs has.
+pub struct CompareRow {
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+// Two assets shown next to each other, one field per row.
+pub struct CompareView<T> {
+    pub left: T,
+    pub right: T,
+    pub state: TableState,
+    // Why the last value couldn't be copied.
+    pub error: Option<String>,
+}
+
+impl CompareRow {
+    // Every field of two defs, lined up by path.
+    pub fn pair(left: &dyn Reflect, right: &dyn Reflect) -> Vec<CompareRow> {
+        paired_fields(left, right)
+            .into_iter()
+            .map(|(path, left, right)| CompareRow { path, left, right })
+            .collect()
+    }
+
+    pub fn differs(&self) -> bool {
+        self.left != self.right
+    }
+}
+
+impl<T> CompareView<T> {
+    pub fn new(left: T, right: T) -> Self {
+        Self {
+            left,
+            right,
+            state: TableState::default().with_selected(0),
+            error: None,
+        }
+    }
+
+    pub fn next(&mut self, num_rows: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        window: Window,
+        titles: (&str, &str),
+        rows: &[CompareRow],
+    ) {
+        let theme = window.theme;
+        let path_width = rows.iter().map(|row| row.path.len()).max().unwrap_or(0) as u16;
+        let table_rows = rows.iter().map(|row| {
+            let value_style = if row.differs() {
+                Style::default().fg(theme.yellow_light)
+            } else {
+                Style::default().fg(theme.white)
+            };
+            Row::new(vec![
+                Cell::from(row.path.clone()).style(Style::default().fg(theme.blue)),
+                Cell::from(row.left.clone().unwrap_or("—".to_string())).style(value_style),
+                Cell::from(row.right.clone().unwrap_or("—".to_string())).style(value_style),
+            ])
+        });
+
+        let hint = match &self.error {
+            Some(error) => Line::styled(error.clone(), Style::default().fg(theme.red)),
+            None => Line::from("←/→ Copy value to that side, Esc to close"),
+        };
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Length(path_width + 1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["", titles.0, titles.1]).style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title(format!("Compare: {} ↔ {}", titles.0, titles.1))
+                .title_bottom(hint)
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green)),
+        )
+        .bg(theme.black_dark)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_mechanic::prelude::*;
+    use game_system::prelude::AssetLib;
+
+    #[test]
+    fn compare_rows_pair_fields() {
+        let item_lib = ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR));
+        let (potion, shoe) = (item_lib.id(0), item_lib.id(1));
+        let rows = CompareRow::pair(potion, shoe);
+        let row = |path: &str| rows.iter().find(|row| row.path == path).unwrap();
+
+        assert_eq!(rows[0].path, "id");
+        assert_eq!(row("name").left.as_deref(), Some("Red Potion"));
+        assert_eq!(row("name").right.as_deref(), Some("Shoe"));
+        assert!(row("name").differs());
+        assert!(!row("rules_text").differs());
+        assert_eq!(row("equipment_def.armor").left.as_deref(), Some("0"));
+        assert_eq!(row("equipment_def.armor").right.as_deref(), Some("5"));
+
+        // Only the shoe has a modifier.
+        let modifier = row("equipment_def.modifiers[0].value");
+        assert_eq!(modifier.left, None);
+        assert_eq!(modifier.right.as_deref(), Some("10"));
+        assert!(modifier.differs());
+
+        // Every field is paired once, and a def compared with itself has no
+        // differences.
+        let mut paths: Vec<&str> = rows.iter().map(|row| row.path.as_str()).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), rows.len());
+        assert!(CompareRow::pair(shoe, shoe)
+            .iter()
+            .all(|row| !row.differs()));
+    }
+}
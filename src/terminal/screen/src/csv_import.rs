@@ -0,0 +1,242 @@
+use std::cmp::min;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+// The defs of one asset lib as a spreadsheet, one column per field path and
+// one row per def.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvSheet {
+    pub columns: Vec<String>,
+    pub rows: Vec<CsvRow>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvRow {
+    // The line the row starts on, counting the header as line 1, or 0 for rows
+    // that weren't read from a file.
+    pub line: u64,
+    pub cells: Vec<String>,
+}
+
+// A row that couldn't be imported. None of its changes are applied.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvRowError {
+    pub line: u64,
+    pub message: String,
+}
+
+// A field that the import changes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvChange<T> {
+    pub asset: T,
+    pub name: String,
+    pub path: String,
+    pub old: String,
+    pub new: String,
+}
+
+// The changes a CSV file makes to an asset lib, previewed before they're
+// applied.
+pub struct CsvImport<T> {
+    pub path: String,
+    pub changes: Vec<CsvChange<T>>,
+    pub errors: Vec<CsvRowError>,
+    pub state: TableState,
+}
+
+impl CsvSheet {
+    // Reads a sheet with a header row. Rows that can't be read, such as ones
+    // with the wrong number of cells, are returned as errors and left out.
+    pub fn parse(data: &str) -> Result<(CsvSheet, Vec<CsvRowError>), String> {
+        let mut reader = csv::Reader::from_reader(data.as_bytes());
+        let columns: Vec<String> = reader
+            .headers()
+            .map_err(|error| format!("The header row could not be read: {}", error))?
+            .iter()
+            .map(|column| column.trim().to_string())
+            .collect();
+        if !columns.iter().any(|column| column == "id") {
+            return Err("There is no id column".to_string());
+        }
+        let mut rows = vec![];
+        let mut errors = vec![];
+        for record in reader.records() {
+            match record {
+                Ok(record) => rows.push(CsvRow {
+                    line: record.position().map_or(0, |position| position.line()),
+                    cells: record.iter().map(|cell| cell.to_string()).collect(),
+                }),
+                Err(error) => errors.push(CsvRowError {
+                    line: error.position().map_or(0, |position| position.line()),
+                    message: match error.kind() {
+                        csv::ErrorKind::UnequalLengths {
+                            expected_len, len, ..
+                        } => format!("Expected {} cells, found {}", expected_len, len),
+                        _ => error.to_string(),
+                    },
+                }),
+            }
+        }
+        Ok((CsvSheet { columns, rows }, errors))
+    }
+
+    pub fn to_csv(&self) -> Result<String, String> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record(&self.columns)
+            .map_err(|error| error.to_string())?;
+        for row in &self.rows {
+            writer
+                .write_record(&row.cells)
+                .map_err(|error| error.to_string())?;
+        }
+        let data = writer.into_inner().map_err(|error| error.to_string())?;
+        String::from_utf8(data).map_err(|error| error.to_string())
+    }
+
+    // The cells of a row by column name.
+    pub fn cells<'a>(&'a self, row: &'a CsvRow) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.columns
+            .iter()
+            .map(String::as_str)
+            .zip(row.cells.iter().map(String::as_str))
+    }
+}
+
+impl<T> CsvImport<T> {
+    pub fn new(path: String, changes: Vec<CsvChange<T>>, errors: Vec<CsvRowError>) -> Self {
+        Self {
+            path,
+            changes,
+            errors,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    fn num_rows(&self) -> usize {
+        self.changes.len() + self.errors.len()
+    }
+
+    pub fn next(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, self.num_rows().saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // Lists the rows that failed to import, then every changed field.
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, window: Window) {
+        let theme = window.theme;
+        let error_rows = self.errors.iter().map(|error| {
+            Row::new(vec![
+                Cell::from(format!("Line {}", error.line)).style(Style::default().fg(theme.red)),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(error.message.clone()).style(Style::default().fg(theme.red)),
+            ])
+        });
+        let change_rows = self.changes.iter().map(|change| {
+            Row::new(vec![
+                Cell::from(change.name.clone()).style(Style::default().fg(theme.blue)),
+                Cell::from(change.path.clone()),
+                Cell::from(change.old.clone()).style(Style::default().fg(theme.white)),
+                Cell::from(change.new.clone()).style(Style::default().fg(theme.yellow_light)),
+            ])
+        });
+        let footer = if self.changes.is_empty() {
+            "Nothing to change. Esc to close"
+        } else {
+            "Enter to apply, Esc to cancel"
+        };
+        let table = Table::new(
+            error_rows.chain(change_rows),
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(3),
+            ],
+        )
+        .header(
+            Row::new(vec!["Asset", "Field", "Old", "New"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title(format!(
+                    "Import {}: {} changes, {} rows skipped",
+                    self.path,
+                    self.changes.len(),
+                    self.errors.len()
+                ))
+                .title_bottom(footer)
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green)),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_sheet_round_trip() {
+        let sheet = CsvSheet {
+            columns: vec!["id".to_string(), "rules_text".to_string()],
+            rows: vec![
+                CsvRow {
+                    line: 2,
+                    cells: vec!["0".to_string(), "Heals, \"a lot\"\nof health".to_string()],
+                },
+                CsvRow {
+                    line: 4,
+                    cells: vec!["1".to_string(), String::new()],
+                },
+            ],
+        };
+        let (parsed, errors) = CsvSheet::parse(&sheet.to_csv().unwrap()).unwrap();
+        assert_eq!(parsed, sheet);
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn csv_sheet_parse_errors() {
+        assert_eq!(
+            CsvSheet::parse("name,buy_value\nRed Potion,10\n"),
+            Err("There is no id column".to_string())
+        );
+
+        let (sheet, errors) = CsvSheet::parse("id, name\n0,Red Potion\n1\n2,Shoe\n").unwrap();
+        assert_eq!(sheet.columns, vec!["id", "name"]);
+        assert_eq!(sheet.rows.len(), 2);
+        assert_eq!(sheet.rows[1].line, 4);
+        assert_eq!(
+            sheet.cells(&sheet.rows[1]).collect::<Vec<_>>(),
+            vec![("id", "2"), ("name", "Shoe")]
+        );
+        assert_eq!(
+            errors,
+            vec![CsvRowError {
+                line: 3,
+                message: "Expected 2 cells, found 1".to_string()
+            }]
+        );
+    }
+}
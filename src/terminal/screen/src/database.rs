@@ -1,26 +1,57 @@
-use game_mechanic::item::equipment::EquipmentSlot;
 use game_mechanic::prelude::*;
-use game_system::asset::asset_lib::AssetLib;
-use game_system::prelude::AssetType;
+use game_system::asset::asset_lib::{AssetDef, AssetLib};
+use game_system::prelude::{
+    assign_guids, merge_libs, migration_report, renumber_collisions, AssetFormat, AssetLayout,
+    AssetType, BundleError, BundleWriter, LibFiles, MarkupText, MergeConflict, MergeSide,
+    MergedLib, MigrationReport, MultiLine, RenumberReport,
+};
 use image::DynamicImage;
 use ratatui::widgets::{
-    Block, Borders, List, ListDirection, ListItem, ListState, Paragraph, Scrollbar,
+    Block, Borders, Clear, List, ListDirection, ListItem, ListState, Paragraph, Scrollbar,
     ScrollbarOrientation, ScrollbarState, Wrap,
 };
 
 use std::cmp::min;
-use std::fmt::Display;
-use std::io;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use term_system::terminal_image::{load_image, set_background_color, UNKNOWN_IMAGE_PATH};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{fs, io};
+use term_system::keymap::Action;
+use term_system::terminal_image::{load_image, set_background_color};
+use term_system::text_input::TextInput;
 use term_system::window::{Screen, Window, WindowName};
 use term_system::{terminal_image, tui};
 
-use bevy_reflect::{GetPath, PartialReflect, Reflect, Struct};
+use crate::bulk_edit::{BulkChange, BulkEdit, BulkRow};
+use crate::changes::{AssetChange, ChangeKind, ChangesView, FieldChange};
+use crate::compare::{CompareRow, CompareSide, CompareView};
+use crate::csv_import::{CsvChange, CsvImport, CsvRow, CsvRowError, CsvSheet};
+use crate::economy_report::EconomyReportView;
+use crate::file_watch::FileWatcher;
+use crate::git;
+use crate::inventory::InventoryView;
+use crate::loot_simulation::LootSimulationView;
+use crate::merge_prompt::MergePrompt;
+use crate::project::Project;
+use crate::recipe_graph::RecipeGraphView;
+use crate::tooltip::{TooltipAlign, TooltipStyle, TooltipTemplate};
+
+use bevy_reflect::{
+    prelude::ReflectDefault, DynamicEnum, DynamicVariant, GetPath, PartialReflect, Reflect,
+    ReflectMut, ReflectRef, TypeInfo, TypeRegistry, VariantInfo, VariantType,
+};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::prelude::*;
+use unicode_width::UnicodeWidthStr;
+
+// String fields longer than this are edited in the multi-line editor.
+const LONG_TEXT_WIDTH: usize = 60;
 
-const MAGIC_CURSOR_SYMBOL: &str = "ඞ";
+// How often the file events for the asset libs and icons are checked while
+// waiting for input.
+const FILE_WATCH_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Clone, Reflect)]
 struct Asset {
@@ -37,15 +68,27 @@ struct AssetList {
 
 pub struct Database {
     window: Window,
+    // Where the assets are stored, and how.
+    project: Project,
     // All assets from each asset lib.
     assets: Vec<Asset>,
     // All assets that match the current search.
     // What shows up in the assets frame
     visible_assets: AssetList,
+    // Ability assets
+    ability_lib: AbilityLib,
     // Aura assets
     aura_lib: AuraLib,
+    // Character assets
+    character_lib: CharacterLib,
     // Item assets
     item_lib: ItemLib,
+    // Loot table assets
+    loot_table_lib: LootTableLib,
+    // Recipe assets
+    recipe_lib: RecipeLib,
+    // Vendor assets
+    vendor_lib: VendorLib,
     // The currently selected frame
     active_frame: DatabaseFrame,
     // How many lines have been scrolled in the details frame.
@@ -53,23 +96,63 @@ pub struct Database {
     // How much you can scroll in the details frame.
     max_details_scroll: u16,
     // Visible text in the search frame.
-    search_input: String,
-    // Current character the cursor is at in the search input
-    search_character_index: usize,
+    search_input: TextInput,
     // Visible text for the field being edited
-    details_input: String,
-    // Current character the cursor is at in the details input
-    details_character_index: usize,
+    details_input: TextInput,
     // Which detail is currently selected
     details_index: usize,
     // Whether a detail is being edited
     editing_details: bool,
-    // The asset currently visible in the details frame
-    current_asset: Asset,
+    // How many lines have been scrolled in the multi-line editor.
+    editor_scroll: u16,
+    // The width text is wrapped to in the multi-line editor.
+    editor_width: u16,
+    // The asset currently visible in the details frame, if any are visible.
+    current_asset: Option<Asset>,
     // All of the field names belonging to the current asset
     current_asset_fields: Vec<String>,
     // The global (x,y) position of the cursor.
     cursor_position: Position,
+    // Whether the tooltip preview is shown in place of the icon.
+    show_preview: bool,
+    // How the tooltip preview is laid out.
+    tooltip_template: TooltipTemplate,
+    // The asset marked to be compared against.
+    marked_asset: Option<Asset>,
+    // The comparison shown in place of the details frame, if open.
+    compare: Option<CompareView<Asset>>,
+    // The loot simulation shown in place of the details frame, if open.
+    loot_simulation: Option<LootSimulationView<Asset>>,
+    // The economy report shown in place of the details frame, if open.
+    economy_report: Option<EconomyReportView>,
+    // The recipe graph shown in place of the details frame, if open.
+    recipe_graph: Option<RecipeGraphView>,
+    // The inventory save file shown in place of the details frame, if open.
+    inventory: Option<InventoryView>,
+    // The changes since the last commit, shown in place of the details frame,
+    // if open.
+    changes: Option<ChangesView>,
+    // The CSV import preview shown in place of the details frame, if open.
+    csv_import: Option<CsvImport<Asset>>,
+    // What happened with the last action that has no view of its own, such
+    // as a CSV export, a reload or a revert.
+    status: Option<String>,
+    // Assets selected for bulk editing.
+    selected_assets: Vec<Asset>,
+    // The bulk edit shown over the details frame, if open.
+    bulk_edit: Option<BulkEdit>,
+    // Why the last edit to a detail field couldn't be saved.
+    details_error: Option<String>,
+    // Type data for the defs, used to create new list elements.
+    type_registry: TypeRegistry,
+    // Notices the asset libs and icons changing on disk.
+    file_watcher: FileWatcher,
+    // Each lib as it was last read from or written to disk, by lib name. Edits
+    // are what the libs in memory have changed from these.
+    saved_libs: HashMap<&'static str, serde_json::Value>,
+    // The merge shown in place of the details frame when a lib with edits
+    // changes on disk, if open.
+    merge_prompt: Option<MergePrompt>,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -81,48 +164,7 @@ enum DatabaseFrame {
 
 impl Screen for Database {
     fn new(window: Window) -> Self {
-        // TODO: Make asset lib loading and asset rendering more generic.
-        let aura_lib = AuraLib::new("asset/def/aura.ron");
-        let item_lib = ItemLib::new("asset/def/item.ron");
-        let mut assets: Vec<Asset> = vec![];
-        for def in &aura_lib.defs {
-            assets.push(Asset {
-                name: def.name.clone(),
-                id: def.id,
-                asset_type: AssetType::Aura,
-                icon: def.icon.clone(),
-            })
-        }
-        for def in &item_lib.defs {
-            assets.push(Asset {
-                name: def.name.clone(),
-                id: def.id,
-                asset_type: AssetType::Item,
-                icon: def.icon.clone(),
-            })
-        }
-        let current_asset = assets[0].clone();
-        let num_assets = assets.len();
-        let visible_assets = AssetList::from_assets((0..num_assets).collect());
-        Self {
-            window,
-            aura_lib,
-            item_lib,
-            assets,
-            visible_assets,
-            active_frame: DatabaseFrame::Search,
-            details_scroll: 0,
-            max_details_scroll: 0,
-            search_input: String::new(),
-            search_character_index: 0,
-            details_input: String::new(),
-            details_character_index: 0,
-            details_index: 0,
-            editing_details: false,
-            current_asset,
-            current_asset_fields: vec![],
-            cursor_position: Position { x: 1, y: 1 },
-        }
+        Self::with_project(window, Project::default())
     }
 
     fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<WindowName> {
@@ -149,26 +191,145 @@ impl Screen for Database {
                 frame.set_cursor_position(self.cursor_position);
             });
             let _ = self.handle_events();
+            // Leaving waits for the edits to be saved, such as after merging
+            // changes made to their files on disk.
+            if self.window.quit && !self.save_libs() {
+                self.window.quit = false;
+            }
         }
-        self.aura_lib.save("asset/def/aura.ron");
-        self.item_lib.save("asset/def/item.ron");
         Ok(WindowName::Menu)
     }
 
+    // Waits for input, stopping early to redraw if files change on disk. File
+    // events are checked for each time `FILE_WATCH_INTERVAL` passes without
+    // input.
     fn handle_events(&mut self) -> io::Result<()> {
+        while !event::poll(FILE_WATCH_INTERVAL)? {
+            if self.reload_changed_files() {
+                return Ok(());
+            }
+        }
         match event::read()? {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
             }
+            Event::Paste(text) => self.handle_paste(&text),
             _ => {}
         };
         Ok(())
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        let action = self.window.keymap.action(key_event);
+        if let Some(merge_prompt) = self.merge_prompt.as_mut() {
+            match key_event.code {
+                KeyCode::Up => merge_prompt.previous(),
+                KeyCode::Down => merge_prompt.next(),
+                KeyCode::Left => merge_prompt.take(MergeSide::Ours),
+                KeyCode::Right => merge_prompt.take(MergeSide::Theirs),
+                KeyCode::Char('m') => merge_prompt.take_all(MergeSide::Ours),
+                KeyCode::Char('d') => merge_prompt.take_all(MergeSide::Theirs),
+                KeyCode::Enter => self.commit_merge_prompt(),
+                _ => {}
+            }
+            return;
+        }
+        if self.compare.is_some() {
+            self.handle_compare_key_event(key_event);
+            return;
+        }
+        if self.bulk_edit.is_some() {
+            self.handle_bulk_edit_key_event(key_event);
+            return;
+        }
+        if let Some(loot_simulation) = self.loot_simulation.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.loot_simulation = None,
+                _ if action == Some(Action::Simulate) => self.loot_simulation = None,
+                KeyCode::Up => loot_simulation.previous(),
+                KeyCode::Down => loot_simulation.next(),
+                KeyCode::Left => loot_simulation.fewer_rolls(),
+                KeyCode::Right => loot_simulation.more_rolls(),
+                KeyCode::Char('r') => loot_simulation.reseed(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(economy_report) = self.economy_report.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.economy_report = None,
+                _ if action == Some(Action::EconomyReport) => self.economy_report = None,
+                KeyCode::Up => economy_report.previous(),
+                KeyCode::Down => economy_report.next(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(recipe_graph) = self.recipe_graph.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.recipe_graph = None,
+                _ if action == Some(Action::RecipeGraph) => self.recipe_graph = None,
+                KeyCode::Up => recipe_graph.previous(),
+                KeyCode::Down => recipe_graph.next(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(inventory) = self.inventory.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.inventory = None,
+                _ if action == Some(Action::Inventory) => self.inventory = None,
+                KeyCode::Up => inventory.previous(),
+                KeyCode::Down => inventory.next(),
+                KeyCode::Left => inventory.take_one(),
+                KeyCode::Right => inventory.add_one(),
+                KeyCode::Enter => {
+                    self.status = Some(match inventory.save() {
+                        Ok(()) => format!("Saved {}", inventory.path),
+                        Err(error) => error,
+                    });
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let Some(changes) = self.changes.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.changes = None,
+                _ if action == Some(Action::Changes) => self.changes = None,
+                KeyCode::Up => changes.previous(),
+                KeyCode::Down => changes.next(),
+                KeyCode::Char('r') => self.revert_change(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some(csv_import) = self.csv_import.as_mut() {
+            match key_event.code {
+                KeyCode::Esc => self.csv_import = None,
+                KeyCode::Up => csv_import.previous(),
+                KeyCode::Down => csv_import.next(),
+                KeyCode::Enter => self.commit_csv_import(),
+                _ => {}
+            }
+            return;
+        }
+
         // Window wide hotkeys
         match key_event.code {
-            KeyCode::Esc => self.window.quit = true,
+            KeyCode::Esc => {
+                if self.editing_details {
+                    self.details_input.clear();
+                    self.details_error = None;
+                    self.editing_details = false;
+                } else {
+                    self.window.quit = true
+                }
+            }
+            _ if action == Some(Action::TooltipPreview) => {
+                self.show_preview = !self.show_preview;
+                return;
+            }
             KeyCode::Tab => {
                 self.active_frame = match self.active_frame {
                     DatabaseFrame::Search => DatabaseFrame::Assets,
@@ -183,34 +344,11 @@ impl Screen for Database {
             DatabaseFrame::Search => {
                 self.editing_details = false;
                 match key_event.code {
-                    KeyCode::Char(to_insert) => {
-                        self.search_input
-                            .insert(self.search_character_index, to_insert);
-                        self.search_character_index += 1;
-                    }
-                    KeyCode::Backspace => {
-                        if self.search_character_index > 0 {
-                            self.search_input
-                                .remove(self.search_character_index.saturating_sub(1));
-                            self.search_character_index =
-                                self.search_character_index.saturating_sub(1);
-                        }
-                    }
-                    KeyCode::Left => {
-                        self.search_character_index = self.search_character_index.saturating_sub(1);
+                    KeyCode::Enter => self.search_input.clear(),
+                    _ => {
+                        self.search_input.handle_key_event(key_event);
                     }
-                    KeyCode::Right => {
-                        self.search_character_index =
-                            min(self.search_character_index + 1, self.search_input.len())
-                    }
-                    KeyCode::Enter => {
-                        self.search_character_index = 0;
-                        self.search_input.clear();
-                    }
-                    _ => {}
                 };
-                self.cursor_position.y = 1;
-                self.cursor_position.x = (self.search_character_index + 1) as u16;
             }
             DatabaseFrame::Assets => {
                 self.details_scroll = 0;
@@ -224,59 +362,117 @@ impl Screen for Database {
                         self.details_index = 0;
                         self.visible_assets.next()
                     }
-                    _ => {}
+                    _ => match action {
+                        Some(Action::Mark) => {
+                            let Some(current) = self.current_asset.clone() else {
+                                return;
+                            };
+                            self.marked_asset = match &self.marked_asset {
+                                Some(marked) if marked.is(&current) => None,
+                                _ => Some(current),
+                            };
+                        }
+                        Some(Action::Compare) => {
+                            if let (Some(marked), Some(current)) =
+                                (&self.marked_asset, &self.current_asset)
+                            {
+                                self.compare =
+                                    Some(CompareView::new(marked.clone(), current.clone()));
+                            }
+                        }
+                        Some(Action::Select) => {
+                            let Some(current) = self.current_asset.clone() else {
+                                return;
+                            };
+                            if self.is_selected(&current) {
+                                self.selected_assets.retain(|asset| !asset.is(&current));
+                            } else {
+                                self.selected_assets.push(current);
+                            }
+                        }
+                        Some(Action::SelectAll) => self.select_all_visible(),
+                        Some(Action::Simulate) => {
+                            let loot_table = self
+                                .current_asset
+                                .clone()
+                                .filter(|asset| asset.asset_type == AssetType::LootTable);
+                            if let Some(loot_table) = loot_table {
+                                self.loot_simulation = Some(LootSimulationView::new(loot_table));
+                            }
+                        }
+                        Some(Action::EconomyReport) => {
+                            self.economy_report = Some(EconomyReportView::new(EconomyReport::new(
+                                &self.vendor_lib,
+                                &self.item_lib,
+                            )));
+                        }
+                        Some(Action::RecipeGraph) => {
+                            self.recipe_graph = Some(RecipeGraphView::new(RecipeGraph::new(
+                                &self.recipe_lib,
+                                &self.item_lib,
+                            )));
+                        }
+                        Some(Action::Inventory) => {
+                            let path = self.project.asset_path(&self.project.inventory);
+                            match InventoryView::open(&path) {
+                                Ok(inventory) => self.inventory = Some(inventory),
+                                Err(error) => self.status = Some(error),
+                            }
+                        }
+                        Some(Action::Changes) => match self.open_changes() {
+                            Ok(changes) => self.changes = Some(changes),
+                            Err(error) => self.status = Some(error),
+                        },
+                        Some(Action::ExportCsv) => {
+                            let Some(asset) = &self.current_asset else {
+                                return;
+                            };
+                            let asset_type = asset.asset_type;
+                            self.status = Some(match self.export_csv(asset_type) {
+                                Ok(path) => format!("Exported {}", path),
+                                Err(error) => error,
+                            });
+                        }
+                        Some(Action::ImportCsv) => {
+                            let Some(asset) = &self.current_asset else {
+                                return;
+                            };
+                            match self.preview_csv_import(asset.asset_type) {
+                                Ok(csv_import) => self.csv_import = Some(csv_import),
+                                Err(error) => self.status = Some(error),
+                            }
+                        }
+                        Some(Action::BulkEdit) => {
+                            if self.selected_assets.is_empty() {
+                                self.selected_assets.extend(self.current_asset.clone());
+                            }
+                            if !self.selected_assets.is_empty() {
+                                self.bulk_edit = Some(BulkEdit::new());
+                            }
+                        }
+                        _ => {}
+                    },
                 };
             }
             DatabaseFrame::Details => {
                 if self.editing_details {
+                    let select = key_event.modifiers.contains(KeyModifiers::SHIFT);
                     match key_event.code {
-                        KeyCode::Char(to_insert) => {
-                            self.details_input
-                                .insert(self.details_character_index, to_insert);
-                            self.details_character_index += 1;
+                        KeyCode::Enter if !self.details_input.is_multi_line() => {
+                            self.commit_details_input()
                         }
-                        KeyCode::Backspace => {
-                            if self.details_character_index > 0 {
-                                self.details_input
-                                    .remove(self.details_character_index.saturating_sub(1));
-                                self.details_character_index =
-                                    self.details_character_index.saturating_sub(1);
-                            }
+                        KeyCode::Char('s') if key_event.modifiers == KeyModifiers::CONTROL => {
+                            self.commit_details_input()
                         }
-                        KeyCode::Left => {
-                            self.details_character_index =
-                                self.details_character_index.saturating_sub(1);
+                        KeyCode::Up if self.details_input.is_multi_line() => {
+                            self.details_input.move_up(self.editor_width, select)
                         }
-                        KeyCode::Right => {
-                            self.details_character_index =
-                                min(self.details_character_index + 1, self.details_input.len());
+                        KeyCode::Down if self.details_input.is_multi_line() => {
+                            self.details_input.move_down(self.editor_width, select)
                         }
-                        KeyCode::Enter => {
-                            match self.current_asset.asset_type {
-                                AssetType::Aura => {
-                                    let mut aura = self.aura_lib.id(self.current_asset.id).clone();
-                                    set_field_value_from_string(
-                                        &mut aura,
-                                        &self.current_asset_fields[self.details_index],
-                                        self.details_input.clone(),
-                                    );
-                                    self.aura_lib.update_def(aura.into());
-                                }
-                                AssetType::Item => {
-                                    let mut item = self.item_lib.id(self.current_asset.id).clone();
-                                    set_field_value_from_string(
-                                        &mut item,
-                                        &self.current_asset_fields[self.details_index],
-                                        self.details_input.clone(),
-                                    );
-                                    self.item_lib.update_def(item.into());
-                                }
-                            };
-                            self.details_character_index = 0;
-                            self.details_input.clear();
-                            self.editing_details = false;
+                        _ => {
+                            self.details_input.handle_key_event(key_event);
                         }
-                        _ => {}
                     };
                 } else {
                     match key_event.code {
@@ -292,7 +488,7 @@ impl Screen for Database {
                             KeyModifiers::SHIFT => {
                                 self.details_index = min(
                                     self.details_index.saturating_add(1),
-                                    self.current_asset_fields.len() - 1,
+                                    self.current_asset_fields.len().saturating_sub(1),
                                 );
                             }
                             _ => {
@@ -302,19 +498,23 @@ impl Screen for Database {
                                 );
                             }
                         },
+                        _ if action == Some(Action::AddListEntry) => self.add_list_element(),
+                        _ if action == Some(Action::RemoveListEntry) => self.remove_list_element(),
                         KeyCode::Enter => {
-                            self.editing_details = true;
-                            self.details_input = match self.current_asset.asset_type {
-                                AssetType::Aura => get_string_value_from_path(
-                                    self.aura_lib.id(self.current_asset.id),
-                                    &self.current_asset_fields[self.details_index],
-                                ),
-                                AssetType::Item => get_string_value_from_path(
-                                    self.item_lib.id(self.current_asset.id),
-                                    &self.current_asset_fields[self.details_index],
-                                ),
+                            let Some((asset, path)) = self.current_field() else {
+                                return;
+                            };
+                            let def = self.def(&asset);
+                            let value = get_string_value_from_path(def, &path);
+                            self.details_input = if is_multi_line_field(def, &path) {
+                                TextInput::multi_line()
+                            } else {
+                                TextInput::new()
                             };
-                            self.details_character_index = self.details_input.len();
+                            self.details_input.set_text(&value);
+                            self.details_error = None;
+                            self.editor_scroll = 0;
+                            self.editing_details = true;
                         }
                         _ => {}
                     }
@@ -342,130 +542,1337 @@ impl Widget for &mut Database {
             .split(horizontal_sections[1]);
         self.render_search_bar(horizontal_sections[0], buf);
         self.render_assets(vertical_sections[0], buf);
-        self.get_cursor_position(vertical_sections[1], buf);
-        self.render_details(vertical_sections[1], buf, false);
+        if let Some(merge_prompt) = self.merge_prompt.as_mut() {
+            merge_prompt.render(vertical_sections[1], buf, self.window);
+        } else if self.compare.is_some() {
+            self.render_compare(vertical_sections[1], buf);
+        } else if self.loot_simulation.is_some() {
+            self.render_loot_simulation(vertical_sections[1], buf);
+        } else if self.economy_report.is_some() {
+            self.render_economy_report(vertical_sections[1], buf);
+        } else if self.recipe_graph.is_some() {
+            self.render_recipe_graph(vertical_sections[1], buf);
+        } else if let Some(inventory) = self.inventory.as_mut() {
+            inventory.render(vertical_sections[1], buf, self.window, &self.item_lib);
+        } else if let Some(changes) = self.changes.as_mut() {
+            changes.render(vertical_sections[1], buf, self.window);
+        } else if let Some(csv_import) = self.csv_import.as_mut() {
+            csv_import.render(vertical_sections[1], buf, self.window);
+        } else {
+            self.render_details(vertical_sections[1], buf);
+        }
+        if self.bulk_edit.is_some() {
+            self.render_bulk_edit(vertical_sections[1], buf);
+        }
     }
 }
 
 impl Database {
-    fn get_title_style(&self, frame: DatabaseFrame) -> Style {
-        if self.active_frame == frame {
-            Style::default().fg(self.window.theme.green)
-        } else {
-            Style::default().fg(self.window.theme.white)
+    // Opens the asset libs of a project.
+    pub fn with_project(window: Window, project: Project) -> Self {
+        // TODO: Make asset lib loading and asset rendering more generic.
+        let ability_lib = AbilityLib::new(&project.lib_path("ability"));
+        let aura_lib = AuraLib::new(&project.lib_path("aura"));
+        let character_lib = CharacterLib::new(&project.lib_path("character"));
+        let item_lib = ItemLib::new(&project.lib_path("item"));
+        let loot_table_lib = LootTableLib::new(&project.lib_path("loot_table"));
+        let recipe_lib = RecipeLib::new(&project.lib_path("recipe"));
+        let vendor_lib = VendorLib::new(&project.lib_path("vendor"));
+        let tooltip_template = TooltipTemplate::new(&project.asset_path(&project.tooltip_template));
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<AbilityDef>();
+        type_registry.register::<AuraDef>();
+        type_registry.register::<CharacterDef>();
+        type_registry.register::<ItemDef>();
+        type_registry.register::<LootTableDef>();
+        type_registry.register::<RecipeDef>();
+        type_registry.register::<VendorDef>();
+        let mut database = Self {
+            window,
+            project,
+            ability_lib,
+            aura_lib,
+            character_lib,
+            item_lib,
+            loot_table_lib,
+            recipe_lib,
+            vendor_lib,
+            assets: vec![],
+            visible_assets: AssetList::from_assets(vec![]),
+            active_frame: DatabaseFrame::Search,
+            details_scroll: 0,
+            max_details_scroll: 0,
+            search_input: TextInput::new(),
+            details_input: TextInput::new(),
+            details_index: 0,
+            editing_details: false,
+            editor_scroll: 0,
+            editor_width: 1,
+            current_asset: None,
+            current_asset_fields: vec![],
+            cursor_position: Position { x: 1, y: 1 },
+            show_preview: false,
+            tooltip_template,
+            marked_asset: None,
+            compare: None,
+            loot_simulation: None,
+            economy_report: None,
+            recipe_graph: None,
+            inventory: None,
+            changes: None,
+            csv_import: None,
+            status: None,
+            selected_assets: vec![],
+            bulk_edit: None,
+            details_error: None,
+            type_registry,
+            file_watcher: FileWatcher::new(),
+            saved_libs: HashMap::new(),
+            merge_prompt: None,
+        };
+        database.assets = database.lib_assets();
+        database.current_asset = database.assets.first().cloned();
+        database.visible_assets = AssetList::from_assets((0..database.assets.len()).collect());
+        for asset_type in AssetType::ALL {
+            let name = lib_name(asset_type);
+            database
+                .saved_libs
+                .insert(name, database.lib_value(asset_type));
+            let path = watch_path(name, &database.project);
+            database.file_watcher.watch(path);
         }
+        let sprite_dir = database.project.asset_path(&database.project.sprite_dir);
+        database.file_watcher.watch(sprite_dir);
+        database
     }
 
-    fn render_assets(&mut self, area: Rect, buf: &mut Buffer) {
-        // TODO: Add Markers for asset types. ◆ ■ ○ ●
-        self.populate_visible_assets();
-        let mut list_items = vec![];
-        for index in &self.visible_assets.assets {
-            list_items.push(self.assets[*index].to_list_item())
+    // Every asset in the libs.
+    fn lib_assets(&self) -> Vec<Asset> {
+        let mut assets: Vec<Asset> = vec![];
+        for asset_type in [
+            AssetType::Aura,
+            AssetType::Item,
+            AssetType::Ability,
+            AssetType::Character,
+            AssetType::LootTable,
+            AssetType::Recipe,
+            AssetType::Vendor,
+        ] {
+            for def in self.lib(asset_type).all_defs() {
+                assets.push(Asset {
+                    name: def.name().to_string(),
+                    id: def.id(),
+                    asset_type,
+                    icon: def.icon().to_string(),
+                })
+            }
         }
+        assets
+    }
 
-        let asset_list = List::new(list_items)
-            .block(
-                Block::default()
-                    .title("Assets")
-                    .borders(Borders::ALL)
-                    .border_type(self.window.border_type)
-                    .style(Style::default().fg(self.window.theme.white))
-                    .title_style(self.get_title_style(DatabaseFrame::Assets)),
-            )
-            .bg(self.window.theme.black_dark)
-            .fg(self.window.theme.white)
-            .highlight_style(Style::default().fg(self.window.theme.red))
-            .direction(ListDirection::TopToBottom);
+    // The lib that holds assets of the type.
+    fn lib(&self, asset_type: AssetType) -> &dyn AssetLib {
+        match asset_type {
+            AssetType::Ability => &self.ability_lib,
+            AssetType::Aura => &self.aura_lib,
+            AssetType::Character => &self.character_lib,
+            AssetType::Item => &self.item_lib,
+            AssetType::LootTable => &self.loot_table_lib,
+            AssetType::Recipe => &self.recipe_lib,
+            AssetType::Vendor => &self.vendor_lib,
+        }
+    }
 
-        StatefulWidget::render(&asset_list, area, buf, &mut self.visible_assets.state);
+    fn lib_mut(&mut self, asset_type: AssetType) -> &mut dyn AssetLib {
+        match asset_type {
+            AssetType::Ability => &mut self.ability_lib,
+            AssetType::Aura => &mut self.aura_lib,
+            AssetType::Character => &mut self.character_lib,
+            AssetType::Item => &mut self.item_lib,
+            AssetType::LootTable => &mut self.loot_table_lib,
+            AssetType::Recipe => &mut self.recipe_lib,
+            AssetType::Vendor => &mut self.vendor_lib,
+        }
+    }
 
-        if asset_list.len() > 0 {
-            let scroll = asset_list.len().saturating_sub((area.height - 1) as usize);
-            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-                .track_symbol(Some(self.window.border_type.to_border_set().vertical_left))
-                .begin_symbol(Some("▲"))
-                .end_symbol(Some("▼"));
+    // The asset type's lib, with its edits, as a value.
+    fn lib_value(&self, asset_type: AssetType) -> serde_json::Value {
+        self.lib(asset_type).to_value()
+    }
 
-            let mut scrollbar_state =
-                ScrollbarState::new(scroll).position(self.visible_assets.state.selected().unwrap());
+    // Replaces the asset type's lib, and relists the assets.
+    fn set_lib_value(
+        &mut self,
+        asset_type: AssetType,
+        lib: serde_json::Value,
+    ) -> Result<(), String> {
+        self.lib_mut(asset_type).load_value(lib)?;
+        self.relist_assets();
+        Ok(())
+    }
 
-            StatefulWidget::render(
-                scrollbar,
-                area.inner(Margin {
-                    vertical: 1,
-                    horizontal: 0,
-                }),
-                buf,
-                &mut scrollbar_state,
-            );
+    // Lists the assets in the libs again after they change, dropping any
+    // marked or selected assets that are gone, and closing the views of them.
+    fn relist_assets(&mut self) {
+        self.assets = self.lib_assets();
+        let find = |assets: &[Asset], asset: &Asset| {
+            assets.iter().find(|listed| listed.is(asset)).cloned()
+        };
+        self.marked_asset = self
+            .marked_asset
+            .take()
+            .and_then(|asset| find(&self.assets, &asset));
+        self.selected_assets = std::mem::take(&mut self.selected_assets)
+            .iter()
+            .filter_map(|asset| find(&self.assets, asset))
+            .collect();
+        if let Some(compare) = &self.compare {
+            if find(&self.assets, &compare.left).is_none()
+                || find(&self.assets, &compare.right).is_none()
+            {
+                self.compare = None;
+            }
         }
+        if let Some(loot_simulation) = &self.loot_simulation {
+            if find(&self.assets, &loot_simulation.table).is_none() {
+                self.loot_simulation = None;
+            }
+        }
+        if let Some(csv_import) = &self.csv_import {
+            if csv_import
+                .changes
+                .iter()
+                .any(|change| find(&self.assets, &change.asset).is_none())
+            {
+                self.csv_import = None;
+            }
+        }
+        self.populate_visible_assets();
     }
 
-    fn render_details(&mut self, area: Rect, buf: &mut Buffer, render_cursor: bool) {
-        if self.visible_assets.assets.is_empty() {
-            self.render_empty_details(area, buf);
-            return;
+    // Reloads the libs whose files changed on disk, returning whether anything
+    // did. Libs without edits are replaced, while ones with edits have the
+    // changes on disk merged in, asking which to keep of the fields both
+    // changed. Changed icons only need to be drawn again.
+    fn reload_changed_files(&mut self) -> bool {
+        // Changes to other libs wait until the open merge is done.
+        if self.merge_prompt.is_some() {
+            return false;
         }
-        let asset = self.assets
-            [self.visible_assets.assets[self.visible_assets.state.selected().unwrap()]]
-        .clone();
-        let img = self.get_icon(&asset);
-        let icon_width = min(area.width / 4, img.width() as u16);
-        let sections = self.build_details_sections(area, icon_width);
+        let changed = self.file_watcher.changes();
+        for asset_type in AssetType::ALL {
+            let name = lib_name(asset_type);
+            if changed.contains(&watch_path(name, &self.project)) {
+                self.reload_lib(asset_type);
+                if self.merge_prompt.is_some() {
+                    break;
+                }
+            }
+        }
+        !changed.is_empty()
+    }
 
-        self.current_asset = asset.clone();
+    fn reload_lib(&mut self, asset_type: AssetType) {
+        let name = lib_name(asset_type);
+        let path = self.project.lib_path(name);
+        let theirs = match read_lib_value(asset_type, &self.project) {
+            Ok(theirs) => theirs,
+            Err(error) => {
+                self.status = Some(format!("{} could not be reloaded: {}", path, error));
+                return;
+            }
+        };
+        let saved = &self.saved_libs[name];
+        if theirs == *saved {
+            return;
+        }
+        let ours = self.lib_value(asset_type);
+        if ours == *saved {
+            self.commit_reload(asset_type, theirs.clone(), theirs);
+            self.status = Some(format!("Reloaded {}", path));
+            return;
+        }
+        let merge = merge_libs(saved, &ours, &theirs);
+        if merge.conflicts.is_empty() {
+            self.commit_reload(asset_type, merge.lib, theirs);
+            self.status = Some(format!("Merged the changes to {} into your edits", path));
+        } else {
+            self.merge_prompt = Some(MergePrompt::new(asset_type, path, merge, theirs));
+        }
+    }
 
-        let full_details = match asset.asset_type {
-            AssetType::Aura => {
-                self.current_asset_fields = get_def_paths(self.aura_lib.id(asset.id));
-                self.add_aura_details(&asset, render_cursor)
+    // Replaces a lib with one reloaded from `theirs`, its file's contents.
+    fn commit_reload(
+        &mut self,
+        asset_type: AssetType,
+        lib: serde_json::Value,
+        theirs: serde_json::Value,
+    ) {
+        match self.set_lib_value(asset_type, lib) {
+            Ok(()) => {
+                self.saved_libs.insert(lib_name(asset_type), theirs);
             }
-            AssetType::Item => {
-                self.current_asset_fields = get_def_paths(self.item_lib.id(asset.id));
-                self.add_item_details(&asset, render_cursor)
+            Err(error) => {
+                self.status = Some(format!(
+                    "{} could not be reloaded: {}",
+                    self.project.lib_path(lib_name(asset_type)),
+                    error
+                ))
             }
+        }
+    }
+
+    // Merges the changes on disk into the edits, keeping the chosen side of
+    // each field that both changed.
+    fn commit_merge_prompt(&mut self) {
+        let Some(merge_prompt) = self.merge_prompt.take() else {
+            return;
         };
+        let conflicts = merge_prompt.merge.conflicts.len();
+        self.commit_reload(
+            merge_prompt.asset_type,
+            merge_prompt.merge.resolve(),
+            merge_prompt.theirs,
+        );
+        self.status = Some(format!(
+            "Merged the changes to {} ({} conflicts)",
+            merge_prompt.path, conflicts
+        ));
+    }
 
-        let p = self.build_details_paragraph(full_details);
+    // Saves the libs that have edits, returning whether they all were. Changes
+    // made to their files since they were read are merged in first. A lib
+    // isn't saved if its file can't be read, or if both changed the same
+    // fields, which opens the merge prompt to choose between them.
+    fn save_libs(&mut self) -> bool {
+        let mut saved = true;
+        for asset_type in AssetType::ALL {
+            let name = lib_name(asset_type);
+            let path = self.project.lib_path(name);
+            let ours = self.lib_value(asset_type);
+            if ours == self.saved_libs[name] {
+                continue;
+            }
+            let theirs = match read_lib_value(asset_type, &self.project) {
+                Ok(theirs) => theirs,
+                // A lib whose file is gone is saved again.
+                Err(_) if !Path::new(&path).exists() => self.saved_libs[name].clone(),
+                Err(error) => {
+                    self.status = Some(format!("{} was not saved: {}", path, error));
+                    saved = false;
+                    continue;
+                }
+            };
+            if theirs != self.saved_libs[name] {
+                let merge = merge_libs(&self.saved_libs[name], &ours, &theirs);
+                if !merge.conflicts.is_empty() {
+                    if self.merge_prompt.is_none() {
+                        self.status = Some(format!(
+                            "{} changed on disk. Choose which changes to keep, then leave again to save",
+                            path
+                        ));
+                        self.merge_prompt = Some(MergePrompt::new(asset_type, path, merge, theirs));
+                    }
+                    saved = false;
+                    continue;
+                }
+                if let Err(error) = self.set_lib_value(asset_type, merge.lib) {
+                    self.status = Some(format!("{} was not saved: {}", path, error));
+                    saved = false;
+                    continue;
+                }
+            }
+            if let Err(error) = self.lib(asset_type).save(&path) {
+                self.status = Some(format!("{} was not saved: {}", path, error));
+                saved = false;
+                continue;
+            }
+            self.saved_libs.insert(name, self.lib_value(asset_type));
+            self.file_watcher.mark_seen(watch_path(name, &self.project));
+        }
+        saved
+    }
 
-        let max_details_scroll =
-            (p.line_count(sections[0].width) as u16).saturating_sub(area.height - 1);
-        p.clone()
-            .scroll((min(self.details_scroll, max_details_scroll), 0))
-            .render(sections[0], buf);
+    // Compares the libs, with their edits, to the last commit.
+    fn open_changes(&mut self) -> Result<ChangesView, String> {
+        let revision = "HEAD";
+        let hash = git::short_hash(revision)?;
+        let mut committed = HashMap::new();
+        for asset_type in AssetType::ALL {
+            let lib = read_lib_at(revision, asset_type, &self.project)?;
+            committed.insert(lib_name(asset_type), lib);
+        }
+        let changes = self.asset_changes(&committed)?;
+        Ok(ChangesView::new(
+            format!("{} ({})", revision, hash),
+            committed,
+            changes,
+        ))
+    }
 
-        self.max_details_scroll = max_details_scroll;
+    // How the assets differ from the committed libs.
+    fn asset_changes(
+        &self,
+        committed: &HashMap<&'static str, serde_json::Value>,
+    ) -> Result<Vec<AssetChange>, String> {
+        let mut changes = vec![];
+        for asset_type in AssetType::ALL {
+            let old = &committed[lib_name(asset_type)];
+            changes.extend(def_changes(
+                asset_type,
+                &lib_from_value(asset_type, old)?.all_defs(),
+                &self.lib(asset_type).all_defs(),
+            ));
+        }
+        Ok(changes)
+    }
 
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .track_symbol(Some(self.window.border_type.to_border_set().vertical_left))
-            .begin_symbol(Some("▲"))
-            .end_symbol(Some("▼"));
+    // Reverts the selected asset, or the selected field of it, to how it was
+    // committed.
+    fn revert_change(&mut self) {
+        let Some(changes) = &self.changes else {
+            return;
+        };
+        let Some((change, field)) = changes.selected() else {
+            return;
+        };
+        let (change, field) = (change.clone(), field.cloned());
+        let result = match &field {
+            Some(field) => self.revert_field(&change, field),
+            None => {
+                let committed_def = changes.committed[lib_name(change.asset_type)]
+                    .get("defs")
+                    .and_then(serde_json::Value::as_array)
+                    .and_then(|defs| defs.iter().find(|def| def["id"] == change.id))
+                    .cloned();
+                self.revert_asset(&change, committed_def)
+            }
+        };
+        let result = result.and_then(|()| {
+            let changes = self.changes.as_ref().unwrap();
+            self.asset_changes(&changes.committed)
+        });
+        match result {
+            Ok(asset_changes) => {
+                self.changes.as_mut().unwrap().set_changes(asset_changes);
+                self.status = Some(match field {
+                    Some(field) => format!("Reverted {} of {}", field.path, change.name),
+                    None => format!("Reverted {}", change.name),
+                });
+            }
+            Err(error) => self.status = Some(error),
+        }
+    }
 
-        let mut scrollbar_state =
-            ScrollbarState::new(max_details_scroll.into()).position(self.details_scroll.into());
+    fn revert_field(&mut self, change: &AssetChange, field: &FieldChange) -> Result<(), String> {
+        let (Some(old), Some(_)) = (&field.old, &field.new) else {
+            return Err(format!(
+                "{} was added or removed, so it can only be reverted with the whole asset",
+                field.path
+            ));
+        };
+        let asset = self
+            .assets
+            .iter()
+            .find(|asset| asset.asset_type == change.asset_type && asset.id == change.id)
+            .cloned()
+            .ok_or(format!(
+                "There is no {} with id {}",
+                change.asset_type, change.id
+            ))?;
+        self.update_field(&asset, &field.path, old.clone())?;
+        self.relist_assets();
+        Ok(())
+    }
 
-        StatefulWidget::render(
-            scrollbar,
-            sections[0].inner(Margin {
-                vertical: 1,
-                horizontal: 0,
-            }),
-            buf,
-            &mut scrollbar_state,
-        );
+    // Puts back the committed def in place of the asset, removing the asset if
+    // there wasn't one.
+    fn revert_asset(
+        &mut self,
+        change: &AssetChange,
+        committed_def: Option<serde_json::Value>,
+    ) -> Result<(), String> {
+        let mut lib = self.lib_value(change.asset_type);
+        if lib.get("defs").is_none() {
+            lib["defs"] = serde_json::Value::Array(vec![]);
+        }
+        let serde_json::Value::Array(defs) = &mut lib["defs"] else {
+            return Err("The lib's defs aren't a list".to_string());
+        };
+        let position = defs.iter().position(|def| def["id"] == change.id);
+        match (position, committed_def) {
+            (Some(i), Some(def)) => defs[i] = def,
+            (Some(i), None) => {
+                defs.remove(i);
+            }
+            (None, Some(def)) => {
+                let i = defs
+                    .iter()
+                    .position(|other| other["id"].as_u64() > Some(change.id as u64))
+                    .unwrap_or(defs.len());
+                defs.insert(i, def);
+            }
+            (None, None) => {}
+        }
+        self.set_lib_value(change.asset_type, lib)
+    }
 
-        terminal_image::render_image_with_border(
-            sections[1],
-            buf,
-            img,
-            self.window.theme,
-            self.window.border_type,
-        );
+    // The current asset and the path of its selected field, if there is one.
+    fn current_field(&self) -> Option<(Asset, String)> {
+        let asset = self.current_asset.clone()?;
+        let path = self.current_asset_fields.get(self.details_index)?.clone();
+        Some((asset, path))
     }
 
-    fn build_details_sections(&mut self, area: Rect, icon_width: u16) -> Rc<[Rect]> {
-        Layout::default()
+    // Writes the edited value back into the current asset's def. If the value
+    // doesn't fit the field, the field stays open for editing.
+    fn commit_details_input(&mut self) {
+        let Some((asset, path)) = self.current_field() else {
+            return;
+        };
+        let value = self.details_input.text().to_string();
+        let result = match value.strip_prefix('=') {
+            Some(source) => self.set_formula(&asset, &path, source),
+            None => self.update_field(&asset, &path, value),
+        };
+        match result {
+            Ok(()) => {
+                self.details_input.clear();
+                self.details_error = None;
+                self.editing_details = false;
+            }
+            Err(error) => self.details_error = Some(error),
+        }
+    }
+
+    // Parses `value` into the field at `path` on the asset's def, adding the
+    // list entry it is in if it is one past the end, then recomputes the fields
+    // with formulas that depend on it. A field given a value other than its
+    // formula's is marked overridden, and one given the formula's value
+    // follows the formula again.
+    fn update_field(&mut self, asset: &Asset, path: &str, value: String) -> Result<(), String> {
+        let overridden = self.lib(asset.asset_type).overrides().paths(asset.id);
+        let type_registry = std::mem::take(&mut self.type_registry);
+        let mut is_override = false;
+        let result = self.edit_def(asset, |def, formulas| {
+            add_missing_list_entries(def, &type_registry, path)?;
+            set_field_with_formulas(def, formulas, &overridden, path, value)?;
+            is_override = formula_value(def, formulas, path)
+                .is_some_and(|computed| computed != Ok(get_string_value_from_path(def, path)));
+            Ok(())
+        });
+        self.type_registry = type_registry;
+        result?;
+        self.lib_mut(asset.asset_type)
+            .overrides_mut()
+            .set(asset.id, path, is_override);
+        Ok(())
+    }
+
+    // Runs `edit` on a copy of the asset's def along with its lib's formulas,
+    // keeping the copy if the edit succeeds.
+    fn edit_def(
+        &mut self,
+        asset: &Asset,
+        edit: impl FnOnce(&mut dyn Reflect, &FormulaSet) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let lib = self.lib_mut(asset.asset_type);
+        let mut def = lib.clone_def(asset.id).ok_or(format!(
+            "There is no {} with id {}",
+            asset.asset_type, asset.id
+        ))?;
+        edit(def.as_mut(), lib.formulas())?;
+        lib.replace_def(def)
+    }
+
+    // Appends an entry to the list that the selected field is in.
+    fn add_list_element(&mut self) {
+        let Some((asset, path)) = self.current_field() else {
+            return;
+        };
+        let Some((list_path, _)) = get_list_path(self.def(&asset), &path) else {
+            return;
+        };
+        let type_registry = std::mem::take(&mut self.type_registry);
+        let result = self.edit_def(&asset, |def, _| {
+            add_list_element(def, &type_registry, &list_path)
+        });
+        self.type_registry = type_registry;
+        self.details_error = result.err();
+    }
+
+    // Removes the list entry that the selected field belongs to.
+    fn remove_list_element(&mut self) {
+        let Some((asset, path)) = self.current_field() else {
+            return;
+        };
+        let Some((list_path, Some(index))) = get_list_path(self.def(&asset), &path) else {
+            return;
+        };
+        let result = self.edit_def(&asset, |def, _| remove_list_element(def, &list_path, index));
+        self.details_error = result.err();
+    }
+
+    // Sets the formula for the field at `path` on every def in the asset's
+    // lib, recomputing the field and its dependents for all of them except
+    // where they are overridden. An empty formula removes it, leaving the
+    // values as they are.
+    fn set_formula(&mut self, asset: &Asset, path: &str, source: &str) -> Result<(), String> {
+        let mut formulas = self.formulas(asset.asset_type).clone();
+        if source.trim().is_empty() {
+            formulas.remove(path);
+        } else {
+            formulas
+                .set(path, source)
+                .map_err(|error| error.to_string())?;
+            if let Some(Err(error)) = formula_value(self.def(asset), &formulas, path) {
+                return Err(error);
+            }
+        }
+        let mut paths = vec![path.to_string()];
+        paths.extend(formulas.dependents(path));
+        let lib = self.lib_mut(asset.asset_type);
+        if formulas.get(path).is_none() {
+            lib.overrides_mut().clear(path);
+        }
+        let ids: Vec<u32> = lib.all_defs().iter().map(|def| def.id()).collect();
+        for id in ids {
+            let mut def = lib.clone_def(id).unwrap();
+            let overridden = lib.overrides().paths(id);
+            recompute_fields(def.as_mut(), &formulas, &overridden, &paths);
+            lib.replace_def(def)?;
+        }
+        *lib.formulas_mut() = formulas;
+        Ok(())
+    }
+
+    fn formulas(&self, asset_type: AssetType) -> &FormulaSet {
+        self.lib(asset_type).formulas()
+    }
+
+    // Whether `value` can be parsed into the field at `path` on the asset's
+    // def, without changing it. Gives the value as the field would hold it.
+    fn check_field(&self, asset: &Asset, path: &str, value: String) -> Result<String, String> {
+        let mut def = self
+            .lib(asset.asset_type)
+            .clone_def(asset.id)
+            .ok_or(format!(
+                "There is no {} with id {}",
+                asset.asset_type, asset.id
+            ))?;
+        set_field_value_from_string(def.as_mut(), path, value)?;
+        Ok(get_string_value_from_path(def.as_ref(), path))
+    }
+
+    // Writes every def of the asset type to the project's CSV directory, one
+    // column per field path. Lists only get columns for their entries, and
+    // cells are left empty for fields that a def doesn't have.
+    fn export_csv(&self, asset_type: AssetType) -> Result<String, String> {
+        let defs: Vec<&dyn Reflect> = self
+            .assets
+            .iter()
+            .filter(|asset| asset.asset_type == asset_type)
+            .map(|asset| self.def(asset))
+            .collect();
+        let mut columns: Vec<String> = vec![];
+        for def in &defs {
+            for path in get_def_paths(*def) {
+                let is_list = matches!(
+                    def.reflect_path(path.as_str())
+                        .map(|field| field.reflect_ref()),
+                    Ok(ReflectRef::List(_))
+                );
+                if !is_list && !columns.contains(&path) {
+                    columns.push(path);
+                }
+            }
+        }
+        let rows = defs
+            .iter()
+            .map(|def| CsvRow {
+                line: 0,
+                cells: columns
+                    .iter()
+                    .map(|path| match def.reflect_path(path.as_str()) {
+                        Ok(_) => get_string_value_from_path(*def, path),
+                        Err(_) => String::new(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let data = CsvSheet { columns, rows }.to_csv()?;
+
+        let path = self.project.csv_path(asset_type);
+        fs::create_dir_all(self.project.asset_path(&self.project.csv_dir))
+            .and_then(|_| fs::write(&path, data))
+            .map_err(|error| format!("{} could not be written: {}", path, error))?;
+        Ok(path)
+    }
+
+    // Reads the asset type's CSV file from the project's CSV directory and
+    // works out which fields it changes. Rows are matched to defs by id, and
+    // a row with any value that doesn't fit its field is skipped.
+    fn preview_csv_import(&self, asset_type: AssetType) -> Result<CsvImport<Asset>, String> {
+        let path = self.project.csv_path(asset_type);
+        let data = fs::read_to_string(&path)
+            .map_err(|error| format!("{} could not be read: {}", path, error))?;
+        let (sheet, mut errors) = CsvSheet::parse(&data)?;
+        let mut changes = vec![];
+        let mut lines_by_id = HashMap::new();
+        for row in &sheet.rows {
+            match self.csv_row_changes(asset_type, &sheet, row, &mut lines_by_id) {
+                Ok(row_changes) => changes.extend(row_changes),
+                Err(message) => errors.push(CsvRowError {
+                    line: row.line,
+                    message,
+                }),
+            }
+        }
+        errors.sort_by_key(|error| error.line);
+        Ok(CsvImport::new(path, changes, errors))
+    }
+
+    // The fields that a row of the CSV file changes on the def with its id.
+    // `lines_by_id` is where each id has been seen so far.
+    fn csv_row_changes(
+        &self,
+        asset_type: AssetType,
+        sheet: &CsvSheet,
+        row: &CsvRow,
+        lines_by_id: &mut HashMap<u32, u64>,
+    ) -> Result<Vec<CsvChange<Asset>>, String> {
+        let id = sheet
+            .cells(row)
+            .find(|(column, _)| *column == "id")
+            .map_or("", |(_, id)| id);
+        let id: u32 = parse_field(id.trim(), "id")?;
+        if let Some(line) = lines_by_id.insert(id, row.line) {
+            return Err(format!("Id {} is already on line {}", id, line));
+        }
+        let asset = self
+            .assets
+            .iter()
+            .find(|asset| asset.asset_type == asset_type && asset.id == id)
+            .ok_or(format!("There is no {} with id {}", asset_type, id))?;
+        let def = self.def(asset);
+        // The row's values are set on a copy of the def, which also gets the
+        // list entries that the row adds.
+        let mut copy = self.lib(asset_type).clone_def(id).unwrap();
+        let mut changes = vec![];
+        for (path, value) in sheet.cells(row) {
+            if path == "id" {
+                continue;
+            }
+            let existed = def.reflect_path(path).is_ok();
+            if !existed {
+                if value.is_empty() {
+                    continue;
+                }
+                add_missing_list_entries(copy.as_mut(), &self.type_registry, path)
+                    .map_err(|error| format!("{}: {}", path, error))?;
+                if copy.reflect_path(path).is_err() {
+                    return Err(format!("{} has no field {}", asset.name, path));
+                }
+            }
+            let old = if existed {
+                get_string_value_from_path(def, path)
+            } else {
+                String::new()
+            };
+            set_field_value_from_string(copy.as_mut(), path, value.to_string())
+                .map_err(|error| format!("{}: {}", path, error))?;
+            let new = get_string_value_from_path(copy.as_ref(), path);
+            if new != old || !existed {
+                changes.push(CsvChange {
+                    asset: asset.clone(),
+                    name: asset.name.clone(),
+                    path: path.to_string(),
+                    old,
+                    new,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    // Applies every change in the CSV import preview, adding the list entries
+    // that they are in if needed. Changes that can't be applied are listed in
+    // the status line.
+    fn commit_csv_import(&mut self) {
+        let Some(csv_import) = self.csv_import.take() else {
+            return;
+        };
+        let mut failed = vec![];
+        for change in &csv_import.changes {
+            let result = self.update_field(&change.asset, &change.path, change.new.clone());
+            if let Err(error) = result {
+                failed.push(format!("{} {}: {}", change.name, change.path, error));
+                continue;
+            }
+            if change.path == "name" {
+                for asset in self
+                    .assets
+                    .iter_mut()
+                    .filter(|asset| asset.is(&change.asset))
+                {
+                    asset.name = change.new.clone();
+                }
+            }
+        }
+        let imported = csv_import.changes.len() - failed.len();
+        self.status = Some(if failed.is_empty() {
+            format!("Imported {} changes from {}", imported, csv_import.path)
+        } else {
+            format!(
+                "Imported {} changes from {}, but couldn't import {}",
+                imported,
+                csv_import.path,
+                failed.join(", ")
+            )
+        });
+    }
+
+    fn is_selected(&self, asset: &Asset) -> bool {
+        self.selected_assets
+            .iter()
+            .any(|selected| selected.is(asset))
+    }
+
+    // Selects every asset matching the current search, or deselects them if
+    // they are all selected already.
+    fn select_all_visible(&mut self) {
+        let visible: Vec<Asset> = self
+            .visible_assets
+            .assets
+            .iter()
+            .map(|i| self.assets[*i].clone())
+            .collect();
+        if visible.iter().all(|asset| self.is_selected(asset)) {
+            self.selected_assets
+                .retain(|selected| !visible.iter().any(|asset| asset.is(selected)));
+        } else {
+            for asset in visible {
+                if !self.is_selected(&asset) {
+                    self.selected_assets.push(asset);
+                }
+            }
+        }
+    }
+
+    fn handle_bulk_edit_key_event(&mut self, key_event: KeyEvent) {
+        let num_rows = self.selected_assets.len();
+        let Some(bulk_edit) = self.bulk_edit.as_mut() else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Esc => self.bulk_edit = None,
+            KeyCode::Tab => bulk_edit.editing_change = !bulk_edit.editing_change,
+            KeyCode::Up => bulk_edit.previous(),
+            KeyCode::Down => bulk_edit.next(num_rows),
+            KeyCode::Enter if !bulk_edit.editing_change => bulk_edit.editing_change = true,
+            KeyCode::Enter => self.commit_bulk_edit(),
+            _ => {
+                bulk_edit.input().handle_key_event(key_event);
+            }
+        }
+    }
+
+    // Applies the bulk edit to every selected asset that has the field. Nothing
+    // is changed unless every one of them can take its new value.
+    fn commit_bulk_edit(&mut self) {
+        let Some(bulk_edit) = &self.bulk_edit else {
+            return;
+        };
+        let path = bulk_edit.path.text().trim().to_string();
+        let rows = self.bulk_rows();
+        if rows
+            .iter()
+            .any(|row| row.old.is_some() && !matches!(row.new, Some(Ok(_))))
+        {
+            return;
+        }
+        let mut failed = vec![];
+        for (asset, row) in self.selected_assets.clone().iter().zip(rows) {
+            if let (Some(_), Some(Ok(new))) = (row.old, row.new) {
+                if let Err(error) = self.update_field(asset, &path, new) {
+                    failed.push(format!("{}: {}", asset.name, error));
+                }
+            }
+        }
+        if !failed.is_empty() {
+            self.status = Some(format!("Couldn't bulk edit {}", failed.join(", ")));
+        }
+        self.bulk_edit = None;
+    }
+
+    // The old and new value of the bulk edited field for each selected asset.
+    fn bulk_rows(&self) -> Vec<BulkRow> {
+        let Some(bulk_edit) = &self.bulk_edit else {
+            return vec![];
+        };
+        let path = bulk_edit.path.text().trim();
+        let change =
+            (!bulk_edit.change.is_empty()).then(|| BulkChange::parse(bulk_edit.change.text()));
+        self.selected_assets
+            .iter()
+            .map(|asset| {
+                let def = self.def(asset);
+                let old = (!path.is_empty() && def.reflect_path(path).is_ok())
+                    .then(|| get_string_value_from_path(def, path));
+                let new = match (&old, &change) {
+                    (Some(old), Some(Ok(change))) => Some(
+                        change
+                            .apply(old, &field_variable(def))
+                            .map_err(|error| error.to_string())
+                            .and_then(|value| {
+                                let value = format_field_value(def, path, value);
+                                self.check_field(asset, path, value.clone())?;
+                                Ok(value)
+                            }),
+                    ),
+                    (_, Some(Err(error))) => Some(Err(error.to_string())),
+                    _ => None,
+                };
+                BulkRow {
+                    name: asset.name.clone(),
+                    old,
+                    new,
+                }
+            })
+            .collect()
+    }
+
+    fn render_bulk_edit(&mut self, area: Rect, buf: &mut Buffer) {
+        let rows = self.bulk_rows();
+        let Some(mut bulk_edit) = self.bulk_edit.take() else {
+            return;
+        };
+        let area = area.inner(Margin {
+            vertical: 2,
+            horizontal: 4,
+        });
+        self.cursor_position = bulk_edit.render(area, buf, self.window, &rows);
+        self.bulk_edit = Some(bulk_edit);
+    }
+
+    fn handle_compare_key_event(&mut self, key_event: KeyEvent) {
+        let num_rows = self.compare_rows().len();
+        let action = self.window.keymap.action(key_event);
+        let Some(compare) = self.compare.as_mut() else {
+            return;
+        };
+        compare.error = None;
+        let copied = match key_event.code {
+            KeyCode::Esc => {
+                self.compare = None;
+                Ok(())
+            }
+            _ if action == Some(Action::Compare) => {
+                self.compare = None;
+                Ok(())
+            }
+            KeyCode::Up => {
+                compare.previous();
+                Ok(())
+            }
+            KeyCode::Down => {
+                compare.next(num_rows);
+                Ok(())
+            }
+            KeyCode::Left => self.copy_compare_value(CompareSide::Left),
+            KeyCode::Right => self.copy_compare_value(CompareSide::Right),
+            _ => Ok(()),
+        };
+        if let (Err(error), Some(compare)) = (copied, self.compare.as_mut()) {
+            compare.error = Some(error);
+        }
+    }
+
+    // Copies the selected field's value from one side of the comparison to
+    // the other. Fields are only copied between values of the same type, and
+    // ids are never copied since they identify the asset.
+    fn copy_compare_value(&mut self, to: CompareSide) -> Result<(), String> {
+        let Some(compare) = &self.compare else {
+            return Ok(());
+        };
+        let rows = self.compare_rows();
+        let Some(row) = compare.state.selected().and_then(|i| rows.get(i)) else {
+            return Ok(());
+        };
+        let (from_asset, to_asset) = match to {
+            CompareSide::Left => (&compare.right, &compare.left),
+            CompareSide::Right => (&compare.left, &compare.right),
+        };
+        // Copying an id or guid would give two assets the same one.
+        if row.path == "id" || row.path == "guid" {
+            return Err(format!("Can't copy the {}", row.path));
+        }
+        let from_type = self.field_type_path(from_asset, &row.path);
+        if from_type.is_none() || from_type != self.field_type_path(to_asset, &row.path) {
+            return Err(format!("Both sides need a {} of the same type", row.path));
+        }
+        let value = get_string_value_from_path(self.def(from_asset), &row.path);
+        self.update_field(&to_asset.clone(), &row.path.clone(), value)
+            .map_err(|error| format!("Can't copy {}: {}", row.path, error))
+    }
+
+    fn field_type_path(&self, asset: &Asset, path: &str) -> Option<&str> {
+        self.def(asset)
+            .reflect_path(path)
+            .ok()
+            .map(|field| field.reflect_type_path())
+    }
+
+    // Every field of both compared assets, lined up by path.
+    fn compare_rows(&self) -> Vec<CompareRow> {
+        let Some(compare) = &self.compare else {
+            return vec![];
+        };
+        CompareRow::pair(self.def(&compare.left), self.def(&compare.right))
+    }
+
+    fn render_compare(&mut self, area: Rect, buf: &mut Buffer) {
+        let rows = self.compare_rows();
+        let Some(mut compare) = self.compare.take() else {
+            return;
+        };
+        let left_name = get_string_value_from_path(self.def(&compare.left), "name");
+        let right_name = get_string_value_from_path(self.def(&compare.right), "name");
+        compare.render(area, buf, self.window, (&left_name, &right_name), &rows);
+        self.compare = Some(compare);
+    }
+
+    fn render_loot_simulation(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(mut view) = self.loot_simulation.take() else {
+            return;
+        };
+        let table = self.loot_table_lib.id(view.table.id);
+        if view.simulation.is_none() {
+            view.simulation =
+                Some(table.simulate(&self.loot_table_lib, &self.item_lib, view.rolls, view.seed));
+        }
+        let item = |id: u32| {
+            let item = self.item_lib.id(id);
+            (item.name.clone(), item.sell_value)
+        };
+        view.render(area, buf, self.window, &table.name, &item);
+        self.loot_simulation = Some(view);
+    }
+
+    fn render_economy_report(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(mut view) = self.economy_report.take() else {
+            return;
+        };
+        let item = |id: u32| self.item_lib.id(id).name.clone();
+        let vendor = |id: u32| self.vendor_lib.id(id).name.clone();
+        view.render(area, buf, self.window, &item, &vendor);
+        self.economy_report = Some(view);
+    }
+
+    fn render_recipe_graph(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(mut view) = self.recipe_graph.take() else {
+            return;
+        };
+        let item = |id: u32| {
+            self.asset_name(AssetType::Item, id)
+                .unwrap_or(format!("Item {}", id))
+        };
+        let recipe = |id: u32| self.recipe_lib.id(id).name.clone();
+        view.render(area, buf, self.window, &item, &recipe);
+        self.recipe_graph = Some(view);
+    }
+
+    fn handle_paste(&mut self, text: &str) {
+        match self.active_frame {
+            _ if self.bulk_edit.is_some() => {
+                if let Some(bulk_edit) = self.bulk_edit.as_mut() {
+                    bulk_edit.input().paste(text)
+                }
+            }
+            DatabaseFrame::Search => self.search_input.paste(text),
+            DatabaseFrame::Details if self.editing_details => self.details_input.paste(text),
+            _ => {}
+        }
+    }
+
+    fn get_title_style(&self, frame: DatabaseFrame) -> Style {
+        if self.active_frame == frame {
+            Style::default().fg(self.window.theme.green)
+        } else {
+            Style::default().fg(self.window.theme.white)
+        }
+    }
+
+    fn render_assets(&mut self, area: Rect, buf: &mut Buffer) {
+        // TODO: Add Markers for asset types. ◆ ■ ○ ●
+        self.populate_visible_assets();
+        let mut list_items = vec![];
+        for index in &self.visible_assets.assets {
+            let asset = &self.assets[*index];
+            let marked = self
+                .marked_asset
+                .as_ref()
+                .is_some_and(|marked| marked.is(asset));
+            list_items.push(asset.to_list_item(marked, self.is_selected(asset)))
+        }
+
+        let title = if self.selected_assets.is_empty() {
+            "Assets".to_string()
+        } else {
+            format!("Assets ({})", self.selected_assets.len())
+        };
+        let asset_list = List::new(list_items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(self.window.border_type)
+                    .style(Style::default().fg(self.window.theme.white))
+                    .title_style(self.get_title_style(DatabaseFrame::Assets)),
+            )
+            .bg(self.window.theme.black_dark)
+            .fg(self.window.theme.white)
+            .highlight_style(Style::default().fg(self.window.theme.red))
+            .direction(ListDirection::TopToBottom);
+
+        StatefulWidget::render(&asset_list, area, buf, &mut self.visible_assets.state);
+
+        if !asset_list.is_empty() {
+            let scroll = asset_list.len().saturating_sub((area.height - 1) as usize);
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .track_symbol(Some(self.window.border_type.to_border_set().vertical_left))
+                .begin_symbol(Some("▲"))
+                .end_symbol(Some("▼"));
+
+            let mut scrollbar_state =
+                ScrollbarState::new(scroll).position(self.visible_assets.state.selected().unwrap());
+
+            StatefulWidget::render(
+                scrollbar,
+                area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                buf,
+                &mut scrollbar_state,
+            );
+        }
+    }
+
+    fn render_details(&mut self, area: Rect, buf: &mut Buffer) {
+        if self.visible_assets.assets.is_empty() {
+            self.current_asset = None;
+            self.current_asset_fields.clear();
+            self.editing_details = false;
+            self.render_empty_details(area, buf);
+            return;
+        }
+        let asset = self.assets
+            [self.visible_assets.assets[self.visible_assets.state.selected().unwrap()]]
+        .clone();
+        let img = self.get_icon(&asset);
+        let icon_width = if self.show_preview {
+            min(area.width / 2, 48)
+        } else {
+            min(area.width / 4, img.width() as u16)
+        };
+        let sections = self.build_details_sections(area, icon_width);
+
+        self.current_asset = Some(asset.clone());
+
+        // The width of the text inside the details border.
+        let text_width = sections[0].width.saturating_sub(2);
+        self.current_asset_fields = get_def_paths(self.def(&asset));
+        self.details_index = min(
+            self.details_index,
+            self.current_asset_fields.len().saturating_sub(1),
+        );
+        let full_details = self.add_details(&asset, text_width);
+
+        if self.editing_details && !self.details_input.is_multi_line() {
+            self.place_details_cursor(sections[0], &full_details);
+        }
+
+        let p = self.build_details_paragraph(full_details.concat());
+
+        let max_details_scroll =
+            (p.line_count(sections[0].width) as u16).saturating_sub(area.height - 1);
+        p.clone()
+            .scroll((min(self.details_scroll, max_details_scroll), 0))
+            .render(sections[0], buf);
+
+        self.max_details_scroll = max_details_scroll;
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .track_symbol(Some(self.window.border_type.to_border_set().vertical_left))
+            .begin_symbol(Some("▲"))
+            .end_symbol(Some("▼"));
+
+        let mut scrollbar_state =
+            ScrollbarState::new(max_details_scroll.into()).position(self.details_scroll.into());
+
+        StatefulWidget::render(
+            scrollbar,
+            sections[0].inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            }),
+            buf,
+            &mut scrollbar_state,
+        );
+
+        if self.show_preview {
+            self.render_preview(sections[1], buf, &asset, img);
+        } else {
+            terminal_image::render_image_with_border(
+                sections[1],
+                buf,
+                img,
+                self.window.theme,
+                self.window.border_type,
+            );
+        }
+
+        if self.editing_details && self.details_input.is_multi_line() {
+            self.render_multi_line_editor(sections[0], buf);
+        }
+    }
+
+    // Draws the asset as the player would see it in a tooltip.
+    fn render_preview(&self, area: Rect, buf: &mut Buffer, asset: &Asset, img: DynamicImage) {
+        let theme = self.window.theme;
+        let block = Block::default()
+            .title("Preview")
+            .borders(Borders::ALL)
+            .border_type(self.window.border_type)
+            .style(Style::default().fg(theme.white));
+        let inner = block.inner(area);
+        block.bg(theme.black_dark).render(area, buf);
+
+        let icon_height = min(self.tooltip_template.icon_height, inner.height);
+        let icon_width = min(icon_height * 2, inner.width);
+        terminal_image::render_image(
+            Rect {
+                x: inner.x + (inner.width - icon_width) / 2,
+                y: inner.y,
+                width: icon_width,
+                height: icon_height,
+            },
+            buf,
+            img,
+            theme,
+        );
+
+        let def = self.def(asset);
+        let mut lines = vec![];
+        for (text, line) in self.tooltip_template.build(asset.asset_type, def) {
+            let color = match line.style {
+                TooltipStyle::Normal | TooltipStyle::Rules => theme.white,
+                TooltipStyle::Muted => theme.white_dark,
+                TooltipStyle::Flavor => theme.yellow,
+                TooltipStyle::Value => theme.cyan_light,
+                TooltipStyle::Rarity => match def.path::<ItemRarity>("item_rarity") {
+                    Ok(ItemRarity::Junk) => theme.black_light,
+                    Ok(ItemRarity::Common) => theme.white_light,
+                    Ok(ItemRarity::Uncommon) => theme.green_light,
+                    Ok(ItemRarity::Rare) => theme.blue_light,
+                    Ok(ItemRarity::Epic) => theme.magenta_light,
+                    Ok(ItemRarity::Mythical) => theme.red_light,
+                    Err(_) => theme.white,
+                },
+                TooltipStyle::AuraType => match def.path::<AuraType>("aura_type") {
+                    Ok(AuraType::Physical) => theme.red_light,
+                    Ok(AuraType::Magic) => theme.blue_light,
+                    Ok(AuraType::Poison) => theme.green_light,
+                    _ => theme.white_light,
+                },
+            };
+            let mut formatted = if line.style == TooltipStyle::Rules {
+                self.format_markup(&text)
+            } else {
+                text.split('\n')
+                    .map(|part| Line::styled(part.to_string(), Style::default().fg(color)))
+                    .collect()
+            };
+            if line.style == TooltipStyle::Rarity || line.style == TooltipStyle::AuraType {
+                formatted = formatted.into_iter().map(|l| l.bold()).collect();
+            }
+            if line.align == TooltipAlign::Center {
+                formatted = formatted.into_iter().map(|l| l.centered()).collect();
+            }
+            lines.extend(formatted);
+        }
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .bg(theme.black_dark)
+            .render(
+                Rect {
+                    y: inner.y + icon_height,
+                    height: inner.height - icon_height,
+                    ..inner
+                },
+                buf,
+            );
+    }
+
+    // Draws a text box over the details frame for editing long text.
+    fn render_multi_line_editor(&mut self, area: Rect, buf: &mut Buffer) {
+        let area = area.inner(Margin {
+            vertical: 2,
+            horizontal: 4,
+        });
+        let block = Block::default()
+            .title(format!(
+                "Editing {}",
+                self.current_asset_fields[self.details_index]
+            ))
+            .title_bottom(match &self.details_error {
+                Some(error) => {
+                    Line::styled(error.clone(), Style::default().fg(self.window.theme.red))
+                }
+                None => Line::from(format!(
+                    "{} chars, {} lines (Ctrl+S to save, Esc to cancel)",
+                    self.details_input.char_count(),
+                    self.details_input.line_count()
+                )),
+            })
+            .borders(Borders::ALL)
+            .border_type(self.window.border_type)
+            .style(Style::default().fg(self.window.theme.white))
+            .title_style(Style::default().fg(self.window.theme.green));
+        let inner = block.inner(area);
+        self.editor_width = inner.width.max(1);
+
+        let (column, row) = self.details_input.cursor_position(self.editor_width, 0);
+        if row < self.editor_scroll {
+            self.editor_scroll = row;
+        } else if row >= self.editor_scroll + inner.height.max(1) {
+            self.editor_scroll = row + 1 - inner.height.max(1);
+        }
+        self.cursor_position = Position {
+            x: inner.x + column,
+            y: inner.y + row - self.editor_scroll,
+        };
+
+        let lines = self.details_input.lines(
+            self.editor_width,
+            vec![],
+            Style::default().fg(self.window.theme.white),
+            Style::default()
+                .fg(self.window.theme.black_dark)
+                .bg(self.window.theme.white),
+        );
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .bg(self.window.theme.black_dark)
+            .scroll((self.editor_scroll, 0))
+            .render(area, buf);
+    }
+
+    fn build_details_sections(&mut self, area: Rect, icon_width: u16) -> Rc<[Rect]> {
+        Layout::default()
             .direction(Direction::Horizontal)
             .constraints(vec![
                 Constraint::Length(area.width - icon_width),
@@ -475,18 +1882,23 @@ impl Database {
     }
 
     fn build_details_paragraph<'a>(&self, contents: Vec<Line<'a>>) -> Paragraph<'a> {
+        let mut block = Block::default()
+            .title("Details")
+            .borders(Borders::ALL)
+            .border_type(self.window.border_type)
+            .style(Style::default().fg(self.window.theme.white))
+            .title_style(self.get_title_style(DatabaseFrame::Details));
+        if let Some(error) = &self.details_error {
+            block = block.title_bottom(Line::styled(
+                error.clone(),
+                Style::default().fg(self.window.theme.red),
+            ));
+        }
         Paragraph::new(contents)
-            .block(
-                Block::default()
-                    .title("Details")
-                    .borders(Borders::ALL)
-                    .border_type(self.window.border_type)
-                    .style(Style::default().fg(self.window.theme.white))
-                    .title_style(self.get_title_style(DatabaseFrame::Details)),
-            )
+            .block(block)
             .bg(self.window.theme.black_dark)
             .fg(self.window.theme.white)
-            .wrap(Wrap { trim: true })
+            .wrap(Wrap { trim: false })
     }
 
     fn render_empty_details(&mut self, area: Rect, buf: &mut Buffer) {
@@ -496,55 +1908,232 @@ impl Database {
         terminal_image::render_image_path_with_border(
             sections[1],
             buf,
-            UNKNOWN_IMAGE_PATH,
+            &self.project.asset_path(&self.project.unknown_icon),
+            &self.project.asset_path(&self.project.unknown_icon),
             self.window.theme,
             self.window.border_type,
         );
     }
 
-    fn get_icon(&self, asset: &Asset) -> DynamicImage {
-        let path = match asset.asset_type {
-            AssetType::Aura => self.aura_lib.id(asset.id).icon.clone(),
-            AssetType::Item => self.item_lib.id(asset.id).icon.clone(),
-        };
-        load_image(&format!("asset/{}", &path))
+    fn get_icon(&self, asset: &Asset) -> DynamicImage {
+        let path = self.asset_def(asset).icon();
+        load_image(
+            &self.project.asset_path(path),
+            &self.project.asset_path(&self.project.unknown_icon),
+        )
+    }
+
+    // The def behind an asset, for reading its fields through reflection.
+    fn def(&self, asset: &Asset) -> &dyn Reflect {
+        self.asset_def(asset).as_reflect()
+    }
+
+    fn asset_def(&self, asset: &Asset) -> &dyn AssetDef {
+        self.lib(asset.asset_type).def(asset.id).unwrap()
+    }
+
+    // Formats every field of the asset, grouped by field. Fields with a
+    // formula note it after their value, or the value the formula gives if
+    // they have been overridden or don't match it.
+    fn add_details(&self, asset: &Asset, width: u16) -> Vec<Vec<Line<'static>>> {
+        let theme = self.window.theme;
+        let def = self.def(asset);
+        let formulas = self.formulas(asset.asset_type);
+        let overrides = self.lib(asset.asset_type).overrides();
+        self.current_asset_fields
+            .iter()
+            .enumerate()
+            .map(|(i, path)| {
+                let value = get_string_value_from_path(def, path);
+                let computed = formula_value(def, formulas, path);
+                let mut lines = self.format_detail(
+                    path,
+                    &value,
+                    width,
+                    field_has_attribute::<MarkupText>(def, path),
+                    computed.as_ref() == Some(&Ok(value.clone())),
+                );
+                let note = match computed {
+                    _ if self.editing_details && i == self.details_index => None,
+                    Some(Ok(computed)) if overrides.contains(asset.id, path) => Some(Span::styled(
+                        format!(" (overridden, formula gives {})", computed),
+                        Style::default().fg(theme.yellow),
+                    )),
+                    Some(Ok(computed)) if computed == value => Some(Span::styled(
+                        format!(" = {}", formulas.get(path).unwrap_or_default()),
+                        Style::default().fg(theme.cyan),
+                    )),
+                    Some(Ok(computed)) => Some(Span::styled(
+                        format!(" (formula gives {})", computed),
+                        Style::default().fg(theme.yellow),
+                    )),
+                    Some(Err(error)) => Some(Span::styled(
+                        format!(" (formula error: {})", error),
+                        Style::default().fg(theme.red_dark),
+                    )),
+                    None => self.reference_note(def, path, &value),
+                };
+                if let Some(note) = note {
+                    lines.last_mut().unwrap().push_span(note);
+                }
+                lines
+            })
+            .collect()
+    }
+
+    // Names the asset that an id field refers to, or flags it if there is no
+    // such asset. Character and ability references are checked by their defs'
+    // own validation.
+    fn reference_note(&self, def: &dyn Reflect, path: &str, value: &str) -> Option<Span<'static>> {
+        if let Some(character) = def.downcast_ref::<CharacterDef>() {
+            return self.character_note(character, path);
+        }
+        if let Some(ability) = def.downcast_ref::<AbilityDef>() {
+            let effect = &ability.effects[list_index(path, "effects")?];
+            if !path.ends_with(".aura_id") || effect.effect_type != EffectType::ApplyAura {
+                return None;
+            }
+            return if ability
+                .missing_auras(&self.aura_lib)
+                .contains(&effect.aura_id)
+            {
+                self.error_note("no aura with this id".to_string())
+            } else {
+                self.name_note(&self.aura_lib.id(effect.aura_id).name)
+            };
+        }
+
+        let (parent, field) = path.rsplit_once('.').unwrap_or(("", path));
+        let sibling = |name: &str| get_string_value_from_path(def, &format!("{}.{}", parent, name));
+        let asset_type = match (def.reflect_type_ident().unwrap_or_default(), field) {
+            ("LootTableDef", "id") => match sibling("entry_type").as_str() {
+                "Item" => AssetType::Item,
+                "Table" => AssetType::LootTable,
+                _ => return None,
+            },
+            ("RecipeDef", "item_id" | "output_item_id") => AssetType::Item,
+            ("VendorDef", "item_id") => AssetType::Item,
+            _ => return None,
+        };
+        match value
+            .parse()
+            .ok()
+            .and_then(|id| self.asset_name(asset_type, id))
+        {
+            Some(name) => self.name_note(&name),
+            None => {
+                let label = match asset_type {
+                    AssetType::LootTable => "loot table".to_string(),
+                    _ => asset_type.to_string().to_lowercase(),
+                };
+                self.error_note(format!("no {} with this id", label))
+            }
+        }
+    }
+
+    // The note for a character's equipment, ability, aura or loot table field. The
+    // equipment is also flagged when the item doesn't go in the slot it is
+    // placed in, or the slot is unusable or already used.
+    fn character_note(&self, character: &CharacterDef, path: &str) -> Option<Span<'static>> {
+        if let Some(i) = list_index(path, "abilities") {
+            let id = character.abilities[i];
+            return if character.missing_abilities(&self.ability_lib).contains(&id) {
+                self.error_note("no ability with this id".to_string())
+            } else {
+                self.name_note(&self.ability_lib.id(id).name)
+            };
+        }
+        if let Some(i) = list_index(path, "auras") {
+            let id = character.auras[i];
+            return if character.missing_auras(&self.aura_lib).contains(&id) {
+                self.error_note("no aura with this id".to_string())
+            } else {
+                self.name_note(&self.aura_lib.id(id).name)
+            };
+        }
+        if path == "loot_table_id" {
+            let id = character.loot_table_id?;
+            return if character.missing_loot_table(&self.loot_table_lib).is_some() {
+                self.error_note("no loot table with this id".to_string())
+            } else {
+                self.name_note(&self.loot_table_lib.id(id).name)
+            };
+        }
+
+        let i = list_index(path, "equipment")?;
+        let mut errors = character
+            .validate_equipment(&self.item_lib)
+            .into_iter()
+            .filter(|(index, _)| *index == i)
+            .map(|(_, error)| error);
+        if path.ends_with(".slot") {
+            return errors.find_map(|error| match error {
+                EquipmentError::DuplicateSlot { .. } => {
+                    self.error_note("slot already used".to_string())
+                }
+                EquipmentError::NoSlot { .. } => {
+                    self.error_note("not an equipment slot".to_string())
+                }
+                _ => None,
+            });
+        }
+        if !path.ends_with(".item_id") {
+            return None;
+        }
+        let item_id = character.equipment[i].item_id;
+        match errors.find(|error| {
+            matches!(
+                error,
+                EquipmentError::MissingItem { .. } | EquipmentError::WrongSlot { .. }
+            )
+        }) {
+            Some(EquipmentError::WrongSlot { item_slot, .. }) => self.error_note(format!(
+                "{} goes in the {} slot",
+                self.item_lib.id(item_id).name,
+                item_slot
+            )),
+            Some(_) => self.error_note("no item with this id".to_string()),
+            None => self.name_note(&self.item_lib.id(item_id).name),
+        }
+    }
+
+    fn name_note(&self, name: &str) -> Option<Span<'static>> {
+        Some(Span::styled(
+            format!(" ({})", name),
+            Style::default().fg(self.window.theme.magenta_light),
+        ))
     }
 
-    fn add_aura_details(&self, asset: &Asset, with_cursor_marker: bool) -> Vec<Line> {
-        let mut details = vec![];
-        let aura = self.aura_lib.id(asset.id);
-        for path in &self.current_asset_fields {
-            details.push(self.format_detail(
-                path,
-                &get_string_value_from_path(aura, path),
-                with_cursor_marker,
-            ));
-        }
-        details
+    fn error_note(&self, text: String) -> Option<Span<'static>> {
+        Some(Span::styled(
+            format!(" ({})", text),
+            Style::default().fg(self.window.theme.red),
+        ))
     }
 
-    fn add_item_details(&self, asset: &Asset, with_cursor_marker: bool) -> Vec<Line> {
-        let mut details = vec![];
-        let item = self.item_lib.id(asset.id);
-        for path in &self.current_asset_fields {
-            details.push(self.format_detail(
-                path,
-                &get_string_value_from_path(item, path),
-                with_cursor_marker,
-            ));
-        }
-        details
+    // The name of the asset with the id, if there is one.
+    fn asset_name(&self, asset_type: AssetType, id: u32) -> Option<String> {
+        self.lib(asset_type)
+            .def(id)
+            .map(|def| def.name().to_string())
     }
 
-    fn format_detail<T: Display + Reflect>(
+    // Formats a single field. This is one line per line of text, unless the
+    // field is being edited inline, in which case it is pre-wrapped to `width`
+    // so the cursor position can be worked out from the input itself.
+    fn format_detail(
         &self,
         path: &str,
-        contents: &T,
-        with_cursor_marker: bool,
-    ) -> Line {
+        contents: &str,
+        width: u16,
+        markup: bool,
+        computed: bool,
+    ) -> Vec<Line<'static>> {
         // This is a bit hacky and probably has issues. If it parses to an f64
         // then assume its a number and color it red.
-        let contents_color = if contents.to_string().parse::<f64>().is_ok() {
+        let contents_color = if computed {
+            self.window.theme.cyan_light
+        } else if contents.parse::<f64>().is_ok() {
             self.window.theme.red
         } else {
             self.window.theme.white
@@ -552,16 +2141,21 @@ impl Database {
 
         let mut contents = contents.to_string();
         let field_color = if self.current_asset_fields[self.details_index] == path {
-            if self.editing_details {
-                contents = self.details_input.clone();
-                if with_cursor_marker {
-                    contents.replace_range(
-                        self.details_character_index.saturating_sub(1)
-                            ..self.details_character_index,
-                        MAGIC_CURSOR_SYMBOL,
-                    );
-                }
+            if self.editing_details && self.details_input.is_multi_line() {
+                contents = self.details_input.text().to_string();
                 self.window.theme.green
+            } else if self.editing_details {
+                return self.details_input.lines(
+                    width,
+                    vec![Span::styled(
+                        format!("{}: ", path),
+                        Style::default().fg(self.window.theme.green),
+                    )],
+                    Style::default().fg(contents_color),
+                    Style::default()
+                        .fg(self.window.theme.black_dark)
+                        .bg(self.window.theme.white),
+                );
             } else {
                 self.window.theme.red
             }
@@ -569,23 +2163,122 @@ impl Database {
             self.window.theme.blue
         };
 
-        vec![
+        let mut details = if markup {
+            self.format_markup(&contents)
+        } else {
+            contents
+                .split('\n')
+                .map(|line| Line::styled(line.to_string(), Style::default().fg(contents_color)))
+                .collect()
+        };
+        details[0].spans.insert(
+            0,
             Span::styled(format!("{}: ", path), Style::default().fg(field_color)),
-            Span::styled(contents.to_string(), Style::default().fg(contents_color)),
-        ]
-        .into()
+        );
+        details
     }
 
-    fn render_search_bar(&self, area: Rect, buf: &mut Buffer) {
-        Paragraph::new(self.search_input.to_string())
-            .block(
-                Block::default()
-                    .title("Search")
-                    .borders(Borders::ALL)
-                    .border_type(self.window.border_type)
-                    .style(Style::default().fg(self.window.theme.white))
-                    .title_style(self.get_title_style(DatabaseFrame::Search)),
-            )
+    // Colors each tag in rules text markup, followed by a line for each problem
+    // found in it.
+    fn format_markup(&self, contents: &str) -> Vec<Line<'static>> {
+        let theme = self.window.theme;
+        let error_style = Style::default().fg(theme.red).underlined();
+        let (markup, errors) = match Markup::parse(contents) {
+            Ok(markup) => {
+                let errors = markup.validate(&self.aura_lib, &self.item_lib);
+                (markup, errors)
+            }
+            Err(error) => (Markup::plain(contents), vec![error]),
+        };
+
+        let mut lines = vec![Line::default()];
+        for (i, token) in markup.tokens.iter().enumerate() {
+            let style = match token {
+                _ if errors.iter().any(|e| e.position == markup.offset(i)) => error_style,
+                MarkupToken::Text(_) => Style::default().fg(theme.white),
+                MarkupToken::Aura(_) => Style::default().fg(theme.magenta_light).bold(),
+                MarkupToken::Item(_) => Style::default().fg(theme.yellow_light).bold(),
+                MarkupToken::Stat { .. } => Style::default().fg(theme.green_light),
+                MarkupToken::Damage(damage_type) => Style::default().fg(match damage_type {
+                    DamageType::Physical => theme.white_light,
+                    DamageType::Magic => theme.blue_light,
+                    DamageType::Poison => theme.green_light,
+                    DamageType::Fire => theme.red_light,
+                    DamageType::Frost => theme.cyan_light,
+                    DamageType::Lightning => theme.yellow_light,
+                }),
+            };
+            for (j, text) in token.display_text().split('\n').enumerate() {
+                if j > 0 {
+                    lines.push(Line::default());
+                }
+                lines
+                    .last_mut()
+                    .unwrap()
+                    .push_span(Span::styled(text.to_string(), style));
+            }
+        }
+        for error in errors {
+            lines.push(Line::styled(
+                format!("  ! {}", error),
+                Style::default().fg(theme.red_dark),
+            ));
+        }
+        lines
+    }
+
+    // Moves the cursor to where it sits in the field being edited, scrolling
+    // the details frame so that it stays visible.
+    fn place_details_cursor(&mut self, area: Rect, details: &[Vec<Line>]) {
+        let width = area.width.saturating_sub(2);
+        let height = area.height.saturating_sub(2).max(1);
+        let offset = format!("{}: ", self.current_asset_fields[self.details_index]).width() as u16;
+        let rows_above = if self.details_index == 0 {
+            0
+        } else {
+            Paragraph::new(details[..self.details_index].concat())
+                .wrap(Wrap { trim: false })
+                .line_count(width) as u16
+        };
+        let (column, row) = self.details_input.cursor_position(width, offset);
+        let row = rows_above + row;
+
+        if row < self.details_scroll {
+            self.details_scroll = row;
+        } else if row >= self.details_scroll + height {
+            self.details_scroll = row + 1 - height;
+        }
+        self.cursor_position = Position {
+            x: area.x + 1 + column,
+            y: area.y + 1 + row - self.details_scroll,
+        };
+    }
+
+    fn render_search_bar(&mut self, area: Rect, buf: &mut Buffer) {
+        if self.active_frame == DatabaseFrame::Search {
+            let (column, _) = self.search_input.cursor_position(u16::MAX, 0);
+            self.cursor_position = Position {
+                x: area.x + 1 + min(column, area.width.saturating_sub(3)),
+                y: area.y + 1,
+            };
+        }
+        let mut block = Block::default()
+            .title("Search")
+            .borders(Borders::ALL)
+            .border_type(self.window.border_type)
+            .style(Style::default().fg(self.window.theme.white))
+            .title_style(self.get_title_style(DatabaseFrame::Search));
+        if let Some(status) = &self.status {
+            block = block.title_bottom(
+                Line::styled(
+                    status.clone(),
+                    Style::default().fg(self.window.theme.yellow),
+                )
+                .right_aligned(),
+            );
+        }
+        Paragraph::new(self.search_input.text().to_string())
+            .block(block)
             .bg(self.window.theme.black_dark)
             .fg(self.window.theme.white)
             .render(area, buf);
@@ -598,69 +2291,509 @@ impl Database {
             if self.assets[i]
                 .name
                 .to_lowercase()
-                .starts_with(&self.search_input.to_lowercase())
+                .starts_with(&self.search_input.text().to_lowercase())
             {
                 self.visible_assets.assets.push(i)
             }
         }
         self.visible_assets.clamp();
     }
+}
 
-    // This is a horrible, terrible hack. We render the entire details frame twice
-    // to a duplicate buffer with a special character, and then iterate through
-    // the entire frame until we find the absolute position of that special
-    // character, and use that as our cursor location.
-    //
-    // TODO: Fix incorrect cursor behaviour that occurs when deleting characters
-    // causes the text to unwrap.
-    // TODO: Get rid of this entirely once Ratatui has better support for getting
-    // cursor position from wrapped text.
-    fn get_cursor_position(&mut self, area: Rect, buf: &mut Buffer) {
-        let mut fake_buf = buf.clone();
-        self.render_details(area, &mut fake_buf, true);
-        for x in 0..area.width {
-            for y in 0..area.height {
-                if let Some(character) = fake_buf.cell(Position { x, y }) {
-                    if character.symbol() == MAGIC_CURSOR_SYMBOL {
-                        if self.details_character_index == 0 {
-                            self.cursor_position = Position { x, y };
-                        } else {
-                            self.cursor_position = Position { x: x + 1, y };
-                        }
+// What is watched for changes to the asset lib with the name: its file, or in
+// the directory layout, its directory.
+fn watch_path(name: &str, project: &Project) -> PathBuf {
+    let path = PathBuf::from(project.lib_path(name));
+    match project.lib_storage(name).layout {
+        AssetLayout::File => path,
+        AssetLayout::Directory => path.parent().unwrap().to_path_buf(),
+    }
+}
 
-                        break;
-                    }
+// An empty lib for assets of the type, to load a lib into.
+fn empty_lib(asset_type: AssetType) -> Box<dyn AssetLib> {
+    match asset_type {
+        AssetType::Ability => Box::new(AbilityLib::default()),
+        AssetType::Aura => Box::new(AuraLib::default()),
+        AssetType::Character => Box::new(CharacterLib::default()),
+        AssetType::Item => Box::new(ItemLib::default()),
+        AssetType::LootTable => Box::new(LootTableLib::default()),
+        AssetType::Recipe => Box::new(RecipeLib::default()),
+        AssetType::Vendor => Box::new(VendorLib::default()),
+    }
+}
+
+// Reads a lib from a value, where `Value::Null` is an empty lib.
+fn lib_from_value(
+    asset_type: AssetType,
+    lib: &serde_json::Value,
+) -> Result<Box<dyn AssetLib>, String> {
+    let mut asset_lib = empty_lib(asset_type);
+    if !lib.is_null() {
+        asset_lib.load_value(lib.clone())?;
+    }
+    Ok(asset_lib)
+}
+
+// Reads the asset type's lib from the file at `path`.
+fn open_lib(asset_type: AssetType, path: &str) -> Result<Box<dyn AssetLib>, String> {
+    let mut lib = empty_lib(asset_type);
+    lib.load(path)?;
+    Ok(lib)
+}
+
+// Saves a lib value, read through the asset type's lib, to `path`.
+fn save_lib_value(asset_type: AssetType, lib: serde_json::Value, path: &str) -> Result<(), String> {
+    lib_from_value(asset_type, &lib)?.save(path)
+}
+
+// Reads the asset type's lib as it was at a git revision, as a value in the
+// same form as `to_value` gives. Libs that didn't exist are `Value::Null`.
+fn read_lib_at(
+    revision: &str,
+    asset_type: AssetType,
+    project: &Project,
+) -> Result<serde_json::Value, String> {
+    let path = project.lib_path(lib_name(asset_type));
+    let storage = project.lib_storage(lib_name(asset_type));
+    let Some(data) = git::show_file(revision, &path)? else {
+        return Ok(serde_json::Value::Null);
+    };
+    let files = match storage.layout {
+        AssetLayout::File => LibFiles::from_file(storage.format, data),
+        AssetLayout::Directory => {
+            let dir = Path::new(&path).parent().unwrap().to_str().unwrap();
+            LibFiles::from_dir(storage.format, data, git::show_dir(revision, dir)?)
+        }
+    };
+    let mut lib = empty_lib(asset_type);
+    lib.load_files(&files)
+        .map_err(|error| format!("{} at {} was not well-formatted: {}", path, revision, error))?;
+    Ok(lib.to_value())
+}
+
+// How the defs of a lib differ from an older version of them, matched by id.
+fn def_changes(
+    asset_type: AssetType,
+    old: &[&dyn AssetDef],
+    new: &[&dyn AssetDef],
+) -> Vec<AssetChange> {
+    let find = |defs: &[&dyn AssetDef], id: u32| defs.iter().any(|def| def.id() == id);
+    let change = |def: &dyn AssetDef, kind: ChangeKind, fields: Vec<FieldChange>| AssetChange {
+        asset_type,
+        id: def.id(),
+        name: def.name().to_string(),
+        kind,
+        fields,
+    };
+    let mut changes = vec![];
+    for def in new {
+        match old.iter().find(|old_def| old_def.id() == def.id()) {
+            None => changes.push(change(*def, ChangeKind::Added, vec![])),
+            Some(old_def) => {
+                let fields: Vec<FieldChange> =
+                    paired_fields(old_def.as_reflect(), def.as_reflect())
+                        .into_iter()
+                        .filter(|(_, old, new)| old != new)
+                        .map(|(path, old, new)| FieldChange { path, old, new })
+                        .collect();
+                if !fields.is_empty() {
+                    changes.push(change(*def, ChangeKind::Modified, fields));
                 }
             }
         }
     }
+    for def in old {
+        if !find(new, def.id()) {
+            changes.push(change(*def, ChangeKind::Removed, vec![]));
+        }
+    }
+    changes
+}
+
+// Every field path of two defs, with the value of each def at the path, or
+// `None` if it doesn't have the field.
+pub(crate) fn paired_fields(
+    left: &dyn Reflect,
+    right: &dyn Reflect,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut paths = get_def_paths(left);
+    for path in get_def_paths(right) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    let value = |def: &dyn Reflect, path: &str| {
+        def.reflect_path(path)
+            .is_ok()
+            .then(|| get_string_value_from_path(def, path))
+    };
+    paths
+        .into_iter()
+        .map(|path| {
+            let (left, right) = (value(left, &path), value(right, &path));
+            (path, left, right)
+        })
+        .collect()
+}
+
+// Reads the asset type's lib from disk as a value, in the same form as
+// `to_value` gives for the lib in memory, so that the two can be compared.
+fn read_lib_value(asset_type: AssetType, project: &Project) -> Result<serde_json::Value, String> {
+    Ok(open_lib(asset_type, &project.lib_path(lib_name(asset_type)))?.to_value())
+}
+
+// The name of the asset lib that holds assets of the type.
+pub fn lib_name(asset_type: AssetType) -> &'static str {
+    match asset_type {
+        AssetType::Ability => "ability",
+        AssetType::Aura => "aura",
+        AssetType::Character => "character",
+        AssetType::Item => "item",
+        AssetType::LootTable => "loot_table",
+        AssetType::Recipe => "recipe",
+        AssetType::Vendor => "vendor",
+    }
+}
+
+// Loads every asset lib in one format or layout and saves it in another,
+// giving the path each was written to, or why it couldn't be.
+pub fn convert_asset_libs(from: &Project, to: &Project) -> Vec<(String, Result<(), String>)> {
+    AssetType::ALL
+        .into_iter()
+        .map(|asset_type| {
+            let name = lib_name(asset_type);
+            let path = to.lib_path(name);
+            let converted =
+                open_lib(asset_type, &from.lib_path(name)).and_then(|lib| lib.save(&path));
+            (path, converted)
+        })
+        .collect()
+}
+
+// Upgrades every asset lib stored in the given way to the latest version, in
+// place, giving what changed in each. Files that are up to date are left alone.
+pub fn migrate_asset_libs(project: &Project) -> Vec<(String, Result<MigrationReport, String>)> {
+    AssetType::ALL
+        .into_iter()
+        .map(|asset_type| {
+            let path = project.lib_path(lib_name(asset_type));
+            let migrations = empty_lib(asset_type).migrations();
+            let report = LibFiles::read(&path).and_then(|files| {
+                let report = migration_report(&files, migrations)?;
+                if !report.is_up_to_date() {
+                    let mut lib = empty_lib(asset_type);
+                    lib.load_files(&files)?;
+                    lib.save(&path)?;
+                }
+                Ok(report)
+            });
+            (path, report)
+        })
+        .collect()
+}
+
+// Gives every def in the asset libs stored in the given way a guid, if it
+// doesn't have one, returning how many were given one in each file.
+pub fn assign_asset_guids(project: &Project) -> Vec<(String, Result<usize, String>)> {
+    AssetType::ALL
+        .into_iter()
+        .map(|asset_type| {
+            let path = project.lib_path(lib_name(asset_type));
+            let count = open_lib(asset_type, &path).and_then(|lib| {
+                let mut lib = lib.to_value();
+                let count = assign_guids(&mut lib);
+                if count > 0 {
+                    save_lib_value(asset_type, lib, &path)?;
+                }
+                Ok(count)
+            });
+            (path, count)
+        })
+        .collect()
+}
+
+// Renumbers defs with colliding ids after a git merge of `theirs` into `ours`,
+// and the references to them, saving the asset libs that change.
+pub fn renumber_asset_libs(
+    project: &Project,
+    ours: &str,
+    theirs: &str,
+) -> Result<RenumberReport, String> {
+    let mut libs = vec![];
+    for asset_type in AssetType::ALL {
+        libs.push(MergedLib {
+            asset_type,
+            references: empty_lib(asset_type).references(),
+            lib: read_lib_value(asset_type, project)?,
+            ours: read_lib_at(ours, asset_type, project)?,
+            theirs: read_lib_at(theirs, asset_type, project)?,
+        });
+    }
+    let before: Vec<serde_json::Value> = libs.iter().map(|lib| lib.lib.clone()).collect();
+    let report = renumber_collisions(&mut libs);
+    for (lib, before) in libs.into_iter().zip(before) {
+        if lib.lib != before {
+            let path = project.lib_path(lib_name(lib.asset_type));
+            save_lib_value(lib.asset_type, lib.lib, &path)
+                .map_err(|error| format!("{}: {}", path, error))?;
+        }
+    }
+    Ok(report)
+}
+
+// The asset type of the lib stored at a path, from the lib's name, e.g.
+// `asset/def/item.ron` or `asset/def/item/lib.ron`.
+pub fn lib_asset_type(path: &str) -> Result<AssetType, String> {
+    let path = Path::new(path);
+    let name = match AssetLayout::from_path(path.to_str().unwrap_or_default()) {
+        AssetLayout::File => path.file_stem(),
+        AssetLayout::Directory => path.parent().and_then(|dir| dir.file_name()),
+    };
+    let name = name.and_then(|name| name.to_str()).unwrap_or_default();
+    AssetType::ALL
+        .into_iter()
+        .find(|asset_type| lib_name(*asset_type) == name)
+        .ok_or(format!("{} is not an asset lib", path.display()))
+}
+
+// Reads a version of the lib at `lib_path` from another file, such as one git
+// made for a merge, in the lib's format. Empty files are `Value::Null`.
+fn read_lib_version(
+    path: &str,
+    asset_type: AssetType,
+    lib_path: &str,
+) -> Result<serde_json::Value, String> {
+    let data = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    if data.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    let format = AssetFormat::from_path(lib_path).unwrap_or_default();
+    let mut lib = empty_lib(asset_type);
+    lib.load_files(&LibFiles::from_file(format, data))
+        .map_err(|error| format!("{}: {}", path, error))?;
+    Ok(lib.to_value())
+}
+
+// How the assets in the `new` version of a lib differ from the `old`, field by
+// field. `lib_path` is where the lib is stored, which gives its format, and its
+// asset type by the project's lib paths or the lib's name.
+pub fn diff_asset_lib(
+    project: &Project,
+    old: &str,
+    new: &str,
+    lib_path: &str,
+) -> Result<Vec<AssetChange>, String> {
+    let asset_type = project.lib_asset_type(lib_path)?;
+    let old = read_lib_version(old, asset_type, lib_path)?;
+    let new = read_lib_version(new, asset_type, lib_path)?;
+    Ok(def_changes(
+        asset_type,
+        &lib_from_value(asset_type, &old)?.all_defs(),
+        &lib_from_value(asset_type, &new)?.all_defs(),
+    ))
+}
+
+// Merges the changes `ours` and `theirs` made to `base`, three versions of the
+// lib at `lib_path`, and writes the result to `ours`. This is what git runs as
+// a merge driver. Defs are matched by id and merged field by field. Fields that
+// both sides changed keep our value, and are returned as conflicts. They are
+// also listed between conflict markers at the top of the file, which keep it
+// from loading until they've been checked and the section removed.
+pub fn merge_asset_lib(
+    project: &Project,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    lib_path: &str,
+) -> Result<Vec<MergeConflict>, String> {
+    let asset_type = project.lib_asset_type(lib_path)?;
+    let merge = merge_libs(
+        &read_lib_version(base, asset_type, lib_path)?,
+        &read_lib_version(ours, asset_type, lib_path)?,
+        &read_lib_version(theirs, asset_type, lib_path)?,
+    );
+    let conflicts = merge.conflicts.clone();
+    // The files git makes for a merge have no extension to tell their format.
+    let format = AssetFormat::from_path(lib_path).unwrap_or_default();
+    let path = format!("{}.merged.{}", ours, format.extension());
+    save_lib_value(asset_type, merge.resolve(), &path)?;
+
+    let mut data = fs::read_to_string(&path).map_err(|error| error.to_string())?;
+    let _ = fs::remove_file(&path);
+    if !conflicts.is_empty() {
+        let mut section = String::from(
+            "<<<<<<< Merge conflicts, keeping ours. Check each field, then remove this section\n",
+        );
+        for conflict in &conflicts {
+            section.push_str(&format!("{}\n", conflict.to_string().replace('\n', " ")));
+        }
+        section.push_str(">>>>>>> End of merge conflicts\n");
+        data.insert_str(0, &section);
+    }
+    fs::write(ours, data).map_err(|error| format!("{}: {}", ours, error))?;
+    Ok(conflicts)
 }
 
-fn get_def_paths_helper(def: &dyn Struct, current_path: &str, paths: &mut Vec<String>) {
-    for (i, field) in def.iter_fields().enumerate() {
-        match field.reflect_ref().as_struct() {
-            Err(_) => {
+// Compiles every asset lib, stored in the given way, into a bundle for the
+// game to load at runtime.
+pub fn bundle_asset_libs(project: &Project) -> Result<BundleWriter, BundleError> {
+    let mut bundle = BundleWriter::new();
+    for asset_type in AssetType::ALL {
+        let name = lib_name(asset_type);
+        let path = project.lib_path(name);
+        let lib = open_lib(asset_type, &path)
+            .map_err(|error| BundleError::InvalidLib(path.clone(), error))?;
+        lib.add_to_bundle(&mut bundle, name)?;
+    }
+    Ok(bundle)
+}
+
+fn get_def_paths_helper(field: &dyn PartialReflect, current_path: &str, paths: &mut Vec<String>) {
+    match field.reflect_ref() {
+        ReflectRef::Struct(def) => {
+            for (i, field) in def.iter_fields().enumerate() {
+                let name = def.name_at(i).unwrap();
                 if current_path.is_empty() {
-                    paths.push(def.name_at(i).unwrap().to_string());
+                    get_def_paths_helper(field, name, paths);
                 } else {
-                    paths.push(format!("{}.{}", current_path, def.name_at(i).unwrap()));
+                    get_def_paths_helper(field, &format!("{}.{}", current_path, name), paths);
                 }
             }
-            Ok(sub_field) => get_def_paths_helper(sub_field, def.name_at(i).unwrap(), paths),
         }
+        // Lists get a path of their own so that empty ones can still be added to.
+        ReflectRef::List(list) => {
+            paths.push(current_path.to_string());
+            for (i, element) in list.iter().enumerate() {
+                get_def_paths_helper(element, &format!("{}[{}]", current_path, i), paths);
+            }
+        }
+        _ => paths.push(current_path.to_string()),
     }
 }
 
-fn get_def_paths(def: &dyn Struct) -> Vec<String> {
+fn get_def_paths(def: &dyn Reflect) -> Vec<String> {
     let mut field_paths: Vec<String> = vec![];
-    get_def_paths_helper(def, "", &mut field_paths);
+    if let ReflectRef::Struct(_) = def.reflect_ref() {
+        get_def_paths_helper(def.as_partial_reflect(), "", &mut field_paths);
+    }
     field_paths
 }
 
-fn get_string_value_from_path<T: PartialReflect + GetPath>(def: &T, path: &str) -> String {
+// The path of the list that the field at `path` is in, and the index of the
+// element it belongs to. For the path of a list itself, the index is `None`.
+fn get_list_path(def: &dyn Reflect, path: &str) -> Option<(String, Option<usize>)> {
+    if let Ok(ReflectRef::List(_)) = def.reflect_path(path).map(|field| field.reflect_ref()) {
+        return Some((path.to_string(), None));
+    }
+    let (list_path, rest) = path.rsplit_once('[')?;
+    let index = rest.split(']').next()?.parse().ok()?;
+    Some((list_path.to_string(), Some(index)))
+}
+
+// Appends a default element to the list at `path`. The element's type needs
+// `#[reflect(Default)]` for this to work.
+fn add_list_element<T: PartialReflect + GetPath + ?Sized>(
+    def: &mut T,
+    type_registry: &TypeRegistry,
+    path: &str,
+) -> Result<(), String> {
+    let field = def
+        .reflect_path_mut(path)
+        .map_err(|error| error.to_string())?;
+    let Some(TypeInfo::List(info)) = field.get_represented_type_info() else {
+        return Err(format!("{} is not a list", path));
+    };
+    let Some(default) = type_registry.get_type_data::<ReflectDefault>(info.item_ty().id()) else {
+        return Err(format!("Can't create new entries for {}", path));
+    };
+    if let ReflectMut::List(list) = field.reflect_mut() {
+        list.push(default.default().into_partial_reflect());
+    }
+    Ok(())
+}
+
+// Adds default entries to the lists that `path` goes through, where it is one
+// past the end of a list, e.g. `stock[2].item_id` on a def with two stock
+// entries. Paths further past the end are left as they are.
+fn add_missing_list_entries(
+    def: &mut dyn Reflect,
+    type_registry: &TypeRegistry,
+    path: &str,
+) -> Result<(), String> {
+    for (start, _) in path.match_indices('[') {
+        let list_path = &path[..start];
+        let Some(index) = list_index(&path[start..], "") else {
+            continue;
+        };
+        let len = match def.reflect_path(list_path).map(|field| field.reflect_ref()) {
+            Ok(ReflectRef::List(list)) => list.len(),
+            _ => continue,
+        };
+        if index == len {
+            add_list_element(def, type_registry, list_path)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_list_element<T: PartialReflect + GetPath + ?Sized>(
+    def: &mut T,
+    path: &str,
+    index: usize,
+) -> Result<(), String> {
+    let field = def
+        .reflect_path_mut(path)
+        .map_err(|error| error.to_string())?;
+    match field.reflect_mut() {
+        ReflectMut::List(list) if index < list.len() => {
+            list.remove(index);
+            Ok(())
+        }
+        _ => Err(format!("{} has no entry {}", path, index)),
+    }
+}
+
+// Whether a field should be edited in the multi-line editor. This is any
+// `String` field marked with `#[reflect(@MultiLine)]`, or that is already long.
+fn is_multi_line_field(def: &dyn Reflect, path: &str) -> bool {
+    let Ok(value) = def.path::<String>(path) else {
+        return false;
+    };
+    value.contains('\n')
+        || value.width() > LONG_TEXT_WIDTH
+        || field_has_attribute::<MultiLine>(def, path)
+}
+
+// Whether the field at `path` is marked with the custom reflect attribute `T`.
+fn field_has_attribute<T: Reflect>(def: &dyn Reflect, path: &str) -> bool {
+    let (parent, field) = match path.rsplit_once('.') {
+        Some((parent, field)) => (def.reflect_path(parent).ok(), field),
+        None => (Some(def.as_partial_reflect()), path),
+    };
+    parent
+        .and_then(|parent| parent.get_represented_type_info())
+        .and_then(|info| info.as_struct().ok())
+        .and_then(|info| info.field(field))
+        .is_some_and(|field| field.has_attribute::<T>())
+}
+
+// The index into `list` that a path is in, e.g. 2 for `abilities[2]` or
+// `equipment[2].slot` in the list `equipment`.
+fn list_index(path: &str, list: &str) -> Option<usize> {
+    let rest = path.strip_prefix(list)?.strip_prefix('[')?;
+    rest.split_once(']')?.0.parse().ok()
+}
+
+pub(crate) fn get_string_value_from_path<T: PartialReflect + GetPath + ?Sized>(
+    def: &T,
+    path: &str,
+) -> String {
     // Numeric Types
     if let Ok(value) = def.path::<u32>(path) {
         return (*value).to_string();
+    } else if let Ok(value) = def.path::<Option<u32>>(path) {
+        return value.map(|value| value.to_string()).unwrap_or_default();
     } else if let Ok(value) = def.path::<u64>(path) {
         return (*value).to_string();
     } else if let Ok(value) = def.path::<i32>(path) {
@@ -679,59 +2812,160 @@ fn get_string_value_from_path<T: PartialReflect + GetPath>(def: &T, path: &str)
         return (*value).to_string();
     } else if let Ok(value) = def.path::<&str>(path) {
         return (*value).to_string();
+    }
 
-    // Enums
-    // TODO: Find some way to handle enums more generally.
-    } else if let Ok(value) = def.path::<ItemType>(path) {
-        return (*value).to_string();
-    } else if let Ok(value) = def.path::<ItemRarity>(path) {
-        return (*value).to_string();
-    } else if let Ok(value) = def.path::<EquipmentSlot>(path) {
-        return (*value).to_string();
-    } else if let Ok(value) = def.path::<AuraType>(path) {
-        return (*value).to_string();
+    match def.reflect_path(path).map(|field| field.reflect_ref()) {
+        // Enums without data, by variant name
+        Ok(ReflectRef::Enum(value)) if value.variant_type() == VariantType::Unit => {
+            value.variant_name().to_string()
+        }
+        Ok(ReflectRef::List(list)) if list.len() == 1 => "[1 entry]".to_string(),
+        Ok(ReflectRef::List(list)) => format!("[{} entries]", list.len()),
+        _ => "UNKNOWN_TYPE".to_string(),
     }
+}
+
+// The current value of each field of `def`, for evaluating expressions.
+fn field_variable<T: PartialReflect + GetPath + ?Sized>(
+    def: &T,
+) -> impl Fn(&str) -> Option<Value> + '_ {
+    move |name| {
+        def.reflect_path(name)
+            .ok()
+            .map(|_| Value::from_field(&get_string_value_from_path(def, name)))
+    }
+}
+
+// Formats a computed value the way the field at `path` stores it, so whole
+// number fields are rounded.
+fn format_field_value<T: PartialReflect + GetPath + ?Sized>(
+    def: &T,
+    path: &str,
+    value: Value,
+) -> String {
+    let Value::Number(number) = value else {
+        return value.to_string();
+    };
+    match def
+        .reflect_path(path)
+        .map(|field| field.reflect_type_path())
+    {
+        Ok("u32" | "u64" | "i32" | "i64") => number.round().to_string(),
+        Ok("f32") => (number as f32).to_string(),
+        _ => number.to_string(),
+    }
+}
 
-    "UNKNOWN_TYPE".to_string()
+// The value the formula for `path` gives for `def`, if it has one.
+fn formula_value<T: PartialReflect + GetPath + ?Sized>(
+    def: &T,
+    formulas: &FormulaSet,
+    path: &str,
+) -> Option<Result<String, String>> {
+    formulas.compute(path, &field_variable(def)).map(|result| {
+        result
+            .map(|value| format_field_value(def, path, value))
+            .map_err(|error| error.to_string())
+    })
 }
 
-fn set_field_value_from_string<T: PartialReflect + GetPath>(
+// Sets a field like `set_field_value_from_string`, then recomputes the fields
+// with formulas that depend on it, leaving the `overridden` ones alone.
+fn set_field_with_formulas<T: PartialReflect + GetPath + ?Sized>(
     def: &mut T,
+    formulas: &FormulaSet,
+    overridden: &[String],
     path: &str,
     new_value: String,
+) -> Result<(), String> {
+    set_field_value_from_string(def, path, new_value)?;
+    recompute_fields(def, formulas, overridden, &formulas.dependents(path));
+    Ok(())
+}
+
+// Sets each field in `paths` that isn't `overridden` to the value its formula
+// gives, in order.
+fn recompute_fields<T: PartialReflect + GetPath + ?Sized>(
+    def: &mut T,
+    formulas: &FormulaSet,
+    overridden: &[String],
+    paths: &[String],
 ) {
-    // TODO: Don't panic when failing to parse. Come up with a way
-    // to indicate to the user that a failure occurred.
+    for path in paths.iter().filter(|path| !overridden.contains(path)) {
+        if let Some(Ok(value)) = formula_value(def, formulas, path) {
+            let _ = set_field_value_from_string(def, path, value);
+        }
+    }
+}
+
+fn set_field_value_from_string<T: PartialReflect + GetPath + ?Sized>(
+    def: &mut T,
+    path: &str,
+    new_value: String,
+) -> Result<(), String> {
     // Numeric Types
     if let Ok(value) = def.path_mut::<u32>(path) {
-        *value = new_value.parse::<u32>().unwrap();
+        *value = parse_field(&new_value, "whole number")?;
+    } else if let Ok(value) = def.path_mut::<Option<u32>>(path) {
+        // Left empty for none.
+        *value = match new_value.trim() {
+            "" => None,
+            new_value => Some(parse_field(new_value, "whole number")?),
+        };
     } else if let Ok(value) = def.path_mut::<u64>(path) {
-        *value = new_value.parse::<u64>().unwrap();
+        *value = parse_field(&new_value, "whole number")?;
     } else if let Ok(value) = def.path_mut::<i32>(path) {
-        *value = new_value.parse::<i32>().unwrap();
+        *value = parse_field(&new_value, "integer")?;
     } else if let Ok(value) = def.path_mut::<i64>(path) {
-        *value = new_value.parse::<i64>().unwrap();
+        *value = parse_field(&new_value, "integer")?;
     } else if let Ok(value) = def.path_mut::<f32>(path) {
-        *value = new_value.parse::<f32>().unwrap();
+        *value = parse_field(&new_value, "number")?;
     } else if let Ok(value) = def.path_mut::<f64>(path) {
-        *value = new_value.parse::<f64>().unwrap();
+        *value = parse_field(&new_value, "number")?;
 
     // String
     } else if let Ok(value) = def.path_mut::<String>(path) {
         *value = new_value
+    } else if let Ok(field) = def.reflect_path_mut(path) {
+        set_reflected_value_from_string(field, path, &new_value)?;
+    } else {
+        return Err(format!("{} can't be edited", path));
+    }
+    Ok(())
+}
 
-    // Enums
-    } else if let Ok(value) = def.path_mut::<ItemType>(path) {
-        *value = new_value.parse::<ItemType>().unwrap();
-    } else if let Ok(value) = def.path_mut::<ItemRarity>(path) {
-        *value = new_value.parse::<ItemRarity>().unwrap();
-    } else if let Ok(value) = def.path_mut::<EquipmentSlot>(path) {
-        *value = new_value.parse::<EquipmentSlot>().unwrap();
-    } else if let Ok(value) = def.path_mut::<AuraType>(path) {
-        *value = new_value.parse::<AuraType>().unwrap();
+// Sets fields that aren't a number or string. Enums without data are set by
+// variant name.
+fn set_reflected_value_from_string(
+    field: &mut dyn PartialReflect,
+    path: &str,
+    new_value: &str,
+) -> Result<(), String> {
+    match field.get_represented_type_info() {
+        Some(TypeInfo::Enum(info)) => match info.variant(new_value) {
+            Some(VariantInfo::Unit(_)) => field
+                .try_apply(&DynamicEnum::new(new_value, DynamicVariant::Unit))
+                .map_err(|error| error.to_string()),
+            _ => Err(format!(
+                "'{}' is not a valid {}",
+                new_value,
+                info.type_path_table().ident().unwrap_or(info.type_path())
+            )),
+        },
+        Some(TypeInfo::List(_)) => Err(format!(
+            "Use Ctrl+N to add an entry to {}, or Ctrl+D to remove one",
+            path
+        )),
+        _ => Err(format!("{} can't be edited", path)),
     }
 }
 
+fn parse_field<T: FromStr>(value: &str, kind: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("'{}' is not a valid {}", value, kind))
+}
+
 impl AssetList {
     fn from_assets(assets: Vec<usize>) -> AssetList {
         AssetList {
@@ -764,7 +2998,505 @@ impl AssetList {
 }
 
 impl Asset {
-    fn to_list_item(&self) -> ListItem {
-        ListItem::new(self.name.to_string())
+    fn to_list_item(&self, marked: bool, selected: bool) -> ListItem<'_> {
+        match (marked, selected) {
+            (true, true) => ListItem::new(format!("*+ {}", self.name)),
+            (true, false) => ListItem::new(format!("* {}", self.name)),
+            (false, true) => ListItem::new(format!("+ {}", self.name)),
+            (false, false) => ListItem::new(self.name.to_string()),
+        }
+    }
+
+    // Whether both refer to the same def.
+    fn is(&self, other: &Asset) -> bool {
+        self.asset_type == other.asset_type && self.id == other.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_mechanic::item::equipment::EquipmentSlot;
+    use game_system::asset::bundle::AssetBundle;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn item(id: u32, name: &str, buy_value: u32) -> Arc<ItemDef> {
+        let def = json!({
+            "id": id,
+            "name": name,
+            "rules_text": "",
+            "flavor_text": "",
+            "icon": "sprite/icon/cheese.png",
+            "item_type": "Material",
+            "item_rarity": "Common",
+            "max_stack": 20,
+            "buy_value": buy_value,
+            "sell_value": 1,
+        });
+        Arc::new(serde_json::from_value(def).unwrap())
+    }
+
+    // A database over the repository's own assets.
+    fn database() -> Database {
+        let project = Project {
+            dir: PathBuf::from("../../.."),
+            ..Project::default()
+        };
+        Database::with_project(Window::default(), project)
+    }
+
+    #[test]
+    fn character_reference_notes() {
+        let database = database();
+        let mut character = (*database.character_lib.id(0)).clone();
+        let entry = |slot, item_id| EquipmentEntry { slot, item_id };
+        character.equipment = vec![
+            entry(EquipmentSlot::Feet, 1),
+            entry(EquipmentSlot::Feet, 1),
+            entry(EquipmentSlot::None, 0),
+            entry(EquipmentSlot::Head, 1),
+            entry(EquipmentSlot::Hands, 99),
+        ];
+        character.abilities = vec![0, 99];
+        let note = |path| {
+            database
+                .character_note(&character, path)
+                .map(|span| span.content.to_string())
+        };
+        assert_eq!(note("equipment[0].slot"), None);
+        assert_eq!(note("equipment[0].item_id"), Some(" (Shoe)".to_string()));
+        assert_eq!(
+            note("equipment[1].slot"),
+            Some(" (slot already used)".to_string())
+        );
+        assert_eq!(
+            note("equipment[2].slot"),
+            Some(" (not an equipment slot)".to_string())
+        );
+        assert_eq!(
+            note("equipment[3].item_id"),
+            Some(" (Shoe goes in the Feet slot)".to_string())
+        );
+        assert_eq!(
+            note("equipment[4].item_id"),
+            Some(" (no item with this id)".to_string())
+        );
+        assert_eq!(note("abilities[0]"), Some(" (Zap)".to_string()));
+        assert_eq!(
+            note("abilities[1]"),
+            Some(" (no ability with this id)".to_string())
+        );
+        assert_eq!(note("loot_table_id"), Some(" (Goblin Pockets)".to_string()));
+
+        set_field_value_from_string(&mut character, "loot_table_id", "99".to_string()).unwrap();
+        assert_eq!(
+            get_string_value_from_path(&character, "loot_table_id"),
+            "99"
+        );
+        assert_eq!(
+            database
+                .character_note(&character, "loot_table_id")
+                .map(|span| span.content.to_string()),
+            Some(" (no loot table with this id)".to_string())
+        );
+        set_field_value_from_string(&mut character, "loot_table_id", "".to_string()).unwrap();
+        assert_eq!(character.loot_table_id, None);
+        assert_eq!(get_string_value_from_path(&character, "loot_table_id"), "");
+    }
+
+    #[test]
+    fn edit_defs_through_their_libs() {
+        let mut database = database();
+        for asset_type in AssetType::ALL {
+            let asset = database
+                .assets
+                .iter()
+                .find(|asset| asset.asset_type == asset_type)
+                .cloned()
+                .unwrap();
+            let renamed = format!("Renamed {}", asset_type);
+            assert_eq!(
+                database.check_field(&asset, "name", renamed.clone()),
+                Ok(renamed.clone())
+            );
+            assert_eq!(
+                database.asset_name(asset_type, asset.id),
+                Some(asset.name.clone())
+            );
+            database
+                .update_field(&asset, "name", renamed.clone())
+                .unwrap();
+            assert_eq!(database.asset_name(asset_type, asset.id), Some(renamed));
+            assert!(database.check_field(&asset, "id", "x".to_string()).is_err());
+        }
+        let missing = Asset {
+            id: 99,
+            ..database.assets[0].clone()
+        };
+        assert!(database
+            .update_field(&missing, "name", "Gone".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn overridden_fields_keep_their_values() {
+        let mut database = database();
+        let items: Vec<Asset> = database
+            .assets
+            .iter()
+            .filter(|asset| asset.asset_type == AssetType::Item)
+            .take(2)
+            .cloned()
+            .collect();
+        let sell_value =
+            |database: &Database, asset: &Asset| database.item_lib.id(asset.id).sell_value;
+        database
+            .update_field(&items[0], "sell_value", "7".to_string())
+            .unwrap();
+        assert!(database
+            .item_lib
+            .overrides
+            .contains(items[0].id, "sell_value"));
+
+        // Editing `buy_value` recomputes `sell_value` only where it isn't
+        // overridden, and so does changing the formula.
+        for asset in &items {
+            database
+                .update_field(asset, "buy_value", "100".to_string())
+                .unwrap();
+        }
+        assert_eq!(sell_value(&database, &items[0]), 7);
+        assert_eq!(sell_value(&database, &items[1]), 50);
+        database
+            .set_formula(&items[1], "sell_value", "buy_value / 4")
+            .unwrap();
+        assert_eq!(sell_value(&database, &items[0]), 7);
+        assert_eq!(sell_value(&database, &items[1]), 25);
+
+        // Typing the value the formula gives follows the formula again.
+        database
+            .update_field(&items[0], "sell_value", "25".to_string())
+            .unwrap();
+        assert!(database.item_lib.overrides.is_empty());
+        database
+            .update_field(&items[0], "buy_value", "40".to_string())
+            .unwrap();
+        assert_eq!(sell_value(&database, &items[0]), 10);
+
+        // The overrides are saved with the lib.
+        database
+            .update_field(&items[1], "sell_value", "1".to_string())
+            .unwrap();
+        let lib = database.lib_value(AssetType::Item);
+        assert_eq!(lib["overrides"], json!({ "sell_value": [items[1].id] }));
+        assert!(ItemLib::from_value(lib)
+            .unwrap()
+            .overrides
+            .contains(items[1].id, "sell_value"));
+    }
+
+    #[test]
+    fn csv_import_adds_list_entries() {
+        let mut database = database();
+        let sheet = CsvSheet {
+            columns: vec![
+                "id".to_string(),
+                "stock[1].item_id".to_string(),
+                "stock[1].quantity".to_string(),
+            ],
+            rows: vec![],
+        };
+        let row = |cells: [&str; 3]| CsvRow {
+            line: 2,
+            cells: cells.iter().map(|cell| cell.to_string()).collect(),
+        };
+        let changes = database
+            .csv_row_changes(
+                AssetType::Vendor,
+                &sheet,
+                &row(["0", "1", "3"]),
+                &mut HashMap::new(),
+            )
+            .unwrap();
+        let values: Vec<(&str, &str, &str)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.path.as_str(),
+                    change.old.as_str(),
+                    change.new.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("stock[1].item_id", "", "1"),
+                ("stock[1].quantity", "", "3")
+            ]
+        );
+        assert!(database
+            .csv_row_changes(
+                AssetType::Vendor,
+                &sheet,
+                &row(["1", "1", "x"]),
+                &mut HashMap::new(),
+            )
+            .is_err());
+
+        // Changes that no longer fit are reported, and the rest still apply.
+        let mut failing = changes[0].clone();
+        failing.path = "stock[5].item_id".to_string();
+        let mut changes = changes;
+        changes.push(failing);
+        database.csv_import = Some(CsvImport::new("vendor.csv".to_string(), changes, vec![]));
+        database.commit_csv_import();
+        let stock = &database.vendor_lib.id(0).stock;
+        assert_eq!(
+            (stock.len(), stock[1].item_id, stock[1].quantity),
+            (2, 1, 3)
+        );
+        assert!(database.status.as_ref().unwrap().starts_with(
+            "Imported 2 changes from vendor.csv, but couldn't import Cheesemonger's Stall stock[5].item_id"
+        ));
+    }
+
+    #[test]
+    fn def_changes_by_field() {
+        let old = [
+            item(0, "Cheese", 2),
+            item(1, "Bread", 3),
+            item(2, "Shoe", 5),
+        ];
+        let new = [
+            item(0, "Cheese", 2),
+            item(1, "Stale Bread", 1),
+            item(3, "Boot", 8),
+        ];
+        let old: Vec<&dyn AssetDef> = old.iter().map(|def| def.as_ref() as _).collect();
+        let new: Vec<&dyn AssetDef> = new.iter().map(|def| def.as_ref() as _).collect();
+        let changes = def_changes(AssetType::Item, &old, &new);
+        let kinds: Vec<(u32, ChangeKind)> = changes
+            .iter()
+            .map(|change| (change.id, change.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (1, ChangeKind::Modified),
+                (3, ChangeKind::Added),
+                (2, ChangeKind::Removed)
+            ]
+        );
+        let field = |path: &str, old: &str, new: &str| FieldChange {
+            path: path.to_string(),
+            old: Some(old.to_string()),
+            new: Some(new.to_string()),
+        };
+        assert_eq!(
+            changes[0].fields,
+            vec![
+                field("name", "Bread", "Stale Bread"),
+                field("buy_value", "3", "1")
+            ]
+        );
+        assert_eq!(changes[0].name, "Stale Bread");
+        assert!(changes[1].fields.is_empty());
+    }
+
+    #[test]
+    fn bundle_every_lib() {
+        let project = Project {
+            dir: PathBuf::from("../../.."),
+            ..Project::default()
+        };
+        let bytes = bundle_asset_libs(&project)
+            .and_then(|bundle| bundle.to_bytes())
+            .unwrap();
+        let bundle = AssetBundle::read(&bytes).unwrap();
+        let database = database();
+        let ability_lib = AbilityLib::from_bundle(&bundle, "ability").unwrap();
+        assert_eq!(ability_lib.defs, database.ability_lib.defs);
+        let aura_lib = AuraLib::from_bundle(&bundle, "aura").unwrap();
+        assert_eq!(aura_lib.defs, database.aura_lib.defs);
+        let character_lib = CharacterLib::from_bundle(&bundle, "character").unwrap();
+        assert_eq!(character_lib.defs, database.character_lib.defs);
+        let item_lib = ItemLib::from_bundle(&bundle, "item").unwrap();
+        assert_eq!(item_lib.defs, database.item_lib.defs);
+        assert_eq!(item_lib.formulas, database.item_lib.formulas);
+        let loot_table_lib = LootTableLib::from_bundle(&bundle, "loot_table").unwrap();
+        assert_eq!(loot_table_lib.defs, database.loot_table_lib.defs);
+        let recipe_lib = RecipeLib::from_bundle(&bundle, "recipe").unwrap();
+        assert_eq!(recipe_lib.defs, database.recipe_lib.defs);
+        let vendor_lib = VendorLib::from_bundle(&bundle, "vendor").unwrap();
+        assert_eq!(vendor_lib.defs, database.vendor_lib.defs);
+
+        // A lib that can't be read fails the bundle rather than the editor.
+        let dir = std::env::temp_dir().join(format!("bundle_libs_{}", std::process::id()));
+        fs::create_dir_all(dir.join("asset/def")).unwrap();
+        for entry in fs::read_dir("../../../asset/def").unwrap().flatten() {
+            fs::copy(entry.path(), dir.join("asset/def").join(entry.file_name())).unwrap();
+        }
+        let project = Project {
+            dir: dir.clone(),
+            ..Project::default()
+        };
+        let path = project.lib_path("item");
+        fs::write(&path, "(next_id:").unwrap();
+        assert!(matches!(
+            bundle_asset_libs(&project),
+            Err(BundleError::InvalidLib(invalid, _)) if invalid == path
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn save_libs_waits_for_conflicts_and_errors() {
+        let dir = std::env::temp_dir().join(format!("save_libs_{}", std::process::id()));
+        for sub_dir in ["asset/def", "asset/template"] {
+            fs::create_dir_all(dir.join(sub_dir)).unwrap();
+            for entry in fs::read_dir(format!("../../../{}", sub_dir))
+                .unwrap()
+                .flatten()
+            {
+                fs::copy(entry.path(), dir.join(sub_dir).join(entry.file_name())).unwrap();
+            }
+        }
+        let project = Project {
+            dir: dir.clone(),
+            ..Project::default()
+        };
+        let path = project.lib_path("item");
+        let mut database = Database::with_project(Window::default(), project);
+        let mut ours = (*database.item_lib.id(0)).clone();
+        ours.buy_value = 111;
+        database.item_lib.update_def(Arc::new(ours));
+        let mut theirs = ItemLib::new(&path);
+        let mut def = (*theirs.id(0)).clone();
+        def.buy_value = 222;
+        theirs.update_def(Arc::new(def));
+        theirs.save(&path).unwrap();
+
+        // Both changed the same field, so the save waits for the merge.
+        assert!(!database.save_libs());
+        assert!(database.merge_prompt.is_some());
+        assert_eq!(ItemLib::new(&path).id(0).buy_value, 222);
+        database.commit_merge_prompt();
+        assert!(database.save_libs());
+        assert_eq!(ItemLib::new(&path).id(0).buy_value, 111);
+
+        // A file that can't be read isn't saved over.
+        let mut ours = (*database.item_lib.id(0)).clone();
+        ours.buy_value = 333;
+        database.item_lib.update_def(Arc::new(ours));
+        fs::write(&path, "(next_id:").unwrap();
+        assert!(!database.save_libs());
+        assert!(database
+            .status
+            .as_ref()
+            .is_some_and(|status| status.contains("was not saved")));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "(next_id:");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn empty_libs_have_no_current_asset() {
+        let dir = std::env::temp_dir().join(format!("empty_libs_{}", std::process::id()));
+        fs::create_dir_all(dir.join("asset/def")).unwrap();
+        fs::create_dir_all(dir.join("asset/template")).unwrap();
+        for entry in fs::read_dir("../../../asset/def").unwrap().flatten() {
+            fs::write(
+                dir.join("asset/def").join(entry.file_name()),
+                "(version: 1, next_id: 0)",
+            )
+            .unwrap();
+        }
+        for entry in fs::read_dir("../../../asset/template").unwrap().flatten() {
+            fs::copy(
+                entry.path(),
+                dir.join("asset/template").join(entry.file_name()),
+            )
+            .unwrap();
+        }
+        let project = Project {
+            dir: dir.clone(),
+            ..Project::default()
+        };
+        let mut database = Database::with_project(Window::default(), project);
+        assert!(database.current_asset.is_none());
+
+        // Nothing to edit, so editing the details does nothing.
+        let area = Rect::new(0, 0, 120, 40);
+        (&mut database).render(area, &mut Buffer::empty(area));
+        database.commit_details_input();
+        database.add_list_element();
+        database.remove_list_element();
+        assert!(database.current_asset.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merge_asset_lib_files() {
+        assert_eq!(lib_asset_type("asset/def/item.ron"), Ok(AssetType::Item));
+        assert_eq!(
+            lib_asset_type("asset/def/loot_table/lib.json"),
+            Ok(AssetType::LootTable)
+        );
+        assert!(lib_asset_type("asset/def/4-iron-bar.ron").is_err());
+
+        let dir = std::env::temp_dir().join(format!("merge_asset_lib_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, defs: Vec<Arc<ItemDef>>| {
+            let path = dir.join(name).to_str().unwrap().to_string();
+            let defs: Vec<ItemDef> = defs.iter().map(|def| (**def).clone()).collect();
+            let lib = json!({ "version": 1, "next_id": 4, "defs": defs });
+            fs::write(&path, lib.to_string()).unwrap();
+            path
+        };
+        // Git's copies of each side have no extension, so the format comes
+        // from the lib's path.
+        let base = write("base", vec![item(0, "Cheese", 2), item(1, "Bread", 3)]);
+        let ours = write(
+            "ours",
+            vec![item(0, "Cheese", 4), item(1, "Stale Bread", 3)],
+        );
+        let theirs = write("theirs", vec![item(0, "Cheese", 5), item(1, "Bread", 1)]);
+        let conflicts = merge_asset_lib(
+            &Project::default(),
+            &base,
+            &ours,
+            &theirs,
+            "asset/def/item.json",
+        )
+        .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].to_string(),
+            "0 Cheese buy_value: ours 4, theirs 5 (base 2)"
+        );
+        // The conflicts are listed at the top, so the file doesn't load until
+        // they're removed.
+        let data = fs::read_to_string(&ours).unwrap();
+        assert!(read_lib_version(&ours, AssetType::Item, "item.json").is_err());
+        let (section, data) = data.split_once(">>>>>>> End of merge conflicts\n").unwrap();
+        assert_eq!(
+            section.lines().collect::<Vec<_>>(),
+            vec![
+                "<<<<<<< Merge conflicts, keeping ours. Check each field, then remove this section",
+                "0 Cheese buy_value: ours 4, theirs 5 (base 2)",
+            ]
+        );
+        fs::write(&ours, data).unwrap();
+        let merged =
+            ItemLib::from_value(read_lib_version(&ours, AssetType::Item, "item.json").unwrap())
+                .unwrap();
+        assert_eq!(merged.id(0).buy_value, 4);
+        assert_eq!(merged.id(1).name, "Stale Bread");
+        assert_eq!(merged.id(1).buy_value, 1);
+
+        let changes = diff_asset_lib(&Project::default(), &base, &ours, "item.json").unwrap();
+        assert_eq!(changes.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -0,0 +1,131 @@
+use std::cmp::min;
+
+use game_mechanic::prelude::*;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+// Item prices across every vendor: the spread of prices per rarity, and each
+// item that can be bought from one vendor and sold to another for a profit.
+pub struct EconomyReportView {
+    pub report: EconomyReport,
+    pub state: TableState,
+}
+
+impl EconomyReportView {
+    pub fn new(report: EconomyReport) -> Self {
+        Self {
+            report,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn next(&mut self) {
+        let num_rows = self.report.arbitrage.len();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // `item` and `vendor` give the name of an item or vendor by id.
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        window: Window,
+        item: &dyn Fn(u32) -> String,
+        vendor: &dyn Fn(u32) -> String,
+    ) {
+        let theme = window.theme;
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(self.report.distribution.len() as u16 + 3),
+                Constraint::Fill(1),
+            ])
+            .split(area);
+        let block = |title: String| {
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green))
+        };
+
+        let distribution_rows = self.report.distribution.iter().map(|prices| {
+            Row::new(vec![
+                Cell::from(prices.rarity.to_string()).style(Style::default().fg(theme.blue)),
+                Cell::from(prices.offers.to_string()),
+                Cell::from(prices.min.to_string()),
+                Cell::from(prices.median.to_string()),
+                Cell::from(prices.max.to_string()),
+                Cell::from(format!("{:.1}", prices.average_payout))
+                    .style(Style::default().fg(theme.yellow)),
+            ])
+        });
+        let distribution = Table::new(distribution_rows, [Constraint::Fill(1); 6])
+            .header(
+                Row::new(vec![
+                    "Rarity",
+                    "Offers",
+                    "Min",
+                    "Median",
+                    "Max",
+                    "Avg Payout",
+                ])
+                .style(Style::default().fg(theme.green).bold()),
+            )
+            .block(block("Economy Report: Vendor Prices by Rarity".to_string()))
+            .bg(theme.black_dark)
+            .fg(theme.white);
+        Widget::render(distribution, sections[0], buf);
+
+        let arbitrage_rows = self.report.arbitrage.iter().map(|arbitrage| {
+            Row::new(vec![
+                Cell::from(item(arbitrage.item_id)).style(Style::default().fg(theme.blue)),
+                Cell::from(format!(
+                    "{} ({})",
+                    vendor(arbitrage.buy_from),
+                    arbitrage.buy_price
+                )),
+                Cell::from(format!(
+                    "{} ({})",
+                    vendor(arbitrage.sell_to),
+                    arbitrage.sell_price
+                )),
+                Cell::from(format!("+{}", arbitrage.profit()))
+                    .style(Style::default().fg(theme.red)),
+            ])
+        });
+        let arbitrage = Table::new(
+            arbitrage_rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Item", "Buy From", "Sell To", "Profit"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            block(format!("Arbitrage Loops ({})", self.report.arbitrage.len()))
+                .title_bottom("Esc to close"),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+        StatefulWidget::render(arbitrage, sections[1], buf, &mut self.state);
+    }
+}
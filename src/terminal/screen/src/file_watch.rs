@@ -0,0 +1,175 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::SystemTime;
+
+// When a file was last modified, and its size.
+type FileStamp = (Option<SystemTime>, u64);
+
+// Notices files and directories changing on disk, such as when they're edited
+// in another program or checked out by git. The system's file events wake the
+// watcher, and the modification times and sizes of the files under a path
+// decide whether it really changed, so the editor's own writes can be ignored.
+pub struct FileWatcher {
+    // `None` if the system can't watch files, in which case nothing is
+    // reported.
+    watcher: Option<RecommendedWatcher>,
+    events: Receiver<notify::Result<Event>>,
+    watched: Vec<WatchedPath>,
+}
+
+struct WatchedPath {
+    path: PathBuf,
+    // File events give absolute paths.
+    absolute: PathBuf,
+    stamps: BTreeMap<PathBuf, FileStamp>,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        let (sender, events) = channel();
+        Self {
+            watcher: notify::recommended_watcher(sender).ok(),
+            events,
+            watched: vec![],
+        }
+    }
+
+    // Starts watching a file, or every file in a directory and the ones under
+    // it. Paths that don't exist yet are watched for being created, through
+    // the directory they'd be created in.
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        let absolute = std::path::absolute(&path).unwrap_or(path.clone());
+        if let Some(watcher) = self.watcher.as_mut() {
+            if absolute.is_dir() {
+                let _ = watcher.watch(&absolute, RecursiveMode::Recursive);
+            } else if let Some(parent) = absolute.parent().filter(|parent| parent.is_dir()) {
+                let _ = watcher.watch(parent, RecursiveMode::NonRecursive);
+            }
+        }
+        self.watched.push(WatchedPath {
+            stamps: file_stamps(&path),
+            path,
+            absolute,
+        });
+    }
+
+    // Drains the file events received so far, returning the watched paths
+    // that changed since they were last checked or marked as seen.
+    pub fn changes(&mut self) -> Vec<PathBuf> {
+        let mut touched = vec![];
+        while let Ok(event) = self.events.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            for (i, watched) in self.watched.iter().enumerate() {
+                let absolute = &watched.absolute;
+                if event.paths.iter().any(|path| path.starts_with(absolute)) {
+                    touched.push(i);
+                }
+            }
+        }
+        let mut changed = vec![];
+        for (i, watched) in self.watched.iter_mut().enumerate() {
+            if !touched.contains(&i) {
+                continue;
+            }
+            let stamps = file_stamps(&watched.path);
+            if stamps != watched.stamps {
+                watched.stamps = stamps;
+                changed.push(watched.path.clone());
+            }
+        }
+        changed
+    }
+
+    // Takes the current state of a watched path as unchanged, such as after
+    // writing to it.
+    pub fn mark_seen(&mut self, path: impl AsRef<Path>) {
+        for watched in &mut self.watched {
+            if watched.path == path.as_ref() {
+                watched.stamps = file_stamps(&watched.path);
+            }
+        }
+    }
+}
+
+fn file_stamps(path: &Path) -> BTreeMap<PathBuf, FileStamp> {
+    let mut stamps = BTreeMap::new();
+    add_file_stamps(path, &mut stamps);
+    stamps
+}
+
+fn add_file_stamps(path: &Path, stamps: &mut BTreeMap<PathBuf, FileStamp>) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            add_file_stamps(&entry.path(), stamps);
+        }
+    } else {
+        stamps.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // The changes reported once the watcher has had time to see them.
+    fn wait_for_changes(watcher: &mut FileWatcher) -> Vec<PathBuf> {
+        let start = Instant::now();
+        let mut changed = vec![];
+        while start.elapsed() < Duration::from_millis(500) {
+            thread::sleep(Duration::from_millis(50));
+            changed.extend(watcher.changes());
+        }
+        changed
+    }
+
+    #[test]
+    fn file_watcher_reports_changes() {
+        let dir = std::env::temp_dir().join(format!("file_watch_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("icon")).unwrap();
+        // Canonical, as file events give the paths with links resolved.
+        let dir = dir.canonicalize().unwrap();
+        let lib = dir.join("item.ron");
+        fs::write(&lib, "(next_id: 0)").unwrap();
+        let mut watcher = FileWatcher::new();
+        watcher.watch(&lib);
+        watcher.watch(dir.join("icon"));
+        assert!(watcher.changes().is_empty());
+
+        fs::write(&lib, "(next_id: 10)").unwrap();
+        fs::write(dir.join("icon/shoe.png"), "").unwrap();
+        assert_eq!(
+            wait_for_changes(&mut watcher),
+            vec![lib.clone(), dir.join("icon")]
+        );
+
+        // Changes that were already seen aren't reported.
+        fs::write(&lib, "(next_id: 11)").unwrap();
+        watcher.mark_seen(&lib);
+        assert!(wait_for_changes(&mut watcher).is_empty());
+
+        fs::remove_file(&lib).unwrap();
+        assert_eq!(wait_for_changes(&mut watcher), vec![lib]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
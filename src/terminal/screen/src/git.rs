@@ -0,0 +1,213 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Runs git in the current directory, giving what it printed.
+fn git(args: &[&str]) -> Result<String, String> {
+    git_in(Path::new("."), args)
+}
+
+// Runs git in `dir`, giving what it printed.
+fn git_in(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|error| format!("git could not be run: {}", error))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|error| error.to_string())
+}
+
+// A commit's abbreviated hash, e.g. `efaf240` for `HEAD`.
+pub fn short_hash(revision: &str) -> Result<String, String> {
+    let commit = format!("{}^{{commit}}", revision);
+    git(&["rev-parse", "--short", "--verify", &commit]).map(|hash| hash.trim().to_string())
+}
+
+// Names a file or directory at a revision. Relative paths are relative to the
+// current directory, and absolute ones have to be in the repository.
+fn object_name(revision: &str, path: &str) -> Result<String, String> {
+    object_name_in(Path::new("."), revision, path)
+}
+
+// Names a file or directory at a revision of the repository `dir` is in, with
+// relative paths relative to `dir`.
+fn object_name_in(dir: &Path, revision: &str, path: &str) -> Result<String, String> {
+    if !Path::new(path).is_absolute() {
+        return Ok(format!("{}:./{}", revision, path));
+    }
+    let top = git_in(dir, &["rev-parse", "--show-toplevel"])?;
+    let top = Path::new(top.trim());
+    let path = Path::new(path);
+    let relative = path
+        .strip_prefix(top)
+        .ok()
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            let path = fs::canonicalize(path).ok()?;
+            Some(
+                path.strip_prefix(fs::canonicalize(top).ok()?)
+                    .ok()?
+                    .to_path_buf(),
+            )
+        })
+        .ok_or(format!("{} is not in the repository", path.display()))?;
+    Ok(format!("{}:{}", revision, relative.display()))
+}
+
+// The contents of a file at a revision, with its path given as `object_name`
+// takes it. Files that didn't exist at the revision are `None`.
+pub fn show_file(revision: &str, path: &str) -> Result<Option<String>, String> {
+    let object = object_name(revision, path)?;
+    if git(&["cat-file", "-e", &object]).is_err() {
+        return Ok(None);
+    }
+    git(&["show", &object]).map(Some)
+}
+
+// The names and contents of the files in a directory at a revision, not
+// counting subdirectories. Directories that didn't exist have no files.
+pub fn show_dir(revision: &str, dir: &str) -> Result<Vec<(String, String)>, String> {
+    let tree = object_name(revision, dir)?;
+    let Ok(names) = git(&["ls-tree", "--name-only", &tree]) else {
+        return Ok(vec![]);
+    };
+    let mut files = vec![];
+    for name in names.lines() {
+        if let Some(data) = show_file(revision, &format!("{}/{}", dir, name))? {
+            files.push((name.to_string(), data));
+        }
+    }
+    Ok(files)
+}
+
+// Our side and their side of the merge being resolved, or of the last commit
+// if it's a merge.
+pub fn merge_sides() -> Result<(String, String), String> {
+    if git(&["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_ok() {
+        return Ok(("HEAD".to_string(), "MERGE_HEAD".to_string()));
+    }
+    if git(&["rev-parse", "-q", "--verify", "HEAD^2"]).is_ok() {
+        return Ok(("HEAD^1".to_string(), "HEAD^2".to_string()));
+    }
+    Err("There's no merge in progress and the last commit isn't a merge".to_string())
+}
+
+// Registers `command` as a merge driver called `name` in the config of the
+// repository `dir` is in, and has git use it for files matching `patterns`
+// through the `.gitattributes` in `dir`, which the patterns are relative to.
+// Returns the lines added to `.gitattributes`.
+pub fn install_merge_driver(
+    name: &str,
+    description: &str,
+    command: &str,
+    dir: &Path,
+    patterns: &[String],
+) -> Result<Vec<String>, String> {
+    git_in(
+        dir,
+        &["config", &format!("merge.{}.name", name), description],
+    )?;
+    git_in(dir, &["config", &format!("merge.{}.driver", name), command])?;
+    let path = dir.join(".gitattributes");
+    let path = path.to_string_lossy();
+    let mut attributes = fs::read_to_string(path.as_ref()).unwrap_or_default();
+    let mut added = vec![];
+    for pattern in patterns {
+        let line = format!("{} merge={}", pattern, name);
+        if attributes.lines().any(|existing| existing.trim() == line) {
+            continue;
+        }
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(&line);
+        attributes.push('\n');
+        added.push(line);
+    }
+    if !added.is_empty() {
+        fs::write(path.as_ref(), attributes).map_err(|error| format!("{}: {}", path, error))?;
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty repository in a new temporary directory.
+    fn temp_repo(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        git_in(&dir, &["init", "-q"]).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn git_object_name() {
+        let dir = temp_repo("git_object_name");
+        fs::create_dir_all(dir.join("asset/def")).unwrap();
+        fs::write(dir.join("asset/def/item.ron"), "(next_id: 0)").unwrap();
+        git_in(&dir, &["add", "-A"]).unwrap();
+        let commit = [
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@test",
+            "commit",
+            "-q",
+            "-m",
+            "Add item lib",
+        ];
+        git_in(&dir, &commit).unwrap();
+
+        // Relative paths are left for git to resolve from the directory.
+        let relative = object_name_in(&dir, "HEAD", "asset/def/item.ron").unwrap();
+        assert_eq!(relative, "HEAD:./asset/def/item.ron");
+        let absolute = dir.join("asset/def/item.ron");
+        let absolute = object_name_in(&dir, "HEAD", absolute.to_str().unwrap()).unwrap();
+        assert_eq!(absolute, "HEAD:asset/def/item.ron");
+
+        // Paths through a link to the repository are resolved.
+        let link = dir.with_file_name(format!("git_object_name_link_{}", std::process::id()));
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        let linked = link.join("asset/def/item.ron");
+        let linked = object_name_in(&dir, "HEAD", linked.to_str().unwrap()).unwrap();
+        assert_eq!(linked, "HEAD:asset/def/item.ron");
+        for object in [relative, absolute, linked] {
+            assert_eq!(git_in(&dir, &["show", &object]).unwrap(), "(next_id: 0)");
+        }
+
+        let outside = std::env::temp_dir().join("item.ron");
+        assert!(object_name_in(&dir, "HEAD", outside.to_str().unwrap()).is_err());
+        let _ = fs::remove_file(&link);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn git_install_merge_driver() {
+        let dir = temp_repo("git_install_merge_driver");
+        fs::write(dir.join(".gitattributes"), "*.png binary\n*.ron merge=lib").unwrap();
+        let patterns = ["*.ron".to_string(), "*/lib.json".to_string()];
+        let added = install_merge_driver("lib", "Lib merge", "merge %O %A %B", &dir, &patterns);
+        assert_eq!(added, Ok(vec!["*/lib.json merge=lib".to_string()]));
+        assert_eq!(
+            git_in(&dir, &["config", "merge.lib.driver"]).unwrap(),
+            "merge %O %A %B\n"
+        );
+
+        // Installing again doesn't add the lines twice.
+        let added = install_merge_driver("lib", "Lib merge", "merge %O %A %B", &dir, &patterns);
+        assert_eq!(added, Ok(vec![]));
+        assert_eq!(
+            fs::read_to_string(dir.join(".gitattributes")).unwrap(),
+            "*.png binary\n*.ron merge=lib\n*/lib.json merge=lib\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,200 @@
+use std::cmp::min;
+
+use game_mechanic::prelude::*;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, List, ListItem, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+// An inventory save file, checked against the item lib. The stacks in it can
+// be changed a quantity at a time and saved back to the file.
+pub struct InventoryView {
+    pub path: String,
+    pub inventory: Inventory,
+    pub state: TableState,
+    // Whether the inventory has changes that haven't been saved.
+    pub changed: bool,
+}
+
+impl InventoryView {
+    pub fn open(path: &str) -> Result<Self, String> {
+        Ok(Self {
+            path: path.to_string(),
+            inventory: Inventory::load(path)?,
+            state: TableState::default().with_selected(0),
+            changed: false,
+        })
+    }
+
+    pub fn next(&mut self) {
+        let num_rows = self.inventory.slots.len();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // Adds one to the stack in the selected slot.
+    pub fn add_one(&mut self) {
+        if let Some(stack) = self.selected_stack() {
+            stack.quantity += 1;
+            self.changed = true;
+        }
+    }
+
+    // Takes one from the stack in the selected slot, emptying the slot when
+    // none are left.
+    pub fn take_one(&mut self) {
+        let Some(stack) = self.selected_stack() else {
+            return;
+        };
+        stack.quantity = stack.quantity.saturating_sub(1);
+        if stack.quantity == 0 {
+            let selected = self.state.selected().unwrap_or(0);
+            self.inventory.slots[selected] = None;
+        }
+        self.changed = true;
+    }
+
+    fn selected_stack(&mut self) -> Option<&mut ItemStack> {
+        let selected = self.state.selected()?;
+        self.inventory.slots.get_mut(selected)?.as_mut()
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        self.inventory.save(&self.path)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, window: Window, item_lib: &ItemLib) {
+        let theme = window.theme;
+        let issues = self.inventory.validate(item_lib);
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Fill(1),
+                Constraint::Length(self.inventory.equipment.len() as u16 + 3),
+                Constraint::Length(issues.len() as u16 + 2),
+            ])
+            .split(area);
+        let block = |title: String| {
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green))
+        };
+        let item = |id: u32| {
+            if item_lib.contains_id(id) {
+                item_lib.id(id).name.clone()
+            } else {
+                format!("Item {}", id)
+            }
+        };
+
+        let slot_rows = self.inventory.slots.iter().enumerate().map(|(i, slot)| {
+            let (name, quantity) = match slot {
+                Some(stack) => (item(stack.item_id), stack.quantity.to_string()),
+                None => ("—".to_string(), String::new()),
+            };
+            Row::new(vec![
+                Cell::from(i.to_string()),
+                Cell::from(name).style(Style::default().fg(theme.blue)),
+                Cell::from(quantity),
+            ])
+        });
+        let changed = if self.changed { " (not saved)" } else { "" };
+        let slots = Table::new(
+            slot_rows,
+            [
+                Constraint::Length(6),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Slot", "Item", "Quantity"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            block(format!("Inventory: {}{}", self.path, changed))
+                .title_bottom("Left/Right to change a stack, Enter to save, Esc to close"),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+        StatefulWidget::render(slots, sections[0], buf, &mut self.state);
+
+        let equipment_rows = self.inventory.equipment.iter().map(|(slot, item_id)| {
+            Row::new(vec![
+                Cell::from(slot.to_string()),
+                Cell::from(item(*item_id)).style(Style::default().fg(theme.blue)),
+            ])
+        });
+        let equipment = Table::new(equipment_rows, [Constraint::Fill(1), Constraint::Fill(2)])
+            .header(Row::new(vec!["Slot", "Item"]).style(Style::default().fg(theme.green).bold()))
+            .block(block("Equipment".to_string()))
+            .bg(theme.black_dark)
+            .fg(theme.white);
+        Widget::render(equipment, sections[1], buf);
+
+        let title = format!("Issues ({})", issues.len());
+        let issues: Vec<ListItem> = issues
+            .iter()
+            .map(|issue| ListItem::new(issue.to_string()).style(Style::default().fg(theme.red)))
+            .collect();
+        let issues = List::new(issues)
+            .block(block(title))
+            .bg(theme.black_dark)
+            .fg(theme.white);
+        Widget::render(issues, sections[2], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_system::asset::asset_lib::AssetLib;
+
+    #[test]
+    fn inventory_view_round_trip() {
+        let item_lib = ItemLib::new("../../../asset/def/item.ron");
+        let path = std::env::temp_dir().join(format!("inventory_view_{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::copy("../../../asset/save/inventory.ron", path).unwrap();
+        let mut view = InventoryView::open(path).unwrap();
+        assert_eq!(view.inventory.validate(&item_lib), vec![]);
+
+        view.add_one();
+        view.next();
+        view.take_one();
+        assert!(view.changed);
+        view.save().unwrap();
+        assert!(!view.changed);
+        let saved = InventoryView::open(path).unwrap().inventory;
+        assert_eq!(saved, view.inventory);
+        assert_eq!(saved.count(0), 13);
+        assert_eq!(saved.count(2), 39);
+
+        // Taking the last of a stack empties its slot.
+        for _ in 0..5 {
+            view.next();
+        }
+        view.previous();
+        view.previous();
+        for _ in 0..5 {
+            view.take_one();
+        }
+        assert_eq!(view.inventory.slots[3], None);
+        let _ = std::fs::remove_file(path);
+        assert!(InventoryView::open(path).is_err());
+    }
+}
@@ -1,2 +1,15 @@
+pub mod bulk_edit;
+pub mod changes;
+pub mod compare;
+pub mod csv_import;
 pub mod database;
+pub mod economy_report;
+pub mod file_watch;
+pub mod git;
+pub mod inventory;
+pub mod loot_simulation;
 pub mod menu;
+pub mod merge_prompt;
+pub mod project;
+pub mod recipe_graph;
+pub mod tooltip;
@@ -0,0 +1,120 @@
+use std::cmp::min;
+
+use game_mechanic::prelude::*;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+const MIN_ROLLS: u32 = 10;
+const MAX_ROLLS: u32 = 100_000;
+
+// A loot table rolled many times, one row per item that dropped.
+pub struct LootSimulationView<T> {
+    pub table: T,
+    pub rolls: u32,
+    pub seed: u64,
+    // The results for the current rolls and seed, once they have been run.
+    pub simulation: Option<LootSimulation>,
+    pub state: TableState,
+}
+
+impl<T> LootSimulationView<T> {
+    pub fn new(table: T) -> Self {
+        Self {
+            table,
+            rolls: 1000,
+            seed: 0,
+            simulation: None,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn more_rolls(&mut self) {
+        self.rolls = min(self.rolls * 10, MAX_ROLLS);
+        self.simulation = None;
+    }
+
+    pub fn fewer_rolls(&mut self) {
+        self.rolls = (self.rolls / 10).max(MIN_ROLLS);
+        self.simulation = None;
+    }
+
+    pub fn reseed(&mut self) {
+        self.seed += 1;
+        self.simulation = None;
+    }
+
+    pub fn next(&mut self) {
+        let num_rows = self.simulation.as_ref().map_or(0, |s| s.drops.len());
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // `item` gives the name and sell value of an item by id.
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        window: Window,
+        title: &str,
+        item: &dyn Fn(u32) -> (String, u32),
+    ) {
+        let theme = window.theme;
+        let Some(simulation) = &self.simulation else {
+            return;
+        };
+        let table_rows = simulation.drops.iter().map(|drop| {
+            let (name, sell_value) = item(drop.item_id);
+            let value = sell_value as f32 * drop.total_quantity as f32 / simulation.rolls as f32;
+            Row::new(vec![
+                Cell::from(name).style(Style::default().fg(theme.blue)),
+                Cell::from(format!("{:.1}%", drop.chance(simulation.rolls) * 100.0)),
+                Cell::from(format!("{:.2}", drop.average_quantity())),
+                Cell::from(format!("{:.2}", value)).style(Style::default().fg(theme.yellow)),
+            ])
+        });
+
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Item", "Drop Chance", "Avg Quantity", "Gold per Roll"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title(format!(
+                    "Loot Simulation: {} ({} rolls, seed {})",
+                    title, simulation.rolls, self.seed
+                ))
+                .title_bottom(format!(
+                    "Expected {:.2} gold per roll. ←/→ Fewer/more rolls, R to reseed, Esc to close",
+                    simulation.expected_value()
+                ))
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green)),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
@@ -189,7 +189,7 @@ fn render_menu(area: Rect, buf: &mut Buffer, menu_options: &mut MenuOptionList,
 }
 
 impl MenuOptionList<'_> {
-    fn with_menu_options(menu_options: Vec<&WindowName>) -> MenuOptionList {
+    fn with_menu_options(menu_options: Vec<&WindowName>) -> MenuOptionList<'_> {
         MenuOptionList {
             state: ListState::default(),
             menu_options: menu_options.iter().map(MenuOption::from).collect(),
@@ -216,7 +216,7 @@ impl<'a> From<&&'a WindowName> for MenuOption<'a> {
 }
 
 impl MenuOption<'_> {
-    fn to_list_item(&self) -> ListItem {
+    fn to_list_item(&self) -> ListItem<'_> {
         ListItem::new(self.menu_option.to_string())
     }
 }
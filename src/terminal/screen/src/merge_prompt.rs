@@ -0,0 +1,133 @@
+use std::cmp::min;
+
+use game_system::prelude::{merge_value_text, AssetType, LibMerge, MergeSide};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use serde_json::Value;
+use term_system::window::Window;
+
+// An asset lib that changed on disk while it had edits of its own, where both
+// changed the same fields. Each conflicting field keeps one side before the
+// changes are merged.
+pub struct MergePrompt {
+    pub asset_type: AssetType,
+    pub path: String,
+    pub merge: LibMerge,
+    // The lib as it is on disk, which the edits are merged into.
+    pub theirs: Value,
+    pub state: TableState,
+}
+
+impl MergePrompt {
+    pub fn new(asset_type: AssetType, path: String, merge: LibMerge, theirs: Value) -> Self {
+        Self {
+            asset_type,
+            path,
+            merge,
+            theirs,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn next(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(min(
+            selected + 1,
+            self.merge.conflicts.len().saturating_sub(1),
+        )));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // Keeps one side of the selected field.
+    pub fn take(&mut self, side: MergeSide) {
+        if let Some(conflict) = self
+            .state
+            .selected()
+            .and_then(|selected| self.merge.conflicts.get_mut(selected))
+        {
+            conflict.take = side;
+        }
+    }
+
+    // Keeps one side of every field.
+    pub fn take_all(&mut self, side: MergeSide) {
+        for conflict in &mut self.merge.conflicts {
+            conflict.take = side;
+        }
+    }
+
+    // Lists each conflicting field with its value before either change, and
+    // on each side. The side that will be kept is highlighted.
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer, window: Window) {
+        let theme = window.theme;
+        let side_style = |taken: bool| {
+            if taken {
+                Style::default().fg(theme.yellow_light).bold()
+            } else {
+                Style::default().fg(theme.white)
+            }
+        };
+        let rows = self.merge.conflicts.iter().map(|conflict| {
+            let asset = match conflict.id {
+                Some(id) => format!("{} {}", id, conflict.name),
+                None => "Lib".to_string(),
+            };
+            let path = if conflict.path.is_empty() {
+                "(whole def)".to_string()
+            } else {
+                conflict.path.clone()
+            };
+            Row::new(vec![
+                Cell::from(asset).style(Style::default().fg(theme.blue)),
+                Cell::from(path),
+                Cell::from(merge_value_text(conflict.base.as_ref()))
+                    .style(Style::default().fg(theme.white_dark)),
+                Cell::from(merge_value_text(conflict.ours.as_ref()))
+                    .style(side_style(conflict.take == MergeSide::Ours)),
+                Cell::from(merge_value_text(conflict.theirs.as_ref()))
+                    .style(side_style(conflict.take == MergeSide::Theirs)),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+            ],
+        )
+        .header(
+            Row::new(vec!["Asset", "Field", "Before", "Mine", "On disk"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title(format!(
+                    "{} changed on disk: {} fields changed on both sides",
+                    self.path,
+                    self.merge.conflicts.len()
+                ))
+                .title_bottom(
+                    "Left to keep mine, Right to take the one on disk, m/d for all, Enter to merge",
+                )
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.red)),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use game_system::prelude::{AssetFormat, AssetLayout, AssetStorage, AssetType};
+use serde::Deserialize;
+use term_system::keymap::{Action, Keymap};
+use term_system::theme::Theme;
+
+use crate::database::{lib_asset_type, lib_name};
+
+// The name of the project file, which is looked for in the current directory
+// and the ones above it.
+pub const PROJECT_FILE: &str = "asset-editor.toml";
+
+// Where an asset lib is stored, and how.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibConfig {
+    // The lib's path relative to the asset root, without an extension, e.g.
+    // `def/item`. In the directory layout, this is the lib's directory.
+    pub path: String,
+    pub storage: AssetStorage,
+}
+
+// Where a project keeps its assets, and how the editor is set up for it.
+// Every path is relative to the asset root, which is relative to the
+// directory the project file is in.
+#[derive(Clone, Debug)]
+pub struct Project {
+    // The directory with the project file, relative to the current directory
+    // when it was found by looking up from it. Empty without a project file.
+    pub dir: PathBuf,
+    pub asset_root: String,
+    // Each asset lib, by lib name.
+    pub libs: BTreeMap<&'static str, LibConfig>,
+    // The directory with the icons of every asset, which is watched for
+    // changes while editing.
+    pub sprite_dir: String,
+    // The icon shown for assets whose icon can't be loaded.
+    pub unknown_icon: String,
+    pub tooltip_template: String,
+    // Where CSV exports are written, and imported from.
+    pub csv_dir: String,
+    // The inventory save file opened from the editor.
+    pub inventory: String,
+    pub theme: Theme,
+    pub keymap: Keymap,
+}
+
+// A project file as it's written. Anything left out is the default.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProjectFile {
+    assets: AssetsSection,
+    libs: BTreeMap<String, LibSection>,
+    editor: EditorSection,
+    keymap: BTreeMap<String, String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AssetsSection {
+    root: Option<String>,
+    // The directory of the libs that don't have a path of their own.
+    defs: Option<String>,
+    // The format and layout of the libs that don't have their own.
+    format: Option<String>,
+    layout: Option<String>,
+    sprites: Option<String>,
+    unknown_icon: Option<String>,
+    tooltip_template: Option<String>,
+    csv: Option<String>,
+    inventory: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LibSection {
+    path: Option<String>,
+    format: Option<String>,
+    layout: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct EditorSection {
+    theme: Option<String>,
+}
+
+impl Default for Project {
+    // The layout of this repository: `asset/def/<lib>.ron`, `asset/sprite`,
+    // and so on, relative to the current directory.
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::new(),
+            asset_root: "asset".to_string(),
+            libs: AssetType::ALL
+                .into_iter()
+                .map(|asset_type| {
+                    let name = lib_name(asset_type);
+                    let lib = LibConfig {
+                        path: format!("def/{}", name),
+                        storage: AssetStorage::default(),
+                    };
+                    (name, lib)
+                })
+                .collect(),
+            sprite_dir: "sprite/icon".to_string(),
+            unknown_icon: "sprite/icon/unknown.png".to_string(),
+            tooltip_template: "template/tooltip.ron".to_string(),
+            csv_dir: "csv".to_string(),
+            inventory: "save/inventory.ron".to_string(),
+            theme: Theme::AMBER,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Project {
+    // Opens the project file in the current directory or the closest one
+    // above it, or gives the default project if there isn't one.
+    pub fn find() -> Result<Self, String> {
+        let current_dir = std::env::current_dir().map_err(|error| error.to_string())?;
+        let mut dir = PathBuf::new();
+        for ancestor in current_dir.ancestors() {
+            if ancestor.join(PROJECT_FILE).is_file() {
+                return Self::open(dir.join(PROJECT_FILE));
+            }
+            dir.push("..");
+        }
+        Ok(Self::default())
+    }
+
+    // Opens a project file. Its paths are relative to the directory it's in.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data =
+            fs::read_to_string(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::parse(dir, &data).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    // Reads the contents of a project file in `dir`.
+    pub fn parse(dir: PathBuf, data: &str) -> Result<Self, String> {
+        let file: ProjectFile = toml::from_str(data).map_err(|error| error.to_string())?;
+        let mut project = Self {
+            dir,
+            ..Self::default()
+        };
+        let assets = file.assets;
+        let storage = AssetStorage {
+            format: parse_format(assets.format, AssetFormat::default())?,
+            layout: parse_layout(assets.layout, AssetLayout::default())?,
+        };
+        let defs = assets.defs.unwrap_or("def".to_string());
+        for (name, lib) in project.libs.iter_mut() {
+            *lib = LibConfig {
+                path: format!("{}/{}", defs, name),
+                storage,
+            };
+        }
+        for (name, section) in file.libs {
+            let lib = project
+                .libs
+                .get_mut(name.as_str())
+                .ok_or(format!("Unknown asset lib '{}'", name))?;
+            lib.path = section.path.unwrap_or(lib.path.clone());
+            lib.storage = AssetStorage {
+                format: parse_format(section.format, lib.storage.format)?,
+                layout: parse_layout(section.layout, lib.storage.layout)?,
+            };
+        }
+        project.asset_root = assets.root.unwrap_or(project.asset_root);
+        project.sprite_dir = assets.sprites.unwrap_or(project.sprite_dir);
+        project.unknown_icon = assets.unknown_icon.unwrap_or(project.unknown_icon);
+        project.tooltip_template = assets.tooltip_template.unwrap_or(project.tooltip_template);
+        project.csv_dir = assets.csv.unwrap_or(project.csv_dir);
+        project.inventory = assets.inventory.unwrap_or(project.inventory);
+        if let Some(theme) = file.editor.theme {
+            project.theme = Theme::named(&theme).ok_or(format!(
+                "Unknown theme '{}', expected one of: {}",
+                theme,
+                Theme::ALL.map(|(name, _)| name).join(", ")
+            ))?;
+        }
+        for (action, key) in file.keymap {
+            let action = action
+                .parse::<Action>()
+                .map_err(|_| format!("Unknown keymap action '{}'", action))?;
+            project.keymap.bind(action, key.parse()?);
+        }
+        Ok(project)
+    }
+
+    // Stores every asset lib in the format or layout, such as to convert
+    // them.
+    pub fn set_storage(&mut self, format: Option<AssetFormat>, layout: Option<AssetLayout>) {
+        for lib in self.libs.values_mut() {
+            lib.storage.format = format.unwrap_or(lib.storage.format);
+            lib.storage.layout = layout.unwrap_or(lib.storage.layout);
+        }
+    }
+
+    // A path relative to the asset root, from the current directory.
+    pub fn asset_path(&self, path: &str) -> String {
+        self.dir
+            .join(&self.asset_root)
+            .join(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn lib_storage(&self, name: &str) -> AssetStorage {
+        self.libs[name].storage
+    }
+
+    // The file the asset lib with the name is opened from. Directory libs are
+    // opened by their lib file.
+    pub fn lib_path(&self, name: &str) -> String {
+        let lib = &self.libs[name];
+        let path = self.dir.join(&self.asset_root).join(&lib.path);
+        lib_file(&path, lib.storage)
+    }
+
+    pub fn csv_path(&self, asset_type: AssetType) -> String {
+        self.asset_path(&format!("{}/{}.csv", self.csv_dir, lib_name(asset_type)))
+    }
+
+    // The asset type of the lib stored at a path, such as one git gives its
+    // merge driver. Paths are matched against where each lib is stored in the
+    // project, in either layout, and otherwise the lib is found by name.
+    pub fn lib_asset_type(&self, path: &str) -> Result<AssetType, String> {
+        let given = Path::new(path);
+        for asset_type in AssetType::ALL {
+            let lib = &self.libs[lib_name(asset_type)];
+            let path = Path::new(&self.asset_root).join(&lib.path);
+            let matches = AssetLayout::ALL.into_iter().any(|layout| {
+                let storage = AssetStorage {
+                    format: lib.storage.format,
+                    layout,
+                };
+                given.ends_with(without_leading_dirs(&lib_file(&path, storage)))
+            });
+            if matches {
+                return Ok(asset_type);
+            }
+        }
+        lib_asset_type(path)
+    }
+
+    // Git attribute patterns, relative to the project directory, for the
+    // files of each lib in either layout.
+    pub fn lib_patterns(&self) -> Vec<String> {
+        let mut patterns = vec![];
+        for lib in self.libs.values() {
+            let path = Path::new(&self.asset_root).join(&lib.path);
+            for layout in AssetLayout::ALL {
+                let storage = AssetStorage {
+                    format: lib.storage.format,
+                    layout,
+                };
+                let pattern = without_leading_dirs(&lib_file(&path, storage));
+                patterns.push(pattern.to_string_lossy().into_owned());
+            }
+        }
+        patterns
+    }
+}
+
+// The file a lib stored at `path`, without an extension, is opened from.
+fn lib_file(path: &Path, storage: AssetStorage) -> String {
+    let dir = path.parent().unwrap_or(Path::new("")).to_string_lossy();
+    let dir = if dir.is_empty() { "." } else { &dir };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    storage.lib_path(dir, &name)
+}
+
+// A path without the `.` and `..` it starts with, which aren't part of the
+// paths git and other programs give.
+fn without_leading_dirs(path: &str) -> PathBuf {
+    Path::new(path)
+        .components()
+        .skip_while(|component| matches!(component, Component::CurDir | Component::ParentDir))
+        .collect()
+}
+
+fn parse_format(value: Option<String>, default: AssetFormat) -> Result<AssetFormat, String> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Unknown format '{}'", value)),
+        None => Ok(default),
+    }
+}
+
+fn parse_layout(value: Option<String>, default: AssetLayout) -> Result<AssetLayout, String> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Unknown layout '{}'", value)),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn default_project_paths() {
+        let project = Project::default();
+        assert_eq!(project.lib_path("item"), "asset/def/item.ron");
+        assert_eq!(
+            project.asset_path("sprite/icon/shoe.png"),
+            "asset/sprite/icon/shoe.png"
+        );
+        assert_eq!(
+            project.csv_path(AssetType::LootTable),
+            "asset/csv/loot_table.csv"
+        );
+        assert_eq!(
+            project.lib_asset_type("asset/def/loot_table/lib.ron"),
+            Ok(AssetType::LootTable)
+        );
+    }
+
+    #[test]
+    fn example_project_file_is_the_default() {
+        let data = include_str!("../../../../asset-editor.toml");
+        let project = Project::parse(PathBuf::new(), data).unwrap();
+        let default = Project::default();
+        assert_eq!(project.libs, default.libs);
+        assert_eq!(project.sprite_dir, default.sprite_dir);
+        assert_eq!(project.inventory, default.inventory);
+        assert_eq!(project.keymap, default.keymap);
+    }
+
+    #[test]
+    fn parse_project_file() {
+        let data = r#"
+            [assets]
+            root = "content"
+            format = "json"
+            sprites = "art"
+
+            [libs.item]
+            path = "items/all"
+            format = "yaml"
+            layout = "directory"
+
+            [editor]
+            theme = "Amber"
+
+            [keymap]
+            changes = "ctrl+g"
+        "#;
+        let project = Project::parse(PathBuf::from("../.."), data).unwrap();
+        assert_eq!(project.lib_path("aura"), "../../content/def/aura.json");
+        assert_eq!(project.lib_path("item"), "../../content/items/all/lib.yaml");
+        assert_eq!(project.sprite_dir, "art");
+        assert_eq!(
+            project
+                .keymap
+                .action(KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL)),
+            Some(Action::Changes)
+        );
+        // Git gives paths relative to the top of the repository.
+        assert_eq!(
+            project.lib_asset_type("content/items/all/lib.yaml"),
+            Ok(AssetType::Item)
+        );
+        assert_eq!(
+            project.lib_asset_type("content/items/all.yaml"),
+            Ok(AssetType::Item)
+        );
+        assert!(project
+            .lib_patterns()
+            .contains(&"content/items/all.yaml".to_string()));
+
+        assert!(Project::parse(PathBuf::new(), "[libs.weapon]").is_err());
+        assert!(Project::parse(PathBuf::new(), "[editor]\ntheme = \"neon\"").is_err());
+        assert!(Project::parse(PathBuf::new(), "[assets]\nformat = \"xml\"").is_err());
+        assert!(Project::parse(PathBuf::new(), "[keymap]\nfly = \"f\"").is_err());
+        assert!(Project::parse(PathBuf::new(), "[asset]\nroot = \"a\"").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use std::cmp::min;
+
+use game_mechanic::prelude::*;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+use term_system::window::Window;
+
+// Every crafted item with the raw materials it takes and its margin, and any
+// items that are crafted from each other.
+pub struct RecipeGraphView {
+    pub graph: RecipeGraph,
+    pub state: TableState,
+}
+
+impl RecipeGraphView {
+    pub fn new(graph: RecipeGraph) -> Self {
+        Self {
+            graph,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn next(&mut self) {
+        let num_rows = self.graph.costs.len();
+        let selected = self.state.selected().unwrap_or(0);
+        self.state
+            .select(Some(min(selected + 1, num_rows.saturating_sub(1))));
+    }
+
+    pub fn previous(&mut self) {
+        let selected = self.state.selected().unwrap_or(0);
+        self.state.select(Some(selected.saturating_sub(1)));
+    }
+
+    // `item` and `recipe` give the name of an item or recipe by id.
+    pub fn render(
+        &mut self,
+        area: Rect,
+        buf: &mut Buffer,
+        window: Window,
+        item: &dyn Fn(u32) -> String,
+        recipe: &dyn Fn(u32) -> String,
+    ) {
+        let theme = window.theme;
+        let table_rows = self.graph.costs.iter().map(|(item_id, cost)| {
+            let name = Cell::from(item(*item_id)).style(Style::default().fg(theme.blue));
+            let Some(cost) = cost else {
+                return Row::new(vec![
+                    name,
+                    Cell::from("—"),
+                    Cell::from("In a cycle or missing an item")
+                        .style(Style::default().fg(theme.red)),
+                ]);
+            };
+            let materials = cost
+                .raw_materials
+                .iter()
+                .map(|(material, count)| format!("{}x {}", count, item(*material)))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let margin_color = if cost.margin < 0.0 {
+                theme.red
+            } else {
+                theme.green_light
+            };
+            Row::new(vec![
+                name,
+                Cell::from(recipe(cost.recipe_id)),
+                Cell::from(materials),
+                Cell::from(format!("{:.1}", cost.cost)).style(Style::default().fg(theme.yellow)),
+                Cell::from(format!("{:+.1}", cost.margin)).style(Style::default().fg(margin_color)),
+            ])
+        });
+
+        let cycles = if self.graph.cycles.is_empty() {
+            "No cycles".to_string()
+        } else {
+            let cycles = self
+                .graph
+                .cycles
+                .iter()
+                .map(|cycle| {
+                    cycle
+                        .iter()
+                        .chain(&cycle[..1])
+                        .map(|id| item(*id))
+                        .collect::<Vec<String>>()
+                        .join(" → ")
+                })
+                .collect::<Vec<String>>()
+                .join("; ");
+            format!("Cycles: {}", cycles)
+        };
+        let table = Table::new(
+            table_rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(4),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            Row::new(vec!["Item", "Recipe", "Raw Materials", "Cost", "Margin"])
+                .style(Style::default().fg(theme.green).bold()),
+        )
+        .block(
+            Block::default()
+                .title("Recipe Graph")
+                .title_bottom(format!("{}. Esc to close", cycles))
+                .borders(Borders::ALL)
+                .border_type(window.border_type)
+                .style(Style::default().fg(theme.white))
+                .title_style(Style::default().fg(theme.green)),
+        )
+        .bg(theme.black_dark)
+        .fg(theme.white)
+        .row_highlight_style(Style::default().bg(theme.black_light))
+        .highlight_symbol("> ");
+
+        StatefulWidget::render(table, area, buf, &mut self.state);
+    }
+}
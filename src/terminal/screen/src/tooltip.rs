@@ -0,0 +1,215 @@
+use std::fs::File;
+use std::io::Read;
+
+use bevy_reflect::{GetPath, Reflect};
+use game_mechanic::prelude::format_duration;
+use game_system::prelude::AssetType;
+use serde::Deserialize;
+
+use crate::database::get_string_value_from_path;
+
+// Describes how the tooltip preview of each asset type is laid out, so it can
+// be made to match the game's UI. See `asset/template/tooltip.ron`.
+#[derive(Debug, Deserialize)]
+pub struct TooltipTemplate {
+    // How many rows the icon at the top of the tooltip takes up.
+    pub icon_height: u16,
+    pub ability: Vec<TooltipLine>,
+    pub item: Vec<TooltipLine>,
+    pub loot_table: Vec<TooltipLine>,
+    pub aura: Vec<TooltipLine>,
+    pub character: Vec<TooltipLine>,
+    pub recipe: Vec<TooltipLine>,
+    pub vendor: Vec<TooltipLine>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TooltipLine {
+    pub text: String,
+    pub style: TooltipStyle,
+    #[serde(default)]
+    pub align: TooltipAlign,
+    #[serde(default)]
+    pub hide_if_empty: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Deserialize)]
+pub enum TooltipStyle {
+    Normal,
+    Muted,
+    // Colored by the item's rarity.
+    Rarity,
+    // Colored by the aura's type.
+    AuraType,
+    // Rendered as rules text markup.
+    Rules,
+    Flavor,
+    Value,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default, Deserialize)]
+pub enum TooltipAlign {
+    #[default]
+    Left,
+    Center,
+}
+
+impl TooltipTemplate {
+    pub fn new(path: &str) -> Self {
+        let mut file = File::open(path).unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        ron::from_str(&data).expect("RON was not well-formatted")
+    }
+
+    // The lines of the tooltip for a def, with every placeholder filled in.
+    pub fn build(&self, asset_type: AssetType, def: &dyn Reflect) -> Vec<(String, &TooltipLine)> {
+        let lines = match asset_type {
+            AssetType::Ability => &self.ability,
+            AssetType::Aura => &self.aura,
+            AssetType::Character => &self.character,
+            AssetType::Item => &self.item,
+            AssetType::LootTable => &self.loot_table,
+            AssetType::Recipe => &self.recipe,
+            AssetType::Vendor => &self.vendor,
+        };
+        lines
+            .iter()
+            .filter_map(|line| {
+                let (text, has_empty_value) = fill_placeholders(&line.text, def);
+                if line.hide_if_empty && has_empty_value {
+                    None
+                } else {
+                    Some((text, line))
+                }
+            })
+            .collect()
+    }
+}
+
+// Replaces each `{path}` or `{path|format}` in `text` with the def's value.
+// Placeholders for fields the def doesn't have are left as they are, so they
+// show up in the preview. Also returns whether any of the values were empty.
+fn fill_placeholders(text: &str, def: &dyn Reflect) -> (String, bool) {
+    let mut filled = String::new();
+    let mut has_empty_value = false;
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(length) = rest[start..].find('}') else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..start + length];
+        let (path, format) = placeholder.split_once('|').unwrap_or((placeholder, ""));
+        rest = &rest[start + length + 1..];
+        if def.reflect_path(path.trim()).is_err() {
+            filled.push_str(&format!("{{{}}}", placeholder));
+            continue;
+        }
+        let value = get_string_value_from_path(def, path.trim());
+        if value.is_empty() || value == "None" || value == "0" {
+            has_empty_value = true;
+        }
+        match format.trim() {
+            "time" => match value.parse::<f32>() {
+                Ok(seconds) => filled.push_str(&format_duration(seconds)),
+                Err(_) => filled.push_str(&value),
+            },
+            _ => filled.push_str(&value),
+        }
+    }
+    filled.push_str(rest);
+    (filled, has_empty_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_mechanic::prelude::*;
+    use game_system::prelude::AssetLib;
+
+    fn item_lib() -> ItemLib {
+        ItemLib::new(&format!("{}/test/data/test_item.ron", MECHANIC_TEST_DIR))
+    }
+
+    #[test]
+    fn tooltip_fill_placeholders() {
+        let item_lib = item_lib();
+        let shoe = item_lib.id(1);
+        assert_eq!(
+            fill_placeholders("{name}: {equipment_def.armor} Armor", shoe),
+            ("Shoe: 5 Armor".to_string(), false)
+        );
+        assert_eq!(
+            fill_placeholders("{ item_rarity } {equipment_def.slot}", shoe),
+            ("Uncommon Feet".to_string(), false)
+        );
+        let aura_lib = AuraLib::new(&format!("{}/test/data/test_aura.ron", MECHANIC_TEST_DIR));
+        assert_eq!(
+            fill_placeholders("Lasts {duration|time}, or {duration}", aura_lib.id(0)),
+            ("Lasts 1h, or 3600".to_string(), false)
+        );
+
+        // The potion has no slot or armor.
+        let potion = item_lib.id(0);
+        assert_eq!(
+            fill_placeholders("{equipment_def.slot}", potion),
+            ("None".to_string(), true)
+        );
+        assert_eq!(
+            fill_placeholders("{equipment_def.armor} Armor", potion),
+            ("0 Armor".to_string(), true)
+        );
+        assert_eq!(
+            fill_placeholders("{rules_text}", potion),
+            (String::new(), true)
+        );
+    }
+
+    #[test]
+    fn tooltip_unknown_placeholders() {
+        let item_lib = item_lib();
+        let shoe = item_lib.id(1);
+        // Fields the def doesn't have are left in, and aren't empty.
+        assert_eq!(
+            fill_placeholders("{name} {armour}", shoe),
+            ("Shoe {armour}".to_string(), false)
+        );
+        assert_eq!(
+            fill_placeholders("{equipment_def.armor|weight}", shoe),
+            ("5".to_string(), false)
+        );
+        // An unclosed brace isn't a placeholder.
+        assert_eq!(
+            fill_placeholders("{name} {name", shoe),
+            ("Shoe {name".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn tooltip_build_hides_empty_lines() {
+        let mut template: TooltipTemplate = ron::from_str(
+            "(icon_height: 1, ability: [], item: [], loot_table: [], aura: [], character: [],
+              recipe: [], vendor: [])",
+        )
+        .unwrap();
+        template.item = ron::from_str(
+            r#"[
+                (text: "{name}", style: Rarity),
+                (text: "{equipment_def.armor} Armor", style: Normal, hide_if_empty: true),
+                (text: "Sell: {sell_value}", style: Value),
+            ]"#,
+        )
+        .unwrap();
+        let item_lib = item_lib();
+        let text = |id| -> Vec<String> {
+            template
+                .build(AssetType::Item, item_lib.id(id))
+                .into_iter()
+                .map(|(text, _)| text)
+                .collect()
+        };
+        assert_eq!(text(0), vec!["Red Potion", "Sell: 5"]);
+        assert_eq!(text(1), vec!["Shoe", "5 Armor", "Sell: 100"]);
+    }
+}
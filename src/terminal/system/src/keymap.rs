@@ -0,0 +1,294 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+
+// Something a hotkey does. Navigation (the arrow keys, Tab, Enter and Esc)
+// isn't remappable.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Action {
+    TooltipPreview,
+    Mark,
+    Compare,
+    Select,
+    SelectAll,
+    BulkEdit,
+    Simulate,
+    EconomyReport,
+    RecipeGraph,
+    Inventory,
+    Changes,
+    ExportCsv,
+    ImportCsv,
+    AddListEntry,
+    RemoveListEntry,
+}
+
+// A key, with the modifiers held down with it, e.g. `ctrl+p`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+// The key bound to each action.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Keymap {
+    bindings: [KeyBinding; Action::ALL.len()],
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::TooltipPreview,
+        Action::Mark,
+        Action::Compare,
+        Action::Select,
+        Action::SelectAll,
+        Action::BulkEdit,
+        Action::Simulate,
+        Action::EconomyReport,
+        Action::RecipeGraph,
+        Action::Inventory,
+        Action::Changes,
+        Action::ExportCsv,
+        Action::ImportCsv,
+        Action::AddListEntry,
+        Action::RemoveListEntry,
+    ];
+
+    // The name used for the action in project files, e.g. `tooltip_preview`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::TooltipPreview => "tooltip_preview",
+            Action::Mark => "mark",
+            Action::Compare => "compare",
+            Action::Select => "select",
+            Action::SelectAll => "select_all",
+            Action::BulkEdit => "bulk_edit",
+            Action::Simulate => "simulate",
+            Action::EconomyReport => "economy_report",
+            Action::RecipeGraph => "recipe_graph",
+            Action::Inventory => "inventory",
+            Action::Changes => "changes",
+            Action::ExportCsv => "export_csv",
+            Action::ImportCsv => "import_csv",
+            Action::AddListEntry => "add_list_entry",
+            Action::RemoveListEntry => "remove_list_entry",
+        }
+    }
+
+    fn default_binding(&self) -> KeyBinding {
+        let (key, modifiers) = match self {
+            Action::TooltipPreview => ('p', KeyModifiers::CONTROL),
+            Action::Mark => ('m', KeyModifiers::NONE),
+            Action::Compare => ('c', KeyModifiers::NONE),
+            Action::Select => (' ', KeyModifiers::NONE),
+            Action::SelectAll => ('a', KeyModifiers::NONE),
+            Action::BulkEdit => ('b', KeyModifiers::NONE),
+            Action::Simulate => ('s', KeyModifiers::NONE),
+            Action::EconomyReport => ('e', KeyModifiers::NONE),
+            Action::RecipeGraph => ('g', KeyModifiers::NONE),
+            Action::Inventory => ('v', KeyModifiers::NONE),
+            Action::Changes => ('d', KeyModifiers::NONE),
+            Action::ExportCsv => ('x', KeyModifiers::NONE),
+            Action::ImportCsv => ('i', KeyModifiers::NONE),
+            Action::AddListEntry => ('n', KeyModifiers::CONTROL),
+            Action::RemoveListEntry => ('d', KeyModifiers::CONTROL),
+        };
+        KeyBinding {
+            code: KeyCode::Char(key),
+            modifiers,
+        }
+    }
+
+    fn index(&self) -> usize {
+        Action::ALL
+            .iter()
+            .position(|action| action == self)
+            .unwrap()
+    }
+}
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Action, Self::Err> {
+        let input = input.to_lowercase().replace('-', "_");
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == input)
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl KeyBinding {
+    // Whether the key was pressed with exactly the binding's modifiers.
+    // Bindings without modifiers also take Shift, which is part of typing
+    // capital letters.
+    pub fn matches(&self, key_event: KeyEvent) -> bool {
+        let modifiers = if self.modifiers.is_empty() {
+            key_event.modifiers - KeyModifiers::SHIFT
+        } else {
+            key_event.modifiers
+        };
+        key_event.code == self.code && modifiers == self.modifiers
+    }
+}
+
+// Parses a key, optionally after modifiers joined by `+`: a character, e.g.
+// `m`, or a named key, e.g. `space`, `f2` or `ctrl+p`.
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<KeyBinding, Self::Err> {
+        let (modifier_names, key) = match input.rsplit_once('+') {
+            // A `+` on its own, or as the key after modifiers, e.g. `ctrl++`.
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", input),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier '{}' in '{}'", name, input)),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => return Err(format!("Unknown key '{}'", input)),
+                },
+            },
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{}", format!("{:?}", code).to_lowercase()),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn binding(&self, action: Action) -> KeyBinding {
+        self.bindings[action.index()]
+    }
+
+    pub fn bind(&mut self, action: Action, binding: KeyBinding) {
+        self.bindings[action.index()] = binding;
+    }
+
+    // The action bound to a key press, if any. Keys bound to more than one
+    // action do the first.
+    pub fn action(&self, key_event: KeyEvent) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.binding(*action).matches(key_event))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap {
+            bindings: Action::ALL.map(|action| action.default_binding()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn keymap_actions() {
+        let mut keymap = Keymap::default();
+        let d = press(KeyCode::Char('d'), KeyModifiers::NONE);
+        let ctrl_d = press(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(d), Some(Action::Changes));
+        assert_eq!(keymap.action(ctrl_d), Some(Action::RemoveListEntry));
+        assert_eq!(
+            keymap.action(press(KeyCode::Char('p'), KeyModifiers::NONE)),
+            None
+        );
+
+        keymap.bind(Action::Changes, "F5".parse().unwrap());
+        assert_eq!(keymap.action(d), None);
+        assert_eq!(
+            keymap.action(press(KeyCode::F(5), KeyModifiers::NONE)),
+            Some(Action::Changes)
+        );
+        keymap.bind(Action::Mark, "M".parse().unwrap());
+        assert_eq!(
+            keymap.action(press(KeyCode::Char('M'), KeyModifiers::SHIFT)),
+            Some(Action::Mark)
+        );
+    }
+
+    #[test]
+    fn key_binding_from_str() {
+        let binding = |code, modifiers| KeyBinding { code, modifiers };
+        assert_eq!(
+            "ctrl+p".parse(),
+            Ok(binding(KeyCode::Char('p'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "Ctrl+Alt+space".parse(),
+            Ok(binding(
+                KeyCode::Char(' '),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            ))
+        );
+        assert_eq!(
+            "ctrl++".parse(),
+            Ok(binding(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "+".parse(),
+            Ok(binding(KeyCode::Char('+'), KeyModifiers::NONE))
+        );
+        assert!("hyper+p".parse::<KeyBinding>().is_err());
+        assert!("escape key".parse::<KeyBinding>().is_err());
+        for input in ["ctrl+p", "space", "alt+f2", "home", "x"] {
+            assert_eq!(input.parse::<KeyBinding>().unwrap().to_string(), input);
+        }
+        assert_eq!("Select-All".parse(), Ok(Action::SelectAll));
+    }
+}
@@ -1,4 +1,6 @@
+pub mod keymap;
 pub mod terminal_image;
+pub mod text_input;
 pub mod theme;
 pub mod tui;
 pub mod window;
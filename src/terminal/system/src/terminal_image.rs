@@ -3,7 +3,9 @@ use image::{imageops::FilterType::Nearest, DynamicImage, GenericImageView};
 
 use ratatui::{prelude::*, widgets::BorderType};
 
-pub const UNKNOWN_IMAGE_PATH: &str = "asset/sprite/icon/unknown.png";
+// The width and height of the blank image shown when there's no image for
+// unknown images.
+const UNKNOWN_IMAGE_SIZE: u32 = 16;
 
 // Renders an image to the screen. Two pixels exist in a single space, where the ▀
 // character represents the top pixel and the background color represents the bottom pixel
@@ -143,9 +145,14 @@ pub fn render_image(area: Rect, buf: &mut Buffer, img: DynamicImage, theme: Them
     render(area.left(), area.top(), buf, img, theme);
 }
 
-pub fn render_image_path(area: Rect, buf: &mut Buffer, image_path: &str, theme: Theme) {
-    let mut img = image::open(image_path).unwrap_or(image::open(UNKNOWN_IMAGE_PATH).unwrap());
-    img = resize_image(area, img);
+pub fn render_image_path(
+    area: Rect,
+    buf: &mut Buffer,
+    image_path: &str,
+    unknown_image_path: &str,
+    theme: Theme,
+) {
+    let img = resize_image(area, load_image(image_path, unknown_image_path));
     render(area.left(), area.top(), buf, img, theme);
 }
 
@@ -153,15 +160,20 @@ pub fn render_image_path_with_border(
     area: Rect,
     buf: &mut Buffer,
     image_path: &str,
+    unknown_image_path: &str,
     theme: Theme,
     border: BorderType,
 ) {
-    let img = load_image(image_path);
+    let img = load_image(image_path, unknown_image_path);
     render_image_with_border(area, buf, img, theme, border);
 }
 
-pub fn load_image(image_path: &str) -> DynamicImage {
-    image::open(image_path).unwrap_or(image::open(UNKNOWN_IMAGE_PATH).unwrap())
+// Loads an image, or the one shown for unknown images if it can't be. When
+// neither can, such as outside of a project, the image is blank.
+pub fn load_image(image_path: &str, unknown_image_path: &str) -> DynamicImage {
+    image::open(image_path)
+        .or_else(|_| image::open(unknown_image_path))
+        .unwrap_or_else(|_| DynamicImage::new_rgb8(UNKNOWN_IMAGE_SIZE, UNKNOWN_IMAGE_SIZE))
 }
 
 // Overwrites the background color of an entire area to the `color`
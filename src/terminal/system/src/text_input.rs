@@ -0,0 +1,497 @@
+use std::ops::Range;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// An editable line of text. All cursor positions are byte offsets into `text`
+// that always sit on a grapheme boundary, so multi-byte characters are never split.
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    text: String,
+    // Where the cursor currently is.
+    cursor: usize,
+    // Where the selection started, if there is one. The selection spans from
+    // the anchor to the cursor.
+    anchor: Option<usize>,
+    // Whether the text can contain line breaks.
+    multi_line: bool,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // An input that keeps line breaks and inserts one when Enter is pressed.
+    pub fn multi_line() -> Self {
+        Self {
+            multi_line: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_multi_line(&self) -> bool {
+        self.multi_line
+    }
+
+    pub fn set_multi_line(&mut self, multi_line: bool) {
+        self.multi_line = multi_line;
+        self.set_text(&self.text.clone());
+    }
+
+    pub fn with_text(text: &str) -> Self {
+        let mut input = Self::new();
+        input.set_text(text);
+        input
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    // Replaces the contents and moves the cursor to the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = sanitize(text, self.multi_line);
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // The number of user visible characters.
+    pub fn char_count(&self) -> usize {
+        self.text.graphemes(true).filter(|g| *g != "\n").count()
+    }
+
+    // The number of lines, counting line breaks but not wrapping.
+    pub fn line_count(&self) -> usize {
+        self.text.split('\n').count()
+    }
+
+    // The selected byte range, ordered from start to end.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        if anchor == self.cursor {
+            return None;
+        }
+        Some(anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|range| &self.text[range])
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    // Inserts text at the cursor, replacing the selection if there is one.
+    pub fn insert_str(&mut self, to_insert: &str) {
+        self.delete_selection();
+        let to_insert = sanitize(to_insert, self.multi_line);
+        self.text.insert_str(self.cursor, &to_insert);
+        self.cursor += to_insert.len();
+    }
+
+    pub fn insert_char(&mut self, to_insert: char) {
+        self.insert_str(to_insert.encode_utf8(&mut [0; 4]));
+    }
+
+    // Pasted text is inserted as-is, replacing any selection.
+    pub fn paste(&mut self, text: &str) {
+        self.insert_str(text);
+    }
+
+    pub fn backspace(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let start = self.previous_boundary(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn delete(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let end = self.next_boundary(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        let target = match (select, self.selection()) {
+            (false, Some(range)) => range.start,
+            _ => self.previous_boundary(self.cursor),
+        };
+        self.move_to(target, select);
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        let target = match (select, self.selection()) {
+            (false, Some(range)) => range.end,
+            _ => self.next_boundary(self.cursor),
+        };
+        self.move_to(target, select);
+    }
+
+    // Moves to the start of the current word, or the previous one if already
+    // at the start of a word.
+    pub fn move_word_left(&mut self, select: bool) {
+        let target = self
+            .text
+            .unicode_word_indices()
+            .map(|(index, _)| index)
+            .take_while(|index| *index < self.cursor)
+            .last()
+            .unwrap_or(0);
+        self.move_to(target, select);
+    }
+
+    // Moves to the end of the current word, or the next one if already at the
+    // end of a word.
+    pub fn move_word_right(&mut self, select: bool) {
+        let target = self
+            .text
+            .unicode_word_indices()
+            .map(|(index, word)| index + word.len())
+            .find(|end| *end > self.cursor)
+            .unwrap_or(self.text.len());
+        self.move_to(target, select);
+    }
+
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.text.len(), select);
+    }
+
+    // Moves to the closest column in the row above, when wrapped to `width`.
+    pub fn move_up(&mut self, width: u16, select: bool) {
+        let (column, row) = self.cursor_position(width, 0);
+        let target = match row.checked_sub(1) {
+            Some(row) => self.index_at(width, column, row),
+            None => 0,
+        };
+        self.move_to(target, select);
+    }
+
+    // Moves to the closest column in the row below, when wrapped to `width`.
+    pub fn move_down(&mut self, width: u16, select: bool) {
+        let (column, row) = self.cursor_position(width, 0);
+        let target = if (row as usize) + 1 < self.wrap(width, 0).len() {
+            self.index_at(width, column, row + 1)
+        } else {
+            self.text.len()
+        };
+        self.move_to(target, select);
+    }
+
+    // Applies an editing key. Returns false for keys the input doesn't use so
+    // the caller can handle them instead.
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        let select = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        let word = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Char('a') if word => self.select_all(),
+            KeyCode::Char(to_insert) if !word => self.insert_char(to_insert),
+            KeyCode::Enter if self.multi_line => self.insert_char('\n'),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if word => self.move_word_left(select),
+            KeyCode::Right if word => self.move_word_right(select),
+            KeyCode::Left => self.move_left(select),
+            KeyCode::Right => self.move_right(select),
+            KeyCode::Home => self.move_home(select),
+            KeyCode::End => self.move_end(select),
+            _ => return false,
+        }
+        true
+    }
+
+    // Splits the text into rows no wider than `width` columns, preferring to
+    // break after whitespace. The first row starts `offset` columns in, which
+    // leaves room for a label in front of the input.
+    pub fn wrap(&self, width: u16, offset: u16) -> Vec<Range<usize>> {
+        let width = width.max(1) as usize;
+        let mut rows = vec![];
+        let mut row_start = 0;
+        let mut row_width = offset as usize;
+        // Where the row can be broken, and the width of the row up to that point.
+        let mut last_break: Option<(usize, usize)> = None;
+        for (index, grapheme) in self.text.grapheme_indices(true) {
+            if grapheme == "\n" {
+                rows.push(row_start..index);
+                row_start = index + 1;
+                row_width = 0;
+                last_break = None;
+                continue;
+            }
+            let grapheme_width = grapheme.width();
+            if row_width + grapheme_width > width && (index > row_start || row_width > 0) {
+                match last_break {
+                    Some((break_index, break_width)) if break_index > row_start => {
+                        rows.push(row_start..break_index);
+                        row_start = break_index;
+                        row_width -= break_width;
+                    }
+                    _ => {
+                        rows.push(row_start..index);
+                        row_start = index;
+                        row_width = 0;
+                    }
+                }
+                last_break = None;
+            }
+            row_width += grapheme_width;
+            if grapheme.chars().all(char::is_whitespace) {
+                last_break = Some((index + grapheme.len(), row_width));
+            }
+        }
+        rows.push(row_start..self.text.len());
+        rows
+    }
+
+    // The (column, row) of the cursor when wrapped with the same arguments as `wrap`.
+    pub fn cursor_position(&self, width: u16, offset: u16) -> (u16, u16) {
+        let rows = self.wrap(width, offset);
+        let row = rows
+            .iter()
+            .rposition(|row| row.start <= self.cursor)
+            .unwrap_or(0);
+        let mut column = self.text[rows[row].start..self.cursor].width() as u16;
+        if row == 0 {
+            column += offset;
+        }
+        // A full last row leaves the cursor hanging past the edge, so it moves
+        // down to the start of the next row instead.
+        if column >= width.max(1) {
+            return (0, row as u16 + 1);
+        }
+        (column, row as u16)
+    }
+
+    // Builds one line per wrapped row, highlighting the selection. The first
+    // line starts with `prefix`, whose width should match the `offset` used for
+    // wrapping.
+    pub fn lines<'a>(
+        &self,
+        width: u16,
+        prefix: Vec<Span<'a>>,
+        style: Style,
+        selection_style: Style,
+    ) -> Vec<Line<'a>> {
+        let offset = prefix.iter().map(|span| span.width()).sum::<usize>() as u16;
+        let selection = self.selection().unwrap_or(0..0);
+        let mut prefix = Some(prefix);
+        self.wrap(width, offset)
+            .into_iter()
+            .map(|row| {
+                let mut spans = prefix.take().unwrap_or_default();
+                let selected = selection.start.clamp(row.start, row.end)
+                    ..selection.end.clamp(row.start, row.end);
+                for (range, style) in [
+                    (row.start..selected.start, style),
+                    (selected.clone(), selection_style),
+                    (selected.end..row.end, style),
+                ] {
+                    if !range.is_empty() {
+                        spans.push(Span::styled(self.text[range].to_string(), style));
+                    }
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    // The byte offset closest to `column` within a wrapped row.
+    fn index_at(&self, width: u16, column: u16, row: u16) -> usize {
+        let row = self.wrap(width, 0)[row as usize].clone();
+        let mut row_width = 0;
+        for (index, grapheme) in self.text[row.clone()].grapheme_indices(true) {
+            row_width += grapheme.width() as u16;
+            if row_width > column {
+                return row.start + index;
+            }
+        }
+        row.end
+    }
+
+    fn move_to(&mut self, target: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = target;
+    }
+
+    // Removes the selected text. Returns whether anything was selected.
+    fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        self.anchor = None;
+        match selection {
+            Some(range) => {
+                self.cursor = range.start;
+                self.text.replace_range(range, "");
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn previous_boundary(&self, index: usize) -> usize {
+        self.text[..index]
+            .grapheme_indices(true)
+            .next_back()
+            .map(|(start, _)| start)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self, index: usize) -> usize {
+        self.text[index..]
+            .graphemes(true)
+            .next()
+            .map(|grapheme| index + grapheme.len())
+            .unwrap_or(self.text.len())
+    }
+}
+
+// Control characters would break the layout, so they are turned into spaces.
+// Line breaks are kept (as a plain `\n`) if the input is multi-line.
+fn sanitize(text: &str, multi_line: bool) -> String {
+    text.replace("\r\n", "\n")
+        .chars()
+        .map(|c| match c {
+            '\n' if multi_line => '\n',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_multi_byte_editing() {
+        let mut input = TextInput::with_text("héllo wörld");
+        input.move_left(false);
+        input.backspace();
+        assert_eq!(input.text(), "héllo wörd");
+        input.move_home(false);
+        input.move_right(false);
+        input.delete();
+        assert_eq!(input.text(), "hllo wörd");
+
+        // Family emoji is a single grapheme made of several code points.
+        let mut input = TextInput::with_text("a👨‍👩‍👧b");
+        input.move_left(false);
+        input.backspace();
+        assert_eq!(input.text(), "ab");
+    }
+
+    #[test]
+    fn test_word_movement() {
+        let mut input = TextInput::with_text("slow the attacker");
+        input.handle_key_event(key(KeyCode::Left, KeyModifiers::CONTROL));
+        assert_eq!(input.cursor(), 9);
+        input.handle_key_event(key(KeyCode::Left, KeyModifiers::CONTROL));
+        assert_eq!(input.cursor(), 5);
+        input.handle_key_event(key(KeyCode::Right, KeyModifiers::CONTROL));
+        assert_eq!(input.cursor(), 8);
+        input.handle_key_event(key(KeyCode::End, KeyModifiers::NONE));
+        assert_eq!(input.cursor(), 17);
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut input = TextInput::with_text("Haste increased by 30%");
+        input.move_word_left(true);
+        assert_eq!(input.selected_text(), Some("30%"));
+        input.insert_str("25%");
+        assert_eq!(input.text(), "Haste increased by 25%");
+
+        input.handle_key_event(key(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        input.paste("Shocked\nagain");
+        assert_eq!(input.text(), "Shocked again");
+        assert_eq!(input.selection(), None);
+
+        input.move_home(true);
+        input.move_right(false);
+        assert_eq!(input.cursor(), 13);
+    }
+
+    #[test]
+    fn test_multi_line() {
+        let mut input = TextInput::multi_line();
+        input.paste("You feel full!\r\nFortitude");
+        input.handle_key_event(key(KeyCode::Enter, KeyModifiers::NONE));
+        input.insert_str("up");
+        assert_eq!(input.text(), "You feel full!\nFortitude\nup");
+        assert_eq!(input.line_count(), 3);
+        assert_eq!(input.char_count(), 25);
+
+        assert_eq!(input.wrap(10, 0), vec![0..9, 9..14, 15..24, 25..27]);
+        assert_eq!(input.cursor_position(10, 0), (2, 3));
+
+        input.move_up(10, false);
+        assert_eq!(input.cursor(), 17);
+        input.move_up(10, false);
+        input.move_up(10, false);
+        assert_eq!(input.cursor(), 2);
+        input.move_down(10, false);
+        assert_eq!(input.cursor(), 11);
+        input.move_down(10, false);
+        input.move_down(10, false);
+        input.move_down(10, false);
+        assert_eq!(input.cursor(), 27);
+
+        // Single line inputs flatten line breaks.
+        let input = TextInput::with_text("a\nb");
+        assert_eq!(input.text(), "a b");
+    }
+
+    #[test]
+    fn test_wrap_and_cursor_position() {
+        let input = TextInput::with_text("aaa bbb ccc");
+        assert_eq!(input.wrap(8, 0), vec![0..8, 8..11]);
+        assert_eq!(input.cursor_position(8, 0), (3, 1));
+
+        // The label pushes the first row over.
+        assert_eq!(input.wrap(8, 5), vec![0..3, 3..11]);
+        assert_eq!(input.wrap(10, 5), vec![0..4, 4..11]);
+
+        // Words longer than a row are broken mid word.
+        let input = TextInput::with_text("abcdefghij");
+        assert_eq!(input.wrap(4, 0), vec![0..4, 4..8, 8..10]);
+
+        // A full last row moves the cursor to the next one.
+        let input = TextInput::with_text("abcd");
+        assert_eq!(input.cursor_position(4, 0), (0, 1));
+
+        // Wide characters take up two columns.
+        let input = TextInput::with_text("日本語");
+        assert_eq!(input.cursor_position(10, 0), (6, 0));
+    }
+}
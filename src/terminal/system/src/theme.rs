@@ -61,6 +61,16 @@ impl Theme {
         magenta_dark: Color::from_u32(0x372e56),
     };
 
+    // Every theme, by the name project files use for it.
+    pub const ALL: [(&'static str, Theme); 1] = [("amber", Theme::AMBER)];
+
+    pub fn named(name: &str) -> Option<Theme> {
+        Theme::ALL
+            .into_iter()
+            .find(|(theme_name, _)| theme_name.eq_ignore_ascii_case(name))
+            .map(|(_, theme)| theme)
+    }
+
     // Linearly interpolates between two different colors
     pub fn lerp(c1: Color, c2: Color, w: f32) -> Color {
         let (r1, g1, b1) = match c1 {
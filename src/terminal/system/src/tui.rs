@@ -1,7 +1,10 @@
 use std::io::{self, stdout, Stdout};
 
 use crossterm::{
-    event::{KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
     terminal::*,
 };
@@ -15,6 +18,7 @@ pub fn init() -> io::Result<Tui> {
     execute!(
         stdout(),
         EnterAlternateScreen,
+        EnableBracketedPaste,
         PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
     )?;
     enable_raw_mode()?;
@@ -24,7 +28,12 @@ pub fn init() -> io::Result<Tui> {
 
 /// Restore the terminal to its original state
 pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen, PopKeyboardEnhancementFlags)?;
+    execute!(
+        stdout(),
+        LeaveAlternateScreen,
+        DisableBracketedPaste,
+        PopKeyboardEnhancementFlags
+    )?;
     disable_raw_mode()?;
     Ok(())
 }
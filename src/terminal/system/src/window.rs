@@ -1,6 +1,6 @@
 use std::{fmt, io};
 
-use crate::{theme::Theme, tui};
+use crate::{keymap::Keymap, theme::Theme, tui};
 use crossterm::event::KeyEvent;
 use ratatui::widgets::block::*;
 
@@ -16,6 +16,7 @@ pub struct Window {
     pub quit: bool,
     pub change: bool,
     pub theme: Theme,
+    pub keymap: Keymap,
     pub border_type: BorderType,
     pub draw_background: bool,
 }
@@ -33,6 +34,7 @@ impl Default for Window {
             quit: false,
             change: false,
             theme: Theme::AMBER,
+            keymap: Keymap::default(),
             border_type: BorderType::Rounded,
             draw_background: true,
         }